@@ -1,11 +1,28 @@
 use dioxus::prelude::*;
+use std::path::PathBuf;
 
-#[derive(Clone, Debug)]
+use crate::views::format_file_size;
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct FileInfo {
     pub name: String,
+    /// Where the file lives; the desktop renderer hands out real paths
+    pub path: PathBuf,
+    /// `None` when the file could not be inspected
     pub size: Option<u64>,
 }
 
+impl FileInfo {
+    pub fn new(path: PathBuf) -> Self {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string());
+        let size = std::fs::metadata(&path).ok().map(|metadata| metadata.len());
+        Self { name, path, size }
+    }
+}
+
 #[component]
 pub fn AddFile(
     /// Whether to allow multiple file selection
@@ -15,9 +32,13 @@ pub fn AddFile(
     #[props(default = None)]
     accept: Option<String>,
     /// Event handler for when files are selected
+    #[props(default)]
     on_change: EventHandler<Vec<FileInfo>>,
+    /// Event handler for when the selected files are confirmed with the add button
+    on_add: EventHandler<Vec<FileInfo>>,
 ) -> Element {
     let mut selected_files = use_signal(Vec::<FileInfo>::new);
+    let total_size: u64 = selected_files.read().iter().filter_map(|file| file.size).sum();
     
     rsx! {
         div { class: "w-full space-y-4",
@@ -45,13 +66,8 @@ pub fn AddFile(
                         if let Some(file_engine) = evt.files() {
                             let files = file_engine.files();
                             let file_infos: Vec<FileInfo> = files
-                                .iter()
-                                .map(|file_name| {
-                                    FileInfo {
-                                        name: file_name.clone(),
-                                        size: None,
-                                    }
-                                })
+                                .into_iter()
+                                .map(|file_name| FileInfo::new(PathBuf::from(file_name)))
                                 .collect();
                             selected_files.set(file_infos.clone());
                             on_change.call(file_infos);
@@ -69,12 +85,15 @@ pub fn AddFile(
                                 div { class: "flex items-center space-x-2",
                                     span { class: "text-sm", "📄" }
                                     span { class: "text-sm text-gray-700", "{file.name}" }
+                                    if let Some(size) = file.size {
+                                        span { class: "text-xs text-gray-500", "{format_file_size(size)}" }
+                                    }
                                 }
                                 button {
                                     class: "text-red-500 hover:text-red-700 text-sm",
                                     onclick: move |_| {
                                         let mut files = selected_files();
-                                        files.retain(|f| f.name != file.name);
+                                        files.retain(|f| f.path != file.path);
                                         selected_files.set(files.clone());
                                         on_change.call(files);
                                     },
@@ -83,6 +102,15 @@ pub fn AddFile(
                             }
                         }
                     }
+                    button {
+                        class: "mt-3 w-full px-4 py-2 bg-blue-500 text-white rounded-md hover:bg-blue-600 transition-colors",
+                        onclick: move |_| {
+                            on_add.call(selected_files());
+                            selected_files.set(Vec::new());
+                            on_change.call(Vec::new());
+                        },
+                        "Add {selected_files.read().len()} files ({format_file_size(total_size)})"
+                    }
                 }
             }
         }
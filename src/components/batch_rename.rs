@@ -0,0 +1,198 @@
+use dioxus::prelude::*;
+use std::path::PathBuf;
+
+use crate::views::operations::rename::{self, CaseTransform, RenamePattern};
+use crate::views::operations::{JobQueue, JournalEntry};
+
+/// Dialog for renaming several files at once from a pattern, with a preview of
+/// every new name. Applied renames go into the undo history.
+#[component]
+pub fn BatchRename(
+    /// Files to rename, in the order the counter numbers them
+    paths: Vec<PathBuf>,
+    /// Called when the dialog is dismissed or the renames were applied
+    on_close: EventHandler<()>,
+) -> Element {
+    let jobs = use_context::<JobQueue>();
+    let mut pattern = use_signal(RenamePattern::default);
+    let mut apply_error = use_signal(|| None::<String>);
+
+    let preview = use_memo({
+        let paths = paths.clone();
+        move || rename::plan(&paths, &pattern.read())
+    });
+    let (changes, problems) = match &*preview.read() {
+        Ok(planned) => (
+            planned.iter().filter(|rename| rename.is_change()).count(),
+            planned.iter().filter(|rename| rename.problem.is_some()).count(),
+        ),
+        Err(_) => (0, 0),
+    };
+    let can_apply = preview.read().is_ok() && changes > 0 && problems == 0;
+
+    let apply = move |_| {
+        let Ok(planned) = &*preview.read() else {
+            return;
+        };
+        let renames: Vec<(PathBuf, PathBuf)> = planned
+            .iter()
+            .map(|rename| (rename.from.clone(), rename.to.clone()))
+            .collect();
+        let count = renames.iter().filter(|(from, to)| from != to).count();
+        match rename::apply(&renames) {
+            Ok(operations) => {
+                jobs.record(JournalEntry {
+                    description: format!("Rename {count} items"),
+                    operations,
+                });
+                on_close.call(());
+            }
+            Err(err) => apply_error.set(Some(format!("Nothing was renamed: {err}"))),
+        }
+    };
+
+    rsx! {
+        div { class: "fixed inset-0 z-40 flex items-center justify-center bg-black/30",
+            div { class: "bg-white rounded-lg shadow-xl w-full max-w-3xl max-h-[90vh] flex flex-col",
+                div { class: "px-6 py-4 border-b",
+                    h2 { class: "text-lg font-semibold text-gray-900", "Rename {paths.len()} items" }
+                    p { class: "text-xs text-gray-500 mt-1",
+                        "Tokens: {{n}} {{n:3}} counter · {{name}} {{ext}} original name · {{date}} {{date:%Y%m%d}} modified date · $1 regex groups"
+                    }
+                }
+                div { class: "px-6 py-4 grid grid-cols-2 gap-3 text-sm",
+                    label { class: "flex flex-col gap-1",
+                        span { class: "text-gray-700", "Find (empty to replace the whole name)" }
+                        input {
+                            class: "px-2 py-1 border border-gray-300 rounded-md font-mono",
+                            value: "{pattern.read().find}",
+                            oninput: move |evt| pattern.write().find = evt.value(),
+                        }
+                    }
+                    label { class: "flex flex-col gap-1",
+                        span { class: "text-gray-700", "Replace with" }
+                        input {
+                            class: "px-2 py-1 border border-gray-300 rounded-md font-mono",
+                            value: "{pattern.read().replace}",
+                            oninput: move |evt| pattern.write().replace = evt.value(),
+                        }
+                    }
+                    div { class: "flex items-center gap-4",
+                        label { class: "flex items-center gap-1 text-gray-700",
+                            input {
+                                r#type: "checkbox",
+                                checked: pattern.read().regex,
+                                onchange: move |evt| pattern.write().regex = evt.checked(),
+                            }
+                            "Regular expression"
+                        }
+                        select {
+                            class: "px-2 py-1 border border-gray-300 rounded-md",
+                            onchange: move |evt| {
+                                if let Some(case) = CaseTransform::ALL.into_iter().find(|case| case.label() == evt.value()) {
+                                    pattern.write().case = case;
+                                }
+                            },
+                            for case in CaseTransform::ALL {
+                                option {
+                                    value: case.label(),
+                                    selected: pattern.read().case == case,
+                                    "{case.label()}"
+                                }
+                            }
+                        }
+                    }
+                    div { class: "flex items-center gap-2 text-gray-700",
+                        "Counter from"
+                        input {
+                            class: "w-20 px-2 py-1 border border-gray-300 rounded-md",
+                            r#type: "number",
+                            min: 0,
+                            value: "{pattern.read().counter_start}",
+                            oninput: move |evt| {
+                                if let Ok(start) = evt.value().parse() {
+                                    pattern.write().counter_start = start;
+                                }
+                            },
+                        }
+                        "step"
+                        input {
+                            class: "w-16 px-2 py-1 border border-gray-300 rounded-md",
+                            r#type: "number",
+                            min: 1,
+                            value: "{pattern.read().counter_step}",
+                            oninput: move |evt| {
+                                if let Ok(step) = evt.value().parse() {
+                                    pattern.write().counter_step = step;
+                                }
+                            },
+                        }
+                    }
+                }
+                div { class: "flex-1 overflow-y-auto border-t border-b",
+                    match &*preview.read() {
+                        Err(err) => rsx! {
+                            pre { class: "p-4 text-sm text-red-600 whitespace-pre-wrap", "{err}" }
+                        },
+                        Ok(planned) => rsx! {
+                            table { class: "w-full text-sm",
+                                thead { class: "bg-gray-50 sticky top-0",
+                                    tr {
+                                        th { class: "px-4 py-2 text-left font-medium text-gray-500", "Current name" }
+                                        th { class: "px-4 py-2 text-left font-medium text-gray-500", "New name" }
+                                    }
+                                }
+                                tbody { class: "divide-y",
+                                    for row in planned.iter() {
+                                        tr { key: "{row.from.display()}",
+                                            td { class: "px-4 py-1 text-gray-700 break-all",
+                                                "{row.from.file_name().unwrap_or_default().to_string_lossy()}"
+                                            }
+                                            td { class: "px-4 py-1 break-all",
+                                                if let Some(problem) = &row.problem {
+                                                    span { class: "text-red-600",
+                                                        "{row.to.file_name().unwrap_or_default().to_string_lossy()} — {problem}"
+                                                    }
+                                                } else if row.is_change() {
+                                                    span { class: "text-gray-900",
+                                                        "→ {row.to.file_name().unwrap_or_default().to_string_lossy()}"
+                                                    }
+                                                } else {
+                                                    span { class: "text-gray-400", "unchanged" }
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        },
+                    }
+                }
+                div { class: "px-6 py-4 flex items-center justify-between gap-4",
+                    span { class: "text-sm",
+                        if let Some(err) = apply_error() {
+                            span { class: "text-red-600", "{err}" }
+                        } else if problems > 0 {
+                            span { class: "text-red-600", "{problems} names need fixing before renaming" }
+                        } else {
+                            span { class: "text-gray-500", "{changes} items will be renamed" }
+                        }
+                    }
+                    div { class: "flex gap-2",
+                        button {
+                            class: "px-3 py-1 rounded-md border bg-white text-gray-700 hover:bg-gray-50 transition-colors",
+                            onclick: move |_| on_close.call(()),
+                            "Cancel"
+                        }
+                        button {
+                            class: "px-3 py-1 rounded-md bg-blue-500 text-white hover:bg-blue-600 transition-colors disabled:opacity-50 disabled:cursor-not-allowed",
+                            disabled: !can_apply,
+                            onclick: apply,
+                            "Rename"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use dioxus::prelude::*;
+
+use crate::views::commands::{fuzzy_score, Command, Commands};
+use crate::views::keybindings::Keybindings;
+
+/// Ctrl+Shift+P palette listing the commands that apply to the active pane,
+/// best fuzzy match first.
+#[component]
+pub fn CommandPalette(on_close: EventHandler<()>) -> Element {
+    let commands = use_context::<Commands>();
+    let keymap = use_context::<Keybindings>().keymap;
+    let mut query = use_signal(String::new);
+    let mut highlighted = use_signal(|| 0usize);
+
+    let state = commands.state.cloned();
+    let mut matches: Vec<(Command, i32)> = Command::ALL
+        .into_iter()
+        .filter(|command| command.in_palette() && command.is_available(&state))
+        .filter_map(|command| Some((command, fuzzy_score(&query.read(), command.label())?)))
+        .collect();
+    // Stable, so equal scores keep the order of Command::ALL
+    matches.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+    let matches: Vec<Command> = matches.into_iter().map(|(command, _)| command).collect();
+    let count = matches.len();
+    let chosen = matches.get(highlighted().min(count.saturating_sub(1))).copied();
+
+    let run = move |command: Command| {
+        on_close.call(());
+        commands.run(command);
+    };
+
+    rsx! {
+        div {
+            class: "fixed inset-0 z-40 flex items-start justify-center pt-24 bg-black/20",
+            onclick: move |_| on_close.call(()),
+            div {
+                class: "w-full max-w-xl bg-white rounded-lg shadow-xl border overflow-hidden",
+                onclick: move |evt| evt.stop_propagation(),
+                input {
+                    class: "w-full px-4 py-3 text-lg border-b focus:outline-none",
+                    placeholder: "Type a command",
+                    spellcheck: false,
+                    value: "{query}",
+                    onmounted: move |evt| async move {
+                        let _ = evt.set_focus(true).await;
+                    },
+                    oninput: move |evt| {
+                        query.set(evt.value());
+                        highlighted.set(0);
+                    },
+                    onkeydown: move |evt| {
+                        match evt.key() {
+                            Key::ArrowDown if count > 0 => highlighted.set((highlighted() + 1) % count),
+                            Key::ArrowUp if count > 0 => highlighted.set((highlighted() + count - 1) % count),
+                            Key::Enter => {
+                                if let Some(command) = chosen {
+                                    run(command);
+                                }
+                            }
+                            Key::Escape => on_close.call(()),
+                            _ => return,
+                        }
+                        evt.prevent_default();
+                        evt.stop_propagation();
+                    },
+                }
+                p { class: "px-4 pt-2 text-xs text-gray-500",
+                    match state.selected {
+                        0 => "Nothing selected".to_string(),
+                        1 => "1 item selected".to_string(),
+                        selected => format!("{selected} items selected"),
+                    }
+                }
+                if matches.is_empty() {
+                    p { class: "px-4 py-3 text-sm text-gray-500", "No matching command." }
+                }
+                ul { class: "py-1 max-h-96 overflow-y-auto",
+                    for (index, command) in matches.into_iter().enumerate() {
+                        li {
+                            key: "{command.label()}",
+                            class: if Some(command) == chosen { "flex items-center justify-between px-4 py-2 cursor-pointer bg-blue-50" } else { "flex items-center justify-between px-4 py-2 cursor-pointer hover:bg-gray-50" },
+                            onmouseenter: move |_| highlighted.set(index),
+                            onclick: move |_| run(command),
+                            span { class: "text-gray-900", "{command.label()}" }
+                            if let Some(shortcut) = keymap.read().shortcut(command) {
+                                kbd { class: "px-1.5 py-0.5 text-xs text-gray-600 bg-gray-100 border rounded", "{shortcut}" }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
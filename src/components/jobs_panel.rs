@@ -0,0 +1,128 @@
+use dioxus::prelude::*;
+
+use crate::views::format_file_size;
+use crate::views::operations::{ConflictChoice, JobQueue, JobState};
+
+/// Floating panel listing background file operations with their progress.
+#[component]
+pub fn JobsPanel() -> Element {
+    let queue = use_context::<JobQueue>();
+    let jobs = queue.jobs();
+    let mut apply_to_all = use_signal(|| false);
+
+    if jobs.read().is_empty() {
+        return rsx! {};
+    }
+
+    let has_finished = jobs.read().iter().any(|job| job.state.is_finished());
+
+    rsx! {
+        div { class: "fixed bottom-4 right-4 z-30 w-96 bg-white border border-gray-200 rounded-lg shadow-lg",
+            div { class: "flex items-center justify-between px-4 py-2 border-b",
+                h3 { class: "text-sm font-semibold text-gray-900", "Jobs" }
+                if has_finished {
+                    button {
+                        class: "text-xs text-gray-500 hover:text-gray-800",
+                        onclick: {
+                            let queue = queue.clone();
+                            move |_| queue.clear_finished()
+                        },
+                        "Clear finished"
+                    }
+                }
+            }
+            div { class: "max-h-96 overflow-y-auto divide-y",
+                for job in jobs.read().iter().cloned() {
+                    div { key: "{job.id}", class: "px-4 py-3 space-y-2",
+                        div { class: "flex items-start justify-between gap-2",
+                            p { class: "text-sm text-gray-800 break-all", "{job.description}" }
+                            if !job.state.is_finished() {
+                                button {
+                                    class: "text-xs text-red-500 hover:text-red-700 shrink-0",
+                                    onclick: {
+                                        let queue = queue.clone();
+                                        move |_| queue.cancel(job.id)
+                                    },
+                                    "Cancel"
+                                }
+                            }
+                        }
+                        div { class: "w-full bg-gray-200 rounded-full h-1.5",
+                            div {
+                                class: "bg-blue-500 h-1.5 rounded-full transition-all",
+                                style: "width: {job.progress.percent()}%",
+                            }
+                        }
+                        p { class: "text-xs text-gray-500",
+                            "{job.progress.files_done} / {job.progress.files_total} files · "
+                            "{format_file_size(job.progress.bytes_done)} / {format_file_size(job.progress.bytes_total)}"
+                        }
+                        match &job.state {
+                            JobState::Queued => rsx! {
+                                p { class: "text-xs text-gray-500", "Waiting…" }
+                            },
+                            JobState::Running => rsx! {
+                                if let Some(current) = &job.progress.current {
+                                    p { class: "text-xs text-gray-400 truncate", "{current.display()}" }
+                                }
+                            },
+                            JobState::AwaitingAnswer(conflict) => rsx! {
+                                div { class: "bg-yellow-50 border border-yellow-200 rounded-md p-2 space-y-2",
+                                    p { class: "text-xs text-yellow-800 break-all",
+                                        "\"{conflict.target.display()}\" already exists."
+                                    }
+                                    div { class: "flex flex-wrap gap-1",
+                                        for (label, choice) in conflict_choices(conflict.both_dirs) {
+                                            button {
+                                                class: "px-2 py-1 text-xs rounded border bg-white hover:bg-gray-50",
+                                                onclick: {
+                                                    let queue = queue.clone();
+                                                    move |_| queue.answer(job.id, choice, apply_to_all())
+                                                },
+                                                "{label}"
+                                            }
+                                        }
+                                    }
+                                    label { class: "flex items-center gap-1 text-xs text-gray-600",
+                                        input {
+                                            r#type: "checkbox",
+                                            checked: apply_to_all(),
+                                            onchange: move |evt| apply_to_all.set(evt.checked()),
+                                        }
+                                        "Apply to all conflicts of this job"
+                                    }
+                                }
+                            },
+                            JobState::Done(errors) if errors.is_empty() => rsx! {
+                                p { class: "text-xs text-green-600", "Done" }
+                            },
+                            JobState::Done(errors) => rsx! {
+                                details { class: "text-xs text-red-600",
+                                    summary { "Finished with {errors.len()} errors" }
+                                    for error in errors.iter() {
+                                        p { class: "break-all", "{error}" }
+                                    }
+                                }
+                            },
+                            JobState::Cancelled => rsx! {
+                                p { class: "text-xs text-gray-500", "Cancelled" }
+                            },
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn conflict_choices(both_dirs: bool) -> Vec<(&'static str, ConflictChoice)> {
+    let mut choices = vec![
+        ("Overwrite", ConflictChoice::Overwrite),
+        ("Skip", ConflictChoice::Skip),
+        ("Keep both", ConflictChoice::Rename),
+    ];
+    if both_dirs {
+        choices.push(("Merge", ConflictChoice::Merge));
+    }
+    choices
+}
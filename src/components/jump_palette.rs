@@ -0,0 +1,94 @@
+use dioxus::prelude::*;
+use std::path::PathBuf;
+
+use crate::views::frecency::Visits;
+
+/// Number of folders the palette lists.
+const MAX_RESULTS: usize = 10;
+
+/// "Jump to" dialog: typing fragments of a previously visited folder's path
+/// lists the best matches by frecency, and Enter opens the first one.
+#[component]
+pub fn JumpPalette(
+    /// Folder being shown, which is never offered
+    current: PathBuf,
+    on_jump: EventHandler<PathBuf>,
+    on_close: EventHandler<()>,
+) -> Element {
+    let visits = use_context::<Visits>();
+    let mut query = use_signal(String::new);
+    let mut highlighted = use_signal(|| 0usize);
+
+    let results = visits.query(&query.read(), &current, MAX_RESULTS);
+    let count = results.len();
+    let chosen = results.get(highlighted().min(count.saturating_sub(1))).cloned();
+
+    rsx! {
+        div {
+            class: "fixed inset-0 z-40 flex items-start justify-center pt-24 bg-black/20",
+            onclick: move |_| on_close.call(()),
+            div {
+                class: "w-full max-w-xl bg-white rounded-lg shadow-xl border overflow-hidden",
+                onclick: move |evt| evt.stop_propagation(),
+                input {
+                    class: "w-full px-4 py-3 text-lg border-b focus:outline-none",
+                    placeholder: "Jump to a visited folder, e.g. \"proj api\"",
+                    spellcheck: false,
+                    value: "{query}",
+                    onmounted: move |evt| async move {
+                        let _ = evt.set_focus(true).await;
+                    },
+                    oninput: move |evt| {
+                        query.set(evt.value());
+                        highlighted.set(0);
+                    },
+                    onkeydown: move |evt| {
+                        match evt.key() {
+                            Key::ArrowDown if count > 0 => highlighted.set((highlighted() + 1) % count),
+                            Key::ArrowUp if count > 0 => highlighted.set((highlighted() + count - 1) % count),
+                            Key::Enter => {
+                                if let Some(path) = chosen.clone() {
+                                    on_jump.call(path);
+                                }
+                            }
+                            Key::Escape => on_close.call(()),
+                            _ => return,
+                        }
+                        evt.prevent_default();
+                        evt.stop_propagation();
+                    },
+                }
+                if results.is_empty() {
+                    p { class: "px-4 py-3 text-sm text-gray-500",
+                        if query.read().trim().is_empty() {
+                            "Folders you open show up here."
+                        } else {
+                            "No visited folder matches."
+                        }
+                    }
+                } else {
+                    if query.read().trim().is_empty() {
+                        h3 { class: "px-4 pt-2 text-xs font-medium text-gray-500 uppercase tracking-wider", "Recent" }
+                    }
+                    ul { class: "py-1",
+                        for (index, path) in results.into_iter().enumerate() {
+                            li {
+                                key: "{path.display()}",
+                                class: if Some(&path) == chosen.as_ref() { "px-4 py-2 cursor-pointer bg-blue-50" } else { "px-4 py-2 cursor-pointer hover:bg-gray-50" },
+                                onmouseenter: move |_| highlighted.set(index),
+                                onclick: {
+                                    let path = path.clone();
+                                    move |_| on_jump.call(path.clone())
+                                },
+                                div { class: "font-medium text-gray-900 truncate",
+                                    "📁 {path.file_name().unwrap_or(path.as_os_str()).to_string_lossy()}"
+                                }
+                                div { class: "text-xs text-gray-500 truncate", "{path.display()}" }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
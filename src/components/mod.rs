@@ -1,5 +1,32 @@
 mod search;
 pub use search::Search;
 
+mod search_results;
+pub use search_results::SearchResults;
+
+mod jobs_panel;
+pub use jobs_panel::JobsPanel;
+
 mod add_file;
-pub use add_file::AddFile;
\ No newline at end of file
+pub use add_file::{AddFile, FileInfo};
+
+mod batch_rename;
+pub use batch_rename::BatchRename;
+
+mod preview_pane;
+pub use preview_pane::PreviewPane;
+
+mod tab_bar;
+pub use tab_bar::TabBar;
+
+mod path_bar;
+pub use path_bar::PathBar;
+
+mod places_sidebar;
+pub use places_sidebar::PlacesSidebar;
+
+mod jump_palette;
+pub use jump_palette::JumpPalette;
+
+mod command_palette;
+pub use command_palette::CommandPalette;
\ No newline at end of file
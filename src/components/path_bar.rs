@@ -0,0 +1,204 @@
+use dioxus::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::views::path_input;
+
+/// Breadcrumb of the current location. Each segment opens that folder, and
+/// the arrow after it lists the folders inside it to jump sideways. Clicking
+/// the empty space switches to typing a path instead, with Tab completing
+/// folder names.
+#[component]
+pub fn PathBar(path: PathBuf, on_navigate: EventHandler<PathBuf>) -> Element {
+    let mut editing = use_signal(|| false);
+    let mut text = use_signal(String::new);
+    let mut error = use_signal(|| None::<String>);
+    // Completions offered after a Tab that matched more than one folder
+    let mut candidates = use_signal(Vec::<String>::new);
+    // Segment whose folders are listed, and those folders
+    let mut open_at = use_signal(|| None::<PathBuf>);
+    let mut folders = use_signal(Vec::<PathBuf>::new);
+
+    let mut toggle_folders = move |segment: PathBuf| {
+        if open_at.peek().as_ref() == Some(&segment) {
+            open_at.set(None);
+        } else {
+            folders.set(subdirectories(&segment));
+            open_at.set(Some(segment));
+        }
+    };
+
+    let mut segments: Vec<PathBuf> = path.ancestors().map(Path::to_path_buf).collect();
+    segments.reverse();
+
+    let mut start_editing = {
+        let path = path.clone();
+        move || {
+            open_at.set(None);
+            error.set(None);
+            candidates.set(Vec::new());
+            text.set(path.display().to_string());
+            editing.set(true);
+        }
+    };
+
+    if editing() {
+        let base = path.clone();
+        let mut submit = move || match path_input::resolve(&text.peek(), &base) {
+            Ok(target) => {
+                editing.set(false);
+                on_navigate.call(target);
+            }
+            Err(err) => error.set(Some(err)),
+        };
+        let base = path.clone();
+        let mut complete = move || {
+            let found = path_input::complete(&text.peek(), &base);
+            match found.as_slice() {
+                [] => error.set(Some(format!("No folder matches \"{}\"", text.peek()))),
+                [single] => {
+                    text.set(single.clone());
+                    candidates.set(Vec::new());
+                }
+                _ => {
+                    text.set(path_input::common_prefix(&found));
+                    candidates.set(found);
+                }
+            }
+        };
+
+        return rsx! {
+            div { class: "relative",
+                input {
+                    class: if error.read().is_some() { "w-full px-3 py-2 border border-red-400 rounded-md focus:outline-none focus:ring-2 focus:ring-red-400 focus:border-transparent" } else { "w-full px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500 focus:border-transparent" },
+                    r#type: "text",
+                    spellcheck: false,
+                    value: "{text}",
+                    onmounted: move |evt| async move {
+                        let _ = evt.set_focus(true).await;
+                    },
+                    oninput: move |evt| {
+                        text.set(evt.value());
+                        error.set(None);
+                        candidates.set(Vec::new());
+                    },
+                    onkeydown: move |evt| match evt.key() {
+                        Key::Enter => submit(),
+                        Key::Tab if !evt.modifiers().shift() => {
+                            evt.prevent_default();
+                            complete();
+                        }
+                        Key::Escape => editing.set(false),
+                        _ => {}
+                    },
+                    onblur: move |_| editing.set(false),
+                }
+                if let Some(err) = error() {
+                    p { class: "mt-1 text-sm text-red-600 break-all", "{err}" }
+                }
+                if !candidates.read().is_empty() {
+                    div { class: "absolute left-0 right-0 top-full z-20 mt-1 max-h-80 overflow-y-auto bg-white border border-gray-200 rounded-md shadow-lg py-1",
+                        for candidate in candidates() {
+                            button {
+                                key: "{candidate}",
+                                class: "block w-full px-3 py-1 text-left text-sm truncate text-gray-700 hover:bg-gray-100",
+                                // Keeps the focus in the input, which closes the editor on blur
+                                onmousedown: move |evt| evt.prevent_default(),
+                                onclick: {
+                                    let candidate = candidate.clone();
+                                    move |_| {
+                                        text.set(candidate.clone());
+                                        candidates.set(Vec::new());
+                                    }
+                                },
+                                "{candidate}"
+                            }
+                        }
+                    }
+                }
+            }
+        };
+    }
+
+    rsx! {
+        div {
+            class: "relative flex items-center min-h-[42px] px-2 border border-gray-300 rounded-md bg-white cursor-text",
+            title: "Click to type a path",
+            onclick: move |_| start_editing(),
+            for segment in segments {
+                div { key: "{segment.display()}", class: "relative flex items-center",
+                    button {
+                        class: if segment == path { "px-1.5 py-1 rounded text-gray-900 font-medium hover:bg-gray-100" } else { "px-1.5 py-1 rounded text-blue-600 hover:bg-gray-100" },
+                        onclick: {
+                            let segment = segment.clone();
+                            move |evt: MouseEvent| {
+                                evt.stop_propagation();
+                                open_at.set(None);
+                                on_navigate.call(segment.clone());
+                            }
+                        },
+                        {segment_label(&segment)}
+                    }
+                    button {
+                        class: "px-1 py-1 rounded text-gray-400 hover:text-gray-900 hover:bg-gray-100",
+                        title: "Folders in here",
+                        onclick: {
+                            let segment = segment.clone();
+                            move |evt: MouseEvent| {
+                                evt.stop_propagation();
+                                toggle_folders(segment.clone());
+                            }
+                        },
+                        "›"
+                    }
+                    if open_at.read().as_ref() == Some(&segment) {
+                        div {
+                            class: "absolute left-0 top-full z-20 mt-1 w-64 max-h-80 overflow-y-auto bg-white border border-gray-200 rounded-md shadow-lg py-1 cursor-default",
+                            onclick: move |evt| evt.stop_propagation(),
+                            if folders.read().is_empty() {
+                                div { class: "px-3 py-1 text-sm text-gray-500", "No folders" }
+                            }
+                            for folder in folders() {
+                                button {
+                                    key: "{folder.display()}",
+                                    // The folder the current path goes through
+                                    class: if path.starts_with(&folder) { "block w-full px-3 py-1 text-left text-sm truncate bg-blue-50 text-blue-700" } else { "block w-full px-3 py-1 text-left text-sm truncate text-gray-700 hover:bg-gray-100" },
+                                    onclick: {
+                                        let folder = folder.clone();
+                                        move |_| {
+                                            open_at.set(None);
+                                            on_navigate.call(folder.clone());
+                                        }
+                                    },
+                                    "📁 {segment_label(&folder)}"
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            div { class: "flex-1" }
+        }
+    }
+}
+
+fn segment_label(path: &Path) -> String {
+    match path.file_name() {
+        Some(name) => name.to_string_lossy().into_owned(),
+        None => path.display().to_string(),
+    }
+}
+
+/// Folders directly inside `path`, sorted by name.
+fn subdirectories(path: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(path) else {
+        return Vec::new();
+    };
+    let mut folders: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.path())
+        .collect();
+    folders.sort_by_key(|folder| segment_label(folder).to_lowercase());
+    folders
+}
@@ -0,0 +1,157 @@
+use dioxus::prelude::*;
+use std::path::PathBuf;
+
+use crate::views::panes::Panes;
+use crate::views::places::{mounted_places, standard_places, Bookmarks, Place};
+
+/// Sidebar of FileManager with standard folders, mounted file systems and
+/// bookmarks. Places open in the active pane; folders dragged from a listing
+/// and dropped here become bookmarks.
+#[component]
+pub fn PlacesSidebar() -> Element {
+    let panes = use_context::<Panes>();
+    let bookmarks = use_context::<Bookmarks>();
+    let places = use_hook(standard_places);
+    let mounts = use_hook(mounted_places);
+    let mut dragged_folder = bookmarks.dragged;
+    // Bookmark being dragged to a new position
+    let mut dragging = use_signal(|| None::<usize>);
+    let mut drop_target = use_signal(|| false);
+    // Bookmark whose name is being edited, and the edited text
+    let mut renaming = use_signal(|| None::<usize>);
+    let mut rename_text = use_signal(String::new);
+
+    let current = panes.path(panes.active.cloned()).cloned();
+    let open = move |path: PathBuf| {
+        let mut target = panes.path(*panes.active.peek());
+        target.set(path);
+    };
+    let mut commit_rename = move || {
+        if let Some(index) = renaming.take() {
+            bookmarks.rename(index, &rename_text.peek());
+        }
+    };
+
+    rsx! {
+        nav {
+            class: if drop_target() {
+                "w-56 shrink-0 bg-white rounded-lg shadow-sm border-2 border-dashed border-blue-400 p-2 text-sm"
+            } else {
+                "w-56 shrink-0 bg-white rounded-lg shadow-sm border p-2 text-sm"
+            },
+            ondragover: move |evt| {
+                if dragged_folder.peek().is_some() {
+                    evt.prevent_default();
+                    drop_target.set(true);
+                }
+            },
+            ondragleave: move |_| drop_target.set(false),
+            ondrop: move |evt| {
+                drop_target.set(false);
+                if let Some(path) = dragged_folder.take() {
+                    evt.prevent_default();
+                    bookmarks.add(path);
+                }
+            },
+            PlaceSection { title: "Places", places, current: current.clone(), on_open: open }
+            PlaceSection { title: "Devices", places: mounts, current: current.clone(), on_open: open }
+
+            div { class: "flex items-center justify-between px-2 mt-3 mb-1",
+                h3 { class: "text-xs font-medium text-gray-500 uppercase tracking-wider", "Bookmarks" }
+                button {
+                    class: "px-1 text-gray-400 hover:text-gray-900",
+                    title: "Bookmark the current folder",
+                    onclick: move |_| bookmarks.add(panes.path(*panes.active.peek()).cloned()),
+                    "+"
+                }
+            }
+            if bookmarks.list().read().is_empty() {
+                p { class: "px-2 py-1 text-gray-400", "Drag a folder here to bookmark it" }
+            }
+            for (index, bookmark) in bookmarks.list().cloned().into_iter().enumerate() {
+                div {
+                    key: "{bookmark.path.display()}",
+                    class: if bookmark.path == current { "group flex items-center gap-1 px-2 py-1 rounded-md bg-blue-50 text-blue-700" } else { "group flex items-center gap-1 px-2 py-1 rounded-md text-gray-700 hover:bg-gray-100" },
+                    title: "{bookmark.path.display()}",
+                    draggable: renaming() != Some(index),
+                    ondragstart: move |_| dragging.set(Some(index)),
+                    ondragend: move |_| dragging.set(None),
+                    ondragover: move |evt| {
+                        if dragging().is_some() {
+                            evt.prevent_default();
+                        }
+                    },
+                    ondrop: move |evt| {
+                        if let Some(from) = dragging.take() {
+                            evt.prevent_default();
+                            evt.stop_propagation();
+                            bookmarks.reorder(from, index);
+                        }
+                    },
+                    if renaming() == Some(index) {
+                        input {
+                            class: "flex-1 min-w-0 px-1 border border-blue-400 rounded focus:outline-none",
+                            value: "{rename_text}",
+                            onmounted: move |evt| async move {
+                                let _ = evt.set_focus(true).await;
+                            },
+                            oninput: move |evt| rename_text.set(evt.value()),
+                            onkeydown: move |evt| match evt.key() {
+                                Key::Enter => commit_rename(),
+                                Key::Escape => renaming.set(None),
+                                _ => {}
+                            },
+                            onblur: move |_| commit_rename(),
+                        }
+                    } else {
+                        button {
+                            class: "flex-1 min-w-0 text-left truncate",
+                            onclick: {
+                                let path = bookmark.path.clone();
+                                move |_| open(path.clone())
+                            },
+                            if bookmark.path.is_dir() { "⭐ {bookmark.name}" } else { "⚠ {bookmark.name}" }
+                        }
+                        button {
+                            class: "px-1 text-gray-400 hover:text-gray-900 opacity-0 group-hover:opacity-100",
+                            title: "Rename",
+                            onclick: {
+                                let name = bookmark.name.clone();
+                                move |_| {
+                                    rename_text.set(name.clone());
+                                    renaming.set(Some(index));
+                                }
+                            },
+                            "✎"
+                        }
+                        button {
+                            class: "px-1 text-gray-400 hover:text-red-600 opacity-0 group-hover:opacity-100",
+                            title: "Remove bookmark",
+                            onclick: move |_| bookmarks.remove(index),
+                            "✕"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn PlaceSection(title: &'static str, places: Vec<Place>, current: PathBuf, on_open: EventHandler<PathBuf>) -> Element {
+    rsx! {
+        h3 { class: "px-2 mt-3 first:mt-0 mb-1 text-xs font-medium text-gray-500 uppercase tracking-wider", "{title}" }
+        for place in places {
+            button {
+                key: "{place.path.display()}",
+                class: if place.path == current { "block w-full px-2 py-1 rounded-md text-left truncate bg-blue-50 text-blue-700" } else { "block w-full px-2 py-1 rounded-md text-left truncate text-gray-700 hover:bg-gray-100" },
+                title: "{place.path.display()}",
+                onclick: {
+                    let path = place.path.clone();
+                    move |_| on_open.call(path.clone())
+                },
+                "{place.icon} {place.name}"
+            }
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use dioxus::prelude::*;
+use futures_channel::oneshot;
+use std::path::PathBuf;
+
+use crate::views::format_file_size;
+use crate::views::preview::{self, Preview, PreviewContent};
+
+/// Side pane showing the contents of one file. Files are read on a background
+/// thread so large ones don't stall the listing.
+#[component]
+pub fn PreviewPane(
+    /// File to show; `None` shows a hint instead
+    path: Option<PathBuf>,
+    on_close: EventHandler<()>,
+) -> Element {
+    let mut loaded = use_signal(|| None::<(PathBuf, Result<Preview, String>)>);
+    let mut loading = use_signal(|| None::<PathBuf>);
+
+    use_effect(use_reactive((&path,), move |(path,)| {
+        loaded.set(None);
+        loading.set(path.clone());
+        let Some(path) = path else {
+            return;
+        };
+        let (tx, rx) = oneshot::channel();
+        std::thread::spawn({
+            let path = path.clone();
+            move || {
+                let _ = tx.send(preview::load(&path).map_err(|err| err.to_string()));
+            }
+        });
+        spawn(async move {
+            let Ok(result) = rx.await else {
+                return;
+            };
+            // Another file may have been picked in the meantime
+            if loading.peek().as_ref() == Some(&path) {
+                loaded.set(Some((path, result)));
+                loading.set(None);
+            }
+        });
+    }));
+
+    let title = path
+        .as_ref()
+        .and_then(|path| path.file_name())
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "Preview".to_string());
+
+    rsx! {
+        div { class: "w-[28rem] shrink-0 bg-white rounded-lg shadow-sm border overflow-hidden flex flex-col max-h-[80vh]",
+            div { class: "flex items-center justify-between gap-2 px-4 py-2 border-b bg-gray-50",
+                h3 { class: "text-sm font-semibold text-gray-900 truncate", "{title}" }
+                button {
+                    class: "text-gray-500 hover:text-gray-900",
+                    onclick: move |_| on_close.call(()),
+                    "✕"
+                }
+            }
+            div { class: "flex-1 overflow-auto",
+                match (&path, &*loaded.read()) {
+                    (None, _) => rsx! {
+                        p { class: "p-6 text-center text-sm text-gray-500", "Select a file to preview it" }
+                    },
+                    (Some(_), None) => rsx! {
+                        div { class: "p-6 text-center",
+                            div { class: "animate-spin rounded-full h-6 w-6 border-b-2 border-blue-500 mx-auto" }
+                        }
+                    },
+                    (Some(_), Some((_, Err(err)))) => rsx! {
+                        p { class: "p-4 text-sm text-red-600 break-all", "Could not read the file: {err}" }
+                    },
+                    (Some(_), Some((_, Ok(preview)))) => rsx! {
+                        PreviewBody { preview: preview.clone() }
+                    },
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn PreviewBody(preview: Preview) -> Element {
+    let details = match &preview.content {
+        PreviewContent::Code { syntax, .. } => format!("{} · {syntax}", format_file_size(preview.size)),
+        PreviewContent::Image { width, height, .. } => {
+            format!("{} · {width} × {height} px", format_file_size(preview.size))
+        }
+        PreviewContent::Markdown { .. } => format!("{} · Markdown", format_file_size(preview.size)),
+        PreviewContent::Hex(_) => format!("{} · binary", format_file_size(preview.size)),
+        PreviewContent::Empty => "Empty file".to_string(),
+    };
+
+    rsx! {
+        div { class: "px-4 py-2 text-xs text-gray-500 border-b space-y-1",
+            p { "{details}" }
+            if preview.truncated {
+                p { class: "text-amber-700", "Only the beginning of the file is shown" }
+            }
+            if let Some(note) = &preview.note {
+                p { class: "text-amber-700 break-all", "{note}" }
+            }
+        }
+        match &preview.content {
+            PreviewContent::Code { html, .. } => rsx! {
+                div {
+                    class: "text-xs font-mono [&_pre]:p-4 [&_pre]:whitespace-pre [&_pre]:min-w-max",
+                    dangerous_inner_html: "{html}",
+                }
+            },
+            PreviewContent::Markdown { html } => rsx! {
+                div {
+                    class: "p-4 text-sm text-gray-800 space-y-3 break-words [&_h1]:text-2xl [&_h1]:font-bold [&_h2]:text-xl [&_h2]:font-semibold [&_h3]:font-semibold [&_a]:text-blue-600 [&_a]:underline [&_ul]:list-disc [&_ul]:pl-6 [&_ol]:list-decimal [&_ol]:pl-6 [&_code]:font-mono [&_code]:bg-gray-100 [&_code]:px-1 [&_pre]:bg-gray-100 [&_pre]:p-3 [&_pre]:overflow-x-auto [&_blockquote]:border-l-4 [&_blockquote]:pl-3 [&_blockquote]:text-gray-600 [&_table]:border-collapse [&_td]:border [&_td]:px-2 [&_th]:border [&_th]:px-2",
+                    dangerous_inner_html: "{html}",
+                }
+            },
+            PreviewContent::Image { data_url: Some(url), .. } => rsx! {
+                div { class: "p-4 flex justify-center bg-gray-50",
+                    img { class: "max-w-full h-auto", src: "{url}" }
+                }
+            },
+            PreviewContent::Image { data_url: None, .. } | PreviewContent::Empty => rsx! {},
+            PreviewContent::Hex(lines) => rsx! {
+                pre { class: "p-4 text-xs font-mono text-gray-800 whitespace-pre min-w-max", "{lines.join(\"\\n\")}" }
+            },
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use dioxus::prelude::*;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::views::indexing::{ContentMatch, SearchHit, Snippet};
+
+#[component]
+pub fn SearchResults(
+    /// Heading shown above the list
+    title: String,
+    /// File name matches to list
+    results: Vec<SearchHit>,
+    /// Full-text hits shown below the filename matches
+    #[props(default = Vec::new())]
+    content_matches: Vec<ContentMatch>,
+    /// Matching lines of the full-text hits, once they have been read
+    #[props(default = HashMap::new())]
+    snippets: HashMap<PathBuf, Vec<Snippet>>,
+    /// Paths are displayed relative to this directory
+    root: PathBuf,
+    /// Callback triggered when a path is chosen
+    on_select: EventHandler<PathBuf>,
+) -> Element {
+    rsx! {
+        div { class: "absolute z-20 mt-1 w-full lg:w-96 bg-white border border-gray-200 rounded-lg shadow-lg max-h-[32rem] overflow-y-auto",
+            div { class: "px-3 py-2 text-xs font-medium text-gray-500 uppercase tracking-wider border-b",
+                "{title}"
+            }
+            if results.is_empty() && content_matches.is_empty() {
+                div { class: "px-3 py-4 text-sm text-center text-gray-500", "No matches" }
+            }
+            for SearchHit { path, is_dir } in results {
+                button {
+                    key: "{path.display()}",
+                    class: "w-full text-left px-3 py-2 hover:bg-blue-50 transition-colors",
+                    // mousedown fires before the search input loses focus and hides this list
+                    onmousedown: {
+                        let path = path.clone();
+                        move |evt: MouseEvent| {
+                            evt.prevent_default();
+                            on_select.call(path.clone());
+                        }
+                    },
+                    div { class: "text-sm font-medium text-gray-900 truncate",
+                        if is_dir {
+                            "📁 "
+                        } else {
+                            "📄 "
+                        }
+                        {path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default()}
+                    }
+                    div { class: "text-xs text-gray-500 truncate",
+                        "{path.strip_prefix(&root).unwrap_or(&path).display()}"
+                    }
+                }
+            }
+            if !content_matches.is_empty() {
+                div { class: "px-3 py-2 text-xs font-medium text-gray-500 uppercase tracking-wider border-y bg-gray-50",
+                    "In file contents"
+                }
+            }
+            for hit in content_matches {
+                button {
+                    key: "content-{hit.path.display()}",
+                    class: "w-full text-left px-3 py-2 hover:bg-blue-50 transition-colors",
+                    onmousedown: {
+                        let path = hit.path.clone();
+                        move |evt: MouseEvent| {
+                            evt.prevent_default();
+                            on_select.call(path.clone());
+                        }
+                    },
+                    div { class: "text-sm font-medium text-gray-900 truncate",
+                        "📝 {hit.path.strip_prefix(&root).unwrap_or(&hit.path).display()}"
+                    }
+                    for snippet in snippets.get(&hit.path).into_iter().flatten() {
+                        div { class: "text-xs font-mono text-gray-600 truncate",
+                            span { class: "text-gray-400 mr-2", "{snippet.line}" }
+                            "{snippet.text}"
+                        }
+                    }
+                }
+            }
+            div { class: "px-3 py-2 text-xs text-gray-400 border-t",
+                "Narrow content search with ext:rs,toml or path:src/views"
+            }
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use dioxus::html::input_data::MouseButton;
+use dioxus::prelude::*;
+
+use crate::views::tabs::Tabs;
+use crate::views::CurrentPath;
+
+/// Tab strip above the FileManager listing. Tabs can be dragged to reorder
+/// them and closed with a middle click.
+#[component]
+pub fn TabBar() -> Element {
+    let tabs = use_context::<Tabs>();
+    let CurrentPath(current_path) = use_context();
+    let set = tabs.list();
+    let mut dragging = use_signal(|| None::<usize>);
+    let count = set.read().tabs.len();
+
+    rsx! {
+        div { class: "flex items-end gap-1 mb-4 border-b border-gray-200 overflow-x-auto",
+            for (index, tab) in set.read().tabs.iter().enumerate() {
+                div {
+                    key: "{tab.id}",
+                    class: if index == set.read().active {
+                        "flex items-center gap-2 max-w-56 px-3 py-1.5 -mb-px text-sm bg-white border border-gray-200 border-b-white rounded-t-md text-gray-900 cursor-default"
+                    } else {
+                        "flex items-center gap-2 max-w-56 px-3 py-1.5 text-sm text-gray-600 hover:text-gray-900 hover:bg-gray-100 rounded-t-md cursor-pointer"
+                    },
+                    title: "{tab.path.display()}",
+                    draggable: true,
+                    onclick: move |_| tabs.activate(index),
+                    onmousedown: move |evt| {
+                        if evt.trigger_button() == Some(MouseButton::Auxiliary) {
+                            evt.prevent_default();
+                            tabs.close(index);
+                        }
+                    },
+                    ondragstart: move |_| dragging.set(Some(index)),
+                    ondragend: move |_| dragging.set(None),
+                    ondragover: move |evt| {
+                        if dragging().is_some() {
+                            evt.prevent_default();
+                        }
+                    },
+                    ondrop: move |evt| {
+                        if let Some(from) = dragging.take() {
+                            evt.prevent_default();
+                            tabs.reorder(from, index);
+                        }
+                    },
+                    span { class: "truncate", "📁 {tab.title()}" }
+                    if count > 1 {
+                        button {
+                            class: "text-gray-400 hover:text-gray-900",
+                            title: "Close tab",
+                            onclick: move |evt| {
+                                evt.stop_propagation();
+                                tabs.close(index);
+                            },
+                            "✕"
+                        }
+                    }
+                }
+            }
+            button {
+                class: "px-3 py-1.5 text-sm text-gray-500 hover:text-gray-900",
+                title: "New tab",
+                onclick: move |_| tabs.open(current_path(), true),
+                "+"
+            }
+        }
+    }
+}
@@ -1,8 +1,18 @@
 // The dioxus prelude contains a ton of common items used in dioxus apps. It's a good idea to import wherever you
 // need dioxus
 use dioxus::prelude::*;
+use std::path::PathBuf;
 
-use views::{Home, Navbar, FileManager};
+use views::columns::{self, ColumnLayouts};
+use views::commands::use_commands_provider;
+use views::frecency::use_visits_provider;
+use views::indexing::use_search_index_provider;
+use views::keybindings::use_keybindings_provider;
+use views::operations::use_job_queue_provider;
+use views::panes::use_panes_provider;
+use views::places::use_bookmarks_provider;
+use views::tabs::use_tabs_provider;
+use views::{use_unsaved_edits_provider, DiskUsage, Editor, Home, Navbar, FileManager, Trash};
 
 mod components;
 mod views;
@@ -16,6 +26,12 @@ enum Route {
         Home {},
         #[route("/files")]
         FileManager {},
+        #[route("/edit/:..segments")]
+        Editor { segments: Vec<String> },
+        #[route("/usage")]
+        DiskUsage {},
+        #[route("/trash")]
+        Trash {},
 }
 const FAVICON: Asset = asset!("/assets/favicon.ico");
 const TAILWIND_CSS: Asset = asset!("/assets/tailwind.css");
@@ -26,9 +42,24 @@ fn main() {
 
 #[component]
 fn App() -> Element {
+    let root = use_hook(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")));
+    use_tabs_provider(root.clone());
+    use_panes_provider(root.clone());
+    use_bookmarks_provider();
+    use_visits_provider();
+    use_commands_provider();
+    use_keybindings_provider();
+    use_context_provider(|| ColumnLayouts(Signal::new(columns::load())));
+    use_search_index_provider(root);
+    use_job_queue_provider();
+    let unsaved = use_unsaved_edits_provider();
+
     rsx! {
         document::Link { rel: "icon", href: FAVICON }
         document::Link { rel: "stylesheet", href: TAILWIND_CSS }
-        Router::<Route> {}
+        Router::<Route> {
+            // Leaving an editor with unsaved changes needs the editor's say-so
+            config: move || RouterConfig::default().on_update(move |router| unsaved.hold(router.current())),
+        }
     }
 }
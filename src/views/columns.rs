@@ -0,0 +1,402 @@
+//! Column layout and sort order of the FileManager table, remembered per
+//! directory.
+//!
+//! A directory without a layout of its own uses the one of its nearest
+//! ancestor that has one, so setting up a folder also sets up everything below it.
+
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock, PoisonError};
+
+use super::config;
+use super::dir_size::DirSizeSettings;
+use super::time_format::TimeFormat;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Column {
+    Type,
+    Name,
+    Size,
+    Modified,
+    Extension,
+    Permissions,
+    Owner,
+    Group,
+    Created,
+    Accessed,
+    Inode,
+    LinkTarget,
+}
+
+impl Column {
+    pub const ALL: [Column; 12] = [
+        Column::Type,
+        Column::Name,
+        Column::Size,
+        Column::Modified,
+        Column::Extension,
+        Column::Permissions,
+        Column::Owner,
+        Column::Group,
+        Column::Created,
+        Column::Accessed,
+        Column::Inode,
+        Column::LinkTarget,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Column::Type => "Type",
+            Column::Name => "Name",
+            Column::Size => "Size",
+            Column::Modified => "Modified",
+            Column::Extension => "Extension",
+            Column::Permissions => "Permissions",
+            Column::Owner => "Owner",
+            Column::Group => "Group",
+            Column::Created => "Created",
+            Column::Accessed => "Accessed",
+            Column::Inode => "Inode",
+            Column::LinkTarget => "Link target",
+        }
+    }
+
+    /// Tailwind width class of the column's header.
+    pub fn width_class(self) -> &'static str {
+        match self {
+            Column::Type => "w-12",
+            Column::Name | Column::LinkTarget => "",
+            Column::Size | Column::Permissions | Column::Inode => "w-32",
+            Column::Extension | Column::Owner | Column::Group => "w-24",
+            Column::Modified | Column::Created | Column::Accessed => "w-48",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SortOrder {
+    pub column: Column,
+    pub descending: bool,
+    /// Compare runs of digits by value, so `file2` sorts before `file10`
+    pub natural: bool,
+    pub case_insensitive: bool,
+}
+
+impl Default for SortOrder {
+    fn default() -> Self {
+        Self {
+            column: Column::Name,
+            descending: false,
+            natural: true,
+            case_insensitive: false,
+        }
+    }
+}
+
+impl SortOrder {
+    /// Compares two names according to the natural and case options.
+    pub fn compare_names(&self, a: &str, b: &str) -> Ordering {
+        let (a, b) = if self.case_insensitive {
+            (a.to_lowercase(), b.to_lowercase())
+        } else {
+            (a.to_string(), b.to_string())
+        };
+        if self.natural {
+            natural_cmp(&a, &b)
+        } else {
+            a.cmp(&b)
+        }
+    }
+}
+
+/// Which columns are shown, in which order, and how rows are sorted.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ListingLayout {
+    pub columns: Vec<Column>,
+    pub sort: SortOrder,
+    /// How the time columns show their timestamps
+    pub time_format: TimeFormat,
+    /// How directories get a size
+    pub dir_sizes: DirSizeSettings,
+}
+
+impl Default for ListingLayout {
+    fn default() -> Self {
+        Self {
+            columns: vec![Column::Type, Column::Name, Column::Size, Column::Modified],
+            sort: SortOrder::default(),
+            time_format: TimeFormat::default(),
+            dir_sizes: DirSizeSettings::default(),
+        }
+    }
+}
+
+impl ListingLayout {
+    /// Shows or hides `column`. The name column always stays.
+    pub fn toggle(&mut self, column: Column) {
+        if column == Column::Name {
+            return;
+        }
+        if let Some(i) = self.columns.iter().position(|&c| c == column) {
+            self.columns.remove(i);
+        } else {
+            self.columns.push(column);
+        }
+    }
+
+    /// Moves a visible column one place to the left (`-1`) or right (`1`).
+    pub fn shift(&mut self, column: Column, by: isize) {
+        let Some(i) = self.columns.iter().position(|&c| c == column) else {
+            return;
+        };
+        let j = i as isize + by;
+        if (0..self.columns.len() as isize).contains(&j) {
+            self.columns.swap(i, j as usize);
+        }
+    }
+
+    /// Sorts by `column`, flipping the direction if it already is the sort column.
+    pub fn sort_by(&mut self, column: Column) {
+        if self.sort.column == column {
+            self.sort.descending = !self.sort.descending;
+        } else {
+            self.sort.column = column;
+            self.sort.descending = false;
+        }
+    }
+}
+
+/// Layouts chosen per directory, provided as context by `App`.
+#[derive(Clone, Copy)]
+pub struct ColumnLayouts(pub Signal<BTreeMap<PathBuf, ListingLayout>>);
+
+impl ColumnLayouts {
+    /// The layout of `dir`, inherited from the nearest ancestor if it has none.
+    pub fn layout_for(&self, dir: &Path) -> ListingLayout {
+        let layouts = self.0.read();
+        dir.ancestors()
+            .find_map(|ancestor| layouts.get(ancestor))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Remembers `layout` for `dir` and saves all layouts to disk.
+    pub fn set_layout(&self, dir: &Path, layout: ListingLayout) {
+        let mut layouts = self.0;
+        layouts.write().insert(dir.to_path_buf(), layout);
+        // Losing a column preference isn't worth interrupting anyone over
+        let _ = save(&layouts.read());
+    }
+
+    /// Drops the layout of `dir` so it inherits again.
+    pub fn reset(&self, dir: &Path) {
+        let mut layouts = self.0;
+        layouts.write().remove(dir);
+        let _ = save(&layouts.read());
+    }
+}
+
+/// TOML, so fields added to ListingLayout later fall back to their defaults
+const LAYOUTS_FILE: &str = "columns.toml";
+
+/// Reads the saved layouts; a missing or unreadable file means no layouts.
+pub fn load() -> BTreeMap<PathBuf, ListingLayout> {
+    config::load_toml(LAYOUTS_FILE).unwrap_or_default()
+}
+
+fn save(layouts: &BTreeMap<PathBuf, ListingLayout>) -> io::Result<()> {
+    // TOML keys are strings, so a directory whose path isn't UTF-8 only keeps
+    // its layout until the app quits
+    let layouts: BTreeMap<&str, &ListingLayout> = layouts
+        .iter()
+        .filter_map(|(dir, layout)| Some((dir.to_str()?, layout)))
+        .collect();
+    config::save_toml(LAYOUTS_FILE, &layouts)
+}
+
+/// Compares strings treating each run of ASCII digits as one number.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a, b);
+    // Equal numbers written with more leading zeros sort later, but only when
+    // nothing else tells the strings apart
+    let mut zeros = Ordering::Equal;
+    loop {
+        match (a.chars().next(), b.chars().next()) {
+            (None, None) => return zeros,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let a_len = a.find(|c: char| !c.is_ascii_digit()).unwrap_or(a.len());
+                let b_len = b.find(|c: char| !c.is_ascii_digit()).unwrap_or(b.len());
+                let (a_digits, b_digits) = (&a[..a_len], &b[..b_len]);
+                let (a_value, b_value) = (a_digits.trim_start_matches('0'), b_digits.trim_start_matches('0'));
+                // Without leading zeros, longer runs are bigger numbers
+                let order = a_value.len().cmp(&b_value.len()).then_with(|| a_value.cmp(b_value));
+                if order != Ordering::Equal {
+                    return order;
+                }
+                zeros = zeros.then(a_len.cmp(&b_len));
+                a = &a[a_len..];
+                b = &b[b_len..];
+            }
+            (Some(x), Some(y)) => {
+                if x != y {
+                    return x.cmp(&y);
+                }
+                a = &a[x.len_utf8()..];
+                b = &b[y.len_utf8()..];
+            }
+        }
+    }
+}
+
+/// `drwxr-xr-x` style rendering of a Unix mode.
+pub fn format_permissions(mode: u32, is_dir: bool, is_symlink: bool) -> String {
+    let kind = if is_symlink {
+        'l'
+    } else if is_dir {
+        'd'
+    } else {
+        '-'
+    };
+    let mut text = String::with_capacity(10);
+    text.push(kind);
+    for shift in [6, 3, 0] {
+        let bits = (mode >> shift) & 0o7;
+        text.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+        text.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+        text.push(if bits & 0o1 != 0 { 'x' } else { '-' });
+    }
+    text
+}
+
+/// Name of the user with `uid`, or the number if there is none.
+pub fn user_name(uid: u32) -> String {
+    static USERS: OnceLock<Mutex<HashMap<u32, String>>> = OnceLock::new();
+    let mut users = USERS.get_or_init(Default::default).lock().unwrap_or_else(PoisonError::into_inner);
+    users.entry(uid).or_insert_with(|| lookup_user(uid).unwrap_or_else(|| uid.to_string())).clone()
+}
+
+/// Name of the group with `gid`, or the number if there is none.
+pub fn group_name(gid: u32) -> String {
+    static GROUPS: OnceLock<Mutex<HashMap<u32, String>>> = OnceLock::new();
+    let mut groups = GROUPS.get_or_init(Default::default).lock().unwrap_or_else(PoisonError::into_inner);
+    groups.entry(gid).or_insert_with(|| lookup_group(gid).unwrap_or_else(|| gid.to_string())).clone()
+}
+
+fn lookup_user(uid: u32) -> Option<String> {
+    let mut buffer = vec![0; 4096];
+    // SAFETY: passwd is plain data that getpwuid_r fills in
+    let mut entry: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut result = std::ptr::null_mut();
+    // SAFETY: every pointer refers to live storage of the advertised size; on
+    // success `result` points at `entry`, whose strings live in `buffer`
+    let status = unsafe { libc::getpwuid_r(uid, &mut entry, buffer.as_mut_ptr(), buffer.len(), &mut result) };
+    if status != 0 || result.is_null() {
+        return None;
+    }
+    // SAFETY: pw_name is a NUL-terminated string inside `buffer`
+    Some(unsafe { std::ffi::CStr::from_ptr(entry.pw_name) }.to_string_lossy().into_owned())
+}
+
+fn lookup_group(gid: u32) -> Option<String> {
+    let mut buffer = vec![0; 4096];
+    // SAFETY: group is plain data that getgrgid_r fills in
+    let mut entry: libc::group = unsafe { std::mem::zeroed() };
+    let mut result = std::ptr::null_mut();
+    // SAFETY: as in lookup_user
+    let status = unsafe { libc::getgrgid_r(gid, &mut entry, buffer.as_mut_ptr(), buffer.len(), &mut result) };
+    if status != 0 || result.is_null() {
+        return None;
+    }
+    // SAFETY: gr_name is a NUL-terminated string inside `buffer`
+    Some(unsafe { std::ffi::CStr::from_ptr(entry.gr_name) }.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn natural_order_compares_digit_runs_by_value() {
+        assert_eq!(natural_cmp("file2", "file10"), Ordering::Less);
+        assert_eq!(natural_cmp("file10", "file9"), Ordering::Greater);
+        assert_eq!(natural_cmp("a1b2", "a1b10"), Ordering::Less);
+        assert_eq!(natural_cmp("img12.png", "img12.png"), Ordering::Equal);
+        assert_eq!(natural_cmp("99", "100"), Ordering::Less);
+    }
+
+    #[test]
+    fn natural_order_uses_leading_zeros_only_as_a_tiebreak() {
+        assert_eq!(natural_cmp("file01", "file1"), Ordering::Greater);
+        assert_eq!(natural_cmp("file001", "file2"), Ordering::Less);
+        assert_eq!(natural_cmp("a01b", "a1c"), Ordering::Less);
+    }
+
+    #[test]
+    fn natural_order_puts_prefixes_first() {
+        assert_eq!(natural_cmp("file", "file1"), Ordering::Less);
+        assert_eq!(natural_cmp("file1", "file1a"), Ordering::Less);
+        assert_eq!(natural_cmp("", "a"), Ordering::Less);
+    }
+
+    #[test]
+    fn compare_names_follows_the_sort_options() {
+        let mut sort = SortOrder::default();
+        assert_eq!(sort.compare_names("b", "a10"), Ordering::Greater);
+        assert_eq!(sort.compare_names("B", "a"), Ordering::Less);
+        sort.case_insensitive = true;
+        assert_eq!(sort.compare_names("B", "a"), Ordering::Greater);
+        sort.natural = false;
+        assert_eq!(sort.compare_names("file10", "file2"), Ordering::Less);
+    }
+
+    #[test]
+    fn layout_edits_keep_the_name_column() {
+        let mut layout = ListingLayout::default();
+        layout.toggle(Column::Name);
+        layout.toggle(Column::Size);
+        layout.toggle(Column::Owner);
+        assert_eq!(layout.columns, [Column::Type, Column::Name, Column::Modified, Column::Owner]);
+
+        layout.shift(Column::Owner, 1);
+        layout.shift(Column::Type, -1);
+        layout.shift(Column::Name, -1);
+        assert_eq!(layout.columns, [Column::Name, Column::Type, Column::Modified, Column::Owner]);
+
+        layout.sort_by(Column::Name);
+        assert!(layout.sort.descending);
+        layout.sort_by(Column::Modified);
+        assert_eq!((layout.sort.column, layout.sort.descending), (Column::Modified, false));
+    }
+
+    #[test]
+    fn saved_layouts_without_newer_fields_still_load() {
+        let layouts: BTreeMap<PathBuf, ListingLayout> = toml::from_str(
+            r#"
+            ["/home/me/photos"]
+            columns = ["Name", "Modified"]
+
+            ["/home/me/photos".sort]
+            column = "Modified"
+            descending = true
+            "#,
+        )
+        .unwrap();
+        let layout = &layouts[Path::new("/home/me/photos")];
+        assert_eq!(layout.columns, [Column::Name, Column::Modified]);
+        assert_eq!(layout.sort.column, Column::Modified);
+        assert!(layout.sort.descending && layout.sort.natural);
+        assert_eq!(layout.time_format, TimeFormat::default());
+        assert_eq!(layout.dir_sizes, DirSizeSettings::default());
+
+        let text = toml::to_string_pretty(&layouts).unwrap();
+        assert_eq!(toml::from_str::<BTreeMap<PathBuf, ListingLayout>>(&text).unwrap(), layouts);
+    }
+}
@@ -0,0 +1,317 @@
+//! Every FileManager action as a [`Command`], so the command palette can list
+//! them and run them without knowing how each one works.
+//!
+//! The active pane publishes what is possible right now as a
+//! [`CommandState`] and carries out commands put into [`Commands::run`];
+//! the few that belong to the whole page are handled by FileManager itself.
+//! Keys reach commands through the keymap in [`super::keybindings`].
+
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Named in kebab-case in the keybindings file, e.g. `copy-to-other-pane`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Command {
+    CursorUp,
+    CursorDown,
+    PageUp,
+    PageDown,
+    CursorFirst,
+    CursorLast,
+    SelectUp,
+    SelectDown,
+    ToggleMark,
+    Open,
+    Find,
+    SelectAll,
+    InvertSelection,
+    ClearSelection,
+    Copy,
+    Cut,
+    Paste,
+    CopyToOtherPane,
+    MoveToOtherPane,
+    Rename,
+    NewFolder,
+    AddFiles,
+    MoveToTrash,
+    DeletePermanently,
+    Undo,
+    Redo,
+    Edit,
+    TogglePreview,
+    CalculateSizes,
+    GoBack,
+    GoForward,
+    GoUp,
+    SwitchPane,
+    ToggleSplit,
+    NewTab,
+    CloseTab,
+    JumpTo,
+    BookmarkFolder,
+    ShowCommands,
+}
+
+impl Command {
+    pub const ALL: [Command; 39] = [
+        Command::CursorUp,
+        Command::CursorDown,
+        Command::PageUp,
+        Command::PageDown,
+        Command::CursorFirst,
+        Command::CursorLast,
+        Command::SelectUp,
+        Command::SelectDown,
+        Command::ToggleMark,
+        Command::Open,
+        Command::Find,
+        Command::SelectAll,
+        Command::InvertSelection,
+        Command::ClearSelection,
+        Command::Copy,
+        Command::Cut,
+        Command::Paste,
+        Command::CopyToOtherPane,
+        Command::MoveToOtherPane,
+        Command::Rename,
+        Command::NewFolder,
+        Command::AddFiles,
+        Command::MoveToTrash,
+        Command::DeletePermanently,
+        Command::Undo,
+        Command::Redo,
+        Command::Edit,
+        Command::TogglePreview,
+        Command::CalculateSizes,
+        Command::GoBack,
+        Command::GoForward,
+        Command::GoUp,
+        Command::SwitchPane,
+        Command::ToggleSplit,
+        Command::NewTab,
+        Command::CloseTab,
+        Command::JumpTo,
+        Command::BookmarkFolder,
+        Command::ShowCommands,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Command::CursorUp => "Cursor up",
+            Command::CursorDown => "Cursor down",
+            Command::PageUp => "Cursor page up",
+            Command::PageDown => "Cursor page down",
+            Command::CursorFirst => "Cursor to first item",
+            Command::CursorLast => "Cursor to last item",
+            Command::SelectUp => "Extend selection up",
+            Command::SelectDown => "Extend selection down",
+            Command::ToggleMark => "Toggle selection of item",
+            Command::Open => "Open item",
+            Command::Find => "Find by name",
+            Command::SelectAll => "Select all",
+            Command::InvertSelection => "Invert selection",
+            Command::ClearSelection => "Clear selection",
+            Command::Copy => "Copy",
+            Command::Cut => "Cut",
+            Command::Paste => "Paste",
+            Command::CopyToOtherPane => "Copy to other pane",
+            Command::MoveToOtherPane => "Move to other pane",
+            Command::Rename => "Rename",
+            Command::NewFolder => "New folder",
+            Command::AddFiles => "Add files",
+            Command::MoveToTrash => "Move to Trash",
+            Command::DeletePermanently => "Delete permanently",
+            Command::Undo => "Undo",
+            Command::Redo => "Redo",
+            Command::Edit => "Edit in text editor",
+            Command::TogglePreview => "Toggle preview",
+            Command::CalculateSizes => "Calculate folder sizes",
+            Command::GoBack => "Go back",
+            Command::GoForward => "Go forward",
+            Command::GoUp => "Go to parent folder",
+            Command::SwitchPane => "Switch to other pane",
+            Command::ToggleSplit => "Toggle split view",
+            Command::NewTab => "New tab",
+            Command::CloseTab => "Close tab",
+            Command::JumpTo => "Jump to visited folder",
+            Command::BookmarkFolder => "Bookmark this folder",
+            Command::ShowCommands => "Show all commands",
+        }
+    }
+
+    /// Whether the command palette lists it; moving the cursor is only
+    /// useful from the keyboard.
+    pub fn in_palette(self) -> bool {
+        !matches!(
+            self,
+            Command::CursorUp
+                | Command::CursorDown
+                | Command::PageUp
+                | Command::PageDown
+                | Command::CursorFirst
+                | Command::CursorLast
+                | Command::SelectUp
+                | Command::SelectDown
+                | Command::ToggleMark
+                | Command::Open
+                | Command::ShowCommands
+        )
+    }
+
+    /// Commands FileManager handles itself instead of the active pane.
+    pub fn is_page_wide(self) -> bool {
+        matches!(self, Command::ToggleSplit | Command::JumpTo | Command::ShowCommands)
+    }
+
+    /// Whether the command can do anything in `state`.
+    pub fn is_available(self, state: &CommandState) -> bool {
+        match self {
+            Command::CursorUp
+            | Command::CursorDown
+            | Command::PageUp
+            | Command::PageDown
+            | Command::CursorFirst
+            | Command::CursorLast
+            | Command::SelectUp
+            | Command::SelectDown
+            | Command::Find
+            | Command::InvertSelection
+            | Command::SelectAll => state.listed > 0,
+            Command::ToggleMark | Command::Open => state.cursor,
+            Command::ClearSelection
+            | Command::Copy
+            | Command::Cut
+            | Command::Rename
+            | Command::MoveToTrash
+            | Command::DeletePermanently => state.selected > 0,
+            Command::Paste => state.clipboard,
+            Command::CopyToOtherPane | Command::MoveToOtherPane => state.split && state.selected > 0,
+            Command::Undo => state.can_undo,
+            Command::Redo => state.can_redo,
+            Command::Edit => state.single_file,
+            Command::GoBack => state.can_go_back,
+            Command::GoForward => state.can_go_forward,
+            Command::GoUp => state.has_parent,
+            Command::SwitchPane => state.split,
+            Command::CloseTab => state.tabs > 1,
+            Command::NewFolder
+            | Command::AddFiles
+            | Command::TogglePreview
+            | Command::CalculateSizes
+            | Command::ToggleSplit
+            | Command::NewTab
+            | Command::JumpTo
+            | Command::BookmarkFolder
+            | Command::ShowCommands => true,
+        }
+    }
+}
+
+/// What the active pane looks like, for deciding which commands apply.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CommandState {
+    pub listed: usize,
+    pub selected: usize,
+    /// The keyboard cursor is on a row
+    pub cursor: bool,
+    /// Exactly one file, not a folder, is selected
+    pub single_file: bool,
+    pub clipboard: bool,
+    pub split: bool,
+    pub tabs: usize,
+    pub can_undo: bool,
+    pub can_redo: bool,
+    pub can_go_back: bool,
+    pub can_go_forward: bool,
+    pub has_parent: bool,
+}
+
+/// Provided as context by [`use_commands_provider`].
+#[derive(Clone, Copy)]
+pub struct Commands {
+    pub state: Signal<CommandState>,
+    /// Command waiting for whoever handles it to take it
+    pub pending: Signal<Option<Command>>,
+}
+
+impl Commands {
+    /// Asks for `command` to be carried out.
+    pub fn run(&self, command: Command) {
+        let mut pending = self.pending;
+        pending.set(Some(command));
+    }
+
+    /// Takes the pending command if `handles` accepts it. Effects calling
+    /// this run again whenever a new command comes in.
+    pub fn take_if(&self, handles: impl Fn(Command) -> bool) -> Option<Command> {
+        let mut pending = self.pending;
+        let command = (*pending.read()).filter(|&command| handles(command))?;
+        pending.set(None);
+        Some(command)
+    }
+}
+
+/// Fuzzy match of `query` against `text`: the query's characters must appear
+/// in order. Higher scores mean tighter matches, preferring runs of
+/// consecutive characters and matches at the start of words.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous = None;
+    for wanted in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = (position..text.len()).find(|&index| text[index] == wanted)?;
+        score += 1;
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 8;
+        }
+        if previous.is_some_and(|previous| previous + 1 == found) {
+            score += 5;
+        } else {
+            score -= (found - position).min(10) as i32;
+        }
+        previous = Some(found);
+        position = found + 1;
+    }
+    Some(score)
+}
+
+pub fn use_commands_provider() {
+    use_context_provider(|| Commands {
+        state: Signal::new(CommandState::default()),
+        pending: Signal::new(None),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_match_needs_every_character_in_order() {
+        assert!(fuzzy_score("npf", "New folder").is_none());
+        assert!(fuzzy_score("nf", "New folder").is_some());
+        assert!(fuzzy_score("fn", "New folder").is_none());
+        assert!(fuzzy_score("zz", "Copy").is_none());
+        assert_eq!(fuzzy_score("", "Anything"), Some(0));
+    }
+
+    #[test]
+    fn fuzzy_match_ignores_case_and_spaces() {
+        assert_eq!(fuzzy_score("NEW FOLDER", "new folder"), fuzzy_score("newfolder", "New Folder"));
+        assert!(fuzzy_score(" go  back ", "Go back").is_some());
+    }
+
+    #[test]
+    fn tighter_matches_score_higher() {
+        let score = |query| fuzzy_score(query, "Toggle split view").unwrap();
+        // Word starts beat letters inside words
+        assert!(score("sv") > score("pl"));
+        // Consecutive letters beat scattered ones
+        assert!(score("spl") > score("sit"));
+        // A prefix of the whole text beats the same letters later on
+        assert!(fuzzy_score("copy", "Copy path").unwrap() > fuzzy_score("copy", "Move or copy").unwrap());
+    }
+}
@@ -0,0 +1,134 @@
+//! Settings files under the user's config directory.
+//!
+//! They are TOML, so a field added later only needs `#[serde(default)]` for
+//! older files to keep loading under the same name. TOML can only hold paths
+//! that are UTF-8, so callers leave the others out.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Sender};
+use std::sync::OnceLock;
+
+fn config_path(file_name: &str) -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("file-manager").join(file_name))
+}
+
+/// Reads `file_name` as TOML. A missing file gives the default; one that
+/// doesn't parse gives the reason, since someone will want to fix it.
+pub fn load_toml<T: DeserializeOwned + Default>(file_name: &str) -> Result<T, String> {
+    let Some(path) = config_path(file_name) else {
+        return Ok(T::default());
+    };
+    match fs::read_to_string(&path) {
+        Ok(text) => toml::from_str(&text).map_err(|err| format!("{}: {err}", path.display())),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(T::default()),
+        Err(err) => Err(format!("{}: {err}", path.display())),
+    }
+}
+
+/// Writes `value` to `file_name` as TOML through a temporary file, so a crash
+/// never leaves half a file behind.
+pub fn save_toml<T: Serialize>(file_name: &str, value: &T) -> io::Result<()> {
+    let text = toml::to_string_pretty(value).map_err(io::Error::other)?;
+    write_file(file_name, |writer| writer.write_all(text.as_bytes()))
+}
+
+/// Sets the top-level `key` of the TOML file `file_name` to `value`, leaving
+/// the rest of a hand-edited file, comments and all, as it was.
+pub fn set_toml_key<T: Serialize>(file_name: &str, key: &str, value: &T) -> io::Result<()> {
+    let path = config_path(file_name).ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err),
+    };
+    let text = with_toml_key(&text, key, value).map_err(io::Error::other)?;
+    write_file(file_name, |writer| writer.write_all(text.as_bytes()))
+}
+
+fn with_toml_key<T: Serialize>(text: &str, key: &str, value: &T) -> Result<String, String> {
+    let mut document: toml_edit::Document = text.parse().map_err(|err| format!("{err}"))?;
+    let value = toml::Value::try_from(value).map_err(|err| err.to_string())?;
+    let mut value: toml_edit::Value = value.to_string().parse().map_err(|err| format!("{err}"))?;
+    match document.get_mut(key).and_then(toml_edit::Item::as_value_mut) {
+        Some(existing) => {
+            // Keeps the spacing and any comment after the old value
+            *value.decor_mut() = existing.decor().clone();
+            *existing = value;
+        }
+        None => document[key] = toml_edit::value(value),
+    }
+    Ok(document.to_string())
+}
+
+/// Like [`save_toml`], but the file is written on a background thread, for
+/// settings that change on every navigation. Saves that queue up while one is
+/// being written collapse into the latest one of each file.
+pub fn save_toml_later<T: Serialize>(file_name: &'static str, value: &T) {
+    static WRITER: OnceLock<Sender<(&'static str, String)>> = OnceLock::new();
+    let text = match toml::to_string_pretty(value) {
+        Ok(text) => text,
+        Err(err) => {
+            eprintln!("Could not save {file_name}: {err}");
+            return;
+        }
+    };
+    let writer = WRITER.get_or_init(|| {
+        let (tx, rx) = mpsc::channel::<(&'static str, String)>();
+        std::thread::spawn(move || {
+            while let Ok(first) = rx.recv() {
+                let mut latest = vec![first];
+                for (name, text) in rx.try_iter() {
+                    latest.retain(|(queued, _)| *queued != name);
+                    latest.push((name, text));
+                }
+                for (name, text) in latest {
+                    if let Err(err) = write_file(name, |writer| writer.write_all(text.as_bytes())) {
+                        eprintln!("Could not save {name}: {err}");
+                    }
+                }
+            }
+        });
+        tx
+    });
+    let _ = writer.send((file_name, text));
+}
+
+fn write_file(file_name: &str, write: impl FnOnce(&mut BufWriter<File>) -> io::Result<()>) -> io::Result<()> {
+    let path = config_path(file_name).ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("tmp");
+    let mut writer = BufWriter::new(File::create(&tmp)?);
+    write(&mut writer)?;
+    writer.into_inner().map_err(|err| err.into_error())?.sync_all()?;
+    fs::rename(tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn setting_a_key_keeps_the_rest_of_the_file() {
+        let text = "# My bindings\npreset = \"default\"  # for now\n\n[bindings]\n# Quicker\n\"ctrl+k\" = \"cursor-up\"\n";
+        let edited = with_toml_key(text, "preset", &"vim").unwrap();
+        assert_eq!(edited, text.replace("\"default\"", "\"vim\""));
+    }
+
+    #[test]
+    fn setting_a_missing_key_adds_it_before_the_tables() {
+        let edited = with_toml_key("[bindings]\n\"j\" = \"cursor-down\"\n", "preset", &"emacs").unwrap();
+        assert_eq!(edited, "preset = \"emacs\"\n[bindings]\n\"j\" = \"cursor-down\"\n");
+        assert_eq!(with_toml_key("", "preset", &"vim").unwrap(), "preset = \"vim\"\n");
+    }
+
+    #[test]
+    fn setting_a_key_in_a_broken_file_fails() {
+        assert!(with_toml_key("preset = ", "preset", &"vim").is_err());
+    }
+}
@@ -0,0 +1,437 @@
+//! Recursive directory sizes, measured on a background thread: as running
+//! totals for the Size column, or as a whole tree for the disk usage view.
+//!
+//! Both the apparent size (the sum of file lengths) and the disk usage
+//! (allocated blocks) are counted. A file with several hard links inside one
+//! directory counts once, and the walk stays on the directory's own file
+//! system unless told otherwise.
+
+use futures_channel::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Running totals are sent at most this often per directory.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(150);
+
+/// `st_blocks` counts 512-byte units whatever the file system's block size.
+const BLOCK_SIZE: u64 = 512;
+
+/// How the Size column treats directories, stored with the column layout.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DirSizeSettings {
+    /// Measure every directory as soon as the listing is read
+    pub automatic: bool,
+    /// Show the space taken on disk instead of the apparent size
+    pub disk_usage: bool,
+    /// Descend into file systems mounted below the measured directory
+    pub cross_mounts: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DirSize {
+    pub apparent: u64,
+    pub disk_usage: u64,
+    pub files: u64,
+    /// False while the walk is still going
+    pub complete: bool,
+}
+
+impl DirSize {
+    /// The size the Size column shows under `settings`.
+    pub fn shown(&self, settings: DirSizeSettings) -> u64 {
+        if settings.disk_usage {
+            self.disk_usage
+        } else {
+            self.apparent
+        }
+    }
+}
+
+/// A running measurement. Dropping it stops the walk, which ends its stream.
+pub struct SizeWalk {
+    cancel: Arc<AtomicBool>,
+}
+
+impl Drop for SizeWalk {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Measures `dirs` one after another, streaming each directory's running
+/// total until its final, complete one.
+pub fn measure_dirs(dirs: Vec<PathBuf>, cross_mounts: bool) -> (SizeWalk, UnboundedReceiver<(PathBuf, DirSize)>) {
+    let cancel = Arc::new(AtomicBool::new(false));
+    let (tx, rx) = mpsc::unbounded();
+
+    let cancelled = cancel.clone();
+    std::thread::spawn(move || {
+        for dir in dirs {
+            let Some(size) = measure(&dir, cross_mounts, &cancelled, &tx) else {
+                return;
+            };
+            if tx.unbounded_send((dir, size)).is_err() {
+                return;
+            }
+        }
+    });
+    (SizeWalk { cancel }, rx)
+}
+
+/// Walks `root`, sending running totals to `progress`. Returns `None` when
+/// cancelled or when nobody listens anymore.
+fn measure(
+    root: &Path,
+    cross_mounts: bool,
+    cancel: &AtomicBool,
+    progress: &UnboundedSender<(PathBuf, DirSize)>,
+) -> Option<DirSize> {
+    let mut size = DirSize::default();
+    let Ok(root_metadata) = fs::metadata(root) else {
+        size.complete = true;
+        return Some(size);
+    };
+    let device = root_metadata.dev();
+    // Directories take space too, as `du` counts them
+    size.apparent = root_metadata.len();
+    size.disk_usage = root_metadata.blocks() * BLOCK_SIZE;
+
+    let mut seen_links = HashSet::new();
+    let mut pending = vec![root.to_path_buf()];
+    let mut last_report = Instant::now();
+
+    while let Some(dir) = pending.pop() {
+        if cancel.load(Ordering::Relaxed) {
+            return None;
+        }
+        // Unreadable directories add nothing, like `du` without the complaint
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            // Symlinks are counted as links, never followed
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.is_dir() {
+                if cross_mounts || metadata.dev() == device {
+                    size.apparent += metadata.len();
+                    size.disk_usage += metadata.blocks() * BLOCK_SIZE;
+                    pending.push(entry.path());
+                }
+                continue;
+            }
+            if metadata.nlink() > 1 && !seen_links.insert((metadata.dev(), metadata.ino())) {
+                continue;
+            }
+            size.apparent += metadata.len();
+            size.disk_usage += metadata.blocks() * BLOCK_SIZE;
+            size.files += 1;
+        }
+
+        if last_report.elapsed() >= PROGRESS_INTERVAL {
+            last_report = Instant::now();
+            progress.unbounded_send((root.to_path_buf(), size)).ok()?;
+        }
+    }
+
+    size.complete = true;
+    Some(size)
+}
+
+/// A file or directory found by [`scan_tree`], with everything below it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UsageNode {
+    pub name: String,
+    pub is_dir: bool,
+    pub size: DirSize,
+    /// Largest first
+    pub children: Vec<UsageNode>,
+}
+
+impl UsageNode {
+    /// The node reached by following `names` down from this one.
+    pub fn find(&self, names: &[String]) -> Option<&UsageNode> {
+        names.iter().try_fold(self, |node, name| node.children.iter().find(|child| child.name == *name))
+    }
+
+    /// Adds `child` and its totals to this node.
+    fn add(&mut self, child: UsageNode) {
+        self.size.apparent += child.size.apparent;
+        self.size.disk_usage += child.size.disk_usage;
+        self.size.files += child.size.files;
+        self.children.push(child);
+    }
+
+    /// The `limit` largest files below this node, as paths relative to it.
+    pub fn largest_files(&self, limit: usize) -> Vec<(PathBuf, &UsageNode)> {
+        let mut files = Vec::new();
+        let mut pending = vec![(PathBuf::new(), self)];
+        while let Some((path, node)) = pending.pop() {
+            for child in &node.children {
+                let child_path = path.join(&child.name);
+                if child.is_dir {
+                    pending.push((child_path, child));
+                } else {
+                    files.push((child_path, child));
+                }
+            }
+        }
+        files.sort_by_key(|(_, file)| Reverse(file.size.disk_usage));
+        files.truncate(limit);
+        files
+    }
+}
+
+#[derive(Debug)]
+pub enum ScanEvent {
+    /// Totals found so far
+    Progress(DirSize),
+    Done(UsageNode),
+    Failed(String),
+}
+
+/// Scans the whole tree below `root` on a background thread, reporting
+/// progress until the finished tree arrives.
+pub fn scan_tree(root: PathBuf, cross_mounts: bool) -> (SizeWalk, UnboundedReceiver<ScanEvent>) {
+    let cancel = Arc::new(AtomicBool::new(false));
+    let (tx, rx) = mpsc::unbounded();
+
+    let cancelled = cancel.clone();
+    std::thread::spawn(move || {
+        let metadata = match fs::metadata(&root) {
+            Ok(metadata) if metadata.is_dir() => metadata,
+            Ok(_) => {
+                let _ = tx.unbounded_send(ScanEvent::Failed(format!("{} is not a directory", root.display())));
+                return;
+            }
+            Err(err) => {
+                let _ = tx.unbounded_send(ScanEvent::Failed(format!("Could not read {}: {err}", root.display())));
+                return;
+            }
+        };
+        let mut scan = TreeScan {
+            device: metadata.dev(),
+            cross_mounts,
+            cancel: &cancelled,
+            progress: &tx,
+            seen_links: HashSet::new(),
+            total: DirSize::default(),
+            last_report: Instant::now(),
+        };
+        let name = root.display().to_string();
+        if let Some(tree) = scan.directory(&root, name, &metadata) {
+            let _ = tx.unbounded_send(ScanEvent::Done(tree));
+        }
+    });
+    (SizeWalk { cancel }, rx)
+}
+
+struct TreeScan<'a> {
+    device: u64,
+    cross_mounts: bool,
+    cancel: &'a AtomicBool,
+    progress: &'a UnboundedSender<ScanEvent>,
+    /// Hard-linked files already counted somewhere in the tree
+    seen_links: HashSet<(u64, u64)>,
+    total: DirSize,
+    last_report: Instant,
+}
+
+/// A directory [`TreeScan`] is inside of, with the entries it has yet to visit.
+struct OpenDir {
+    node: UsageNode,
+    entries: std::vec::IntoIter<(PathBuf, String, fs::Metadata)>,
+}
+
+impl TreeScan<'_> {
+    /// Same rules as [`measure`]. Returns `None` once cancelled.
+    ///
+    /// Walks with an explicit stack of open directories, so a deep tree can't
+    /// overflow the thread's stack.
+    fn directory(&mut self, path: &Path, name: String, metadata: &fs::Metadata) -> Option<UsageNode> {
+        let mut stack = vec![self.open(path, name, metadata)];
+
+        loop {
+            let dir = stack.last_mut()?;
+            let Some((path, name, metadata)) = dir.entries.next() else {
+                let mut done = stack.pop()?;
+                done.node.children.sort_by_key(|child| Reverse(child.size.disk_usage));
+                done.node.size.complete = true;
+                match stack.last_mut() {
+                    Some(parent) => parent.node.add(done.node),
+                    None => return Some(done.node),
+                }
+                continue;
+            };
+
+            if metadata.is_dir() {
+                if !self.cross_mounts && metadata.dev() != self.device {
+                    continue;
+                }
+                if self.cancel.load(Ordering::Relaxed) {
+                    return None;
+                }
+                let child = self.open(&path, name, &metadata);
+                stack.push(child);
+            } else {
+                if metadata.nlink() > 1 && !self.seen_links.insert((metadata.dev(), metadata.ino())) {
+                    continue;
+                }
+                let file = UsageNode {
+                    name,
+                    is_dir: false,
+                    size: self.count(&metadata, true),
+                    children: Vec::new(),
+                };
+                dir.node.add(file);
+            }
+        }
+    }
+
+    /// Counts the directory at `path` itself and lists its entries. They are
+    /// read up front so no more than one directory handle is open at a time.
+    fn open(&mut self, path: &Path, name: String, metadata: &fs::Metadata) -> OpenDir {
+        let entries: Vec<_> = fs::read_dir(path)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+                Some((entry.path(), entry.file_name().to_string_lossy().into_owned(), metadata))
+            })
+            .collect();
+        let node = UsageNode {
+            name,
+            is_dir: true,
+            size: self.count(metadata, false),
+            children: Vec::new(),
+        };
+        OpenDir { node, entries: entries.into_iter() }
+    }
+
+    /// Adds one entry to the running total and returns its own size.
+    fn count(&mut self, metadata: &fs::Metadata, is_file: bool) -> DirSize {
+        let size = DirSize {
+            apparent: metadata.len(),
+            disk_usage: metadata.blocks() * BLOCK_SIZE,
+            files: u64::from(is_file),
+            complete: true,
+        };
+        self.total.apparent += size.apparent;
+        self.total.disk_usage += size.disk_usage;
+        self.total.files += size.files;
+        if self.last_report.elapsed() >= PROGRESS_INTERVAL {
+            self.last_report = Instant::now();
+            let _ = self.progress.unbounded_send(ScanEvent::Progress(self.total));
+        }
+        size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(case: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("dir-size-{}-{case}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn measure_now(root: &Path, cross_mounts: bool) -> DirSize {
+        let (progress, _updates) = mpsc::unbounded();
+        measure(root, cross_mounts, &AtomicBool::new(false), &progress).unwrap()
+    }
+
+    /// Scans `root` as [`scan_tree`] does, taking `device` as the one it is on.
+    fn scan_now(root: &Path, device: u64, cross_mounts: bool) -> UsageNode {
+        let (progress, _events) = mpsc::unbounded();
+        let cancel = AtomicBool::new(false);
+        let mut scan = TreeScan {
+            device,
+            cross_mounts,
+            cancel: &cancel,
+            progress: &progress,
+            seen_links: HashSet::new(),
+            total: DirSize::default(),
+            last_report: Instant::now(),
+        };
+        scan.directory(root, "root".to_string(), &fs::metadata(root).unwrap()).unwrap()
+    }
+
+    fn dir_len(path: &Path) -> u64 {
+        fs::metadata(path).unwrap().len()
+    }
+
+    #[test]
+    fn totals_add_up_files_and_folders() {
+        let root = scratch("totals");
+        fs::create_dir_all(root.join("a/b")).unwrap();
+        fs::write(root.join("one"), [0; 100]).unwrap();
+        fs::write(root.join("a/two"), [0; 20]).unwrap();
+        fs::write(root.join("a/b/three"), [0; 3]).unwrap();
+        let folders = dir_len(&root) + dir_len(&root.join("a")) + dir_len(&root.join("a/b"));
+
+        let size = measure_now(&root, false);
+        assert_eq!((size.apparent, size.files, size.complete), (folders + 123, 3, true));
+        assert_eq!(size.shown(DirSizeSettings::default()), size.apparent);
+        assert_eq!(size.shown(DirSizeSettings { disk_usage: true, ..Default::default() }), size.disk_usage);
+
+        let tree = scan_now(&root, fs::metadata(&root).unwrap().dev(), false);
+        assert_eq!((tree.size.apparent, tree.size.disk_usage, tree.size.files), (size.apparent, size.disk_usage, 3));
+        let a = tree.find(&["a".to_string()]).unwrap();
+        assert_eq!(a.size.apparent, dir_len(&root.join("a")) + dir_len(&root.join("a/b")) + 23);
+        assert_eq!(tree.largest_files(1)[0].0, Path::new("one"));
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn hard_links_count_once() {
+        let root = scratch("hard-links");
+        fs::create_dir(root.join("sub")).unwrap();
+        fs::write(root.join("data"), [0; 1000]).unwrap();
+        fs::hard_link(root.join("data"), root.join("again")).unwrap();
+        fs::hard_link(root.join("data"), root.join("sub/and-again")).unwrap();
+        let folders = dir_len(&root) + dir_len(&root.join("sub"));
+
+        let size = measure_now(&root, false);
+        assert_eq!((size.apparent, size.files), (folders + 1000, 1));
+
+        let tree = scan_now(&root, fs::metadata(&root).unwrap().dev(), false);
+        assert_eq!((tree.size.apparent, tree.size.files), (folders + 1000, 1));
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn folders_on_other_devices_are_skipped_unless_crossing_mounts() {
+        let root = scratch("mounts");
+        fs::create_dir_all(root.join("sub/deeper")).unwrap();
+        fs::write(root.join("top"), [0; 10]).unwrap();
+        fs::write(root.join("sub/inside"), [0; 5]).unwrap();
+
+        // Taking the root for another device makes every folder below it look
+        // like a mount point
+        let other_device = fs::metadata(&root).unwrap().dev() + 1;
+        let tree = scan_now(&root, other_device, false);
+        assert_eq!(tree.children.len(), 1);
+        assert_eq!((tree.size.apparent, tree.size.files), (dir_len(&root) + 10, 1));
+
+        let tree = scan_now(&root, other_device, true);
+        assert_eq!(tree.size.files, 2);
+        assert!(tree.find(&["sub".to_string(), "deeper".to_string()]).is_some());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,110 @@
+use futures_channel::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use notify::event::{AccessKind, AccessMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+/// Events closer together than this are delivered as one change.
+const DEBOUNCE: Duration = Duration::from_millis(150);
+/// Upper bound on how long a directory that never goes quiet can hold back a
+/// change, e.g. while a download is being written into it.
+const MAX_BATCH_DELAY: Duration = Duration::from_secs(1);
+
+/// A debounced change inside a watched directory.
+#[derive(Debug)]
+pub enum DirChange {
+    /// These paths were created, modified, renamed or deleted
+    Entries(BTreeSet<PathBuf>),
+    /// Events were lost; the whole listing must be read again
+    Rescan,
+}
+
+/// Watches the direct children of `dir`.
+///
+/// Changes arrive on the returned stream until the watcher is dropped, at
+/// which point the stream ends.
+pub fn watch_dir(dir: &Path) -> notify::Result<(RecommendedWatcher, UnboundedReceiver<DirChange>)> {
+    let (event_tx, event_rx) = std::sync::mpsc::channel();
+    let mut watcher = notify::recommended_watcher(event_tx)?;
+    watcher.watch(dir, RecursiveMode::NonRecursive)?;
+
+    let (change_tx, change_rx) = mpsc::unbounded();
+    std::thread::spawn(move || debounce(&event_rx, &change_tx));
+
+    Ok((watcher, change_rx))
+}
+
+fn debounce(events: &Receiver<notify::Result<Event>>, changes: &UnboundedSender<DirChange>) {
+    let mut touched = BTreeSet::new();
+    let mut rescan = false;
+    // When the first event of the pending batch arrived
+    let mut started: Option<Instant> = None;
+
+    loop {
+        let received = match started {
+            None => events.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            Some(started) => events.recv_timeout(DEBOUNCE.min(MAX_BATCH_DELAY.saturating_sub(started.elapsed()))),
+        };
+
+        match received {
+            Ok(Ok(event)) if event.need_rescan() => rescan = true,
+            Ok(Ok(event)) => match event.kind {
+                EventKind::Access(AccessKind::Close(AccessMode::Write)) => {
+                    touched.extend(event.paths)
+                }
+                EventKind::Access(_) => {}
+                _ => touched.extend(event.paths),
+            },
+            Ok(Err(_)) => rescan = true,
+            Err(RecvTimeoutError::Timeout) => {
+                let change = if rescan {
+                    DirChange::Rescan
+                } else {
+                    DirChange::Entries(std::mem::take(&mut touched))
+                };
+                touched.clear();
+                rescan = false;
+                started = None;
+                if changes.unbounded_send(change).is_err() {
+                    return;
+                }
+            }
+            // The watcher was dropped
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+        if started.is_none() && (rescan || !touched.is_empty()) {
+            started = Some(Instant::now());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::CreateKind;
+
+    #[test]
+    fn a_busy_directory_still_delivers_changes() {
+        let (event_tx, event_rx) = std::sync::mpsc::channel();
+        let (change_tx, mut change_rx) = mpsc::unbounded();
+        std::thread::spawn(move || debounce(&event_rx, &change_tx));
+
+        // Events keep coming faster than the debounce gap for well past the limit
+        let started = Instant::now();
+        let mut first_change = None;
+        while first_change.is_none() && started.elapsed() < MAX_BATCH_DELAY * 3 {
+            let event = Event::new(EventKind::Create(CreateKind::File)).add_path(PathBuf::from("/d/part"));
+            event_tx.send(Ok(event)).unwrap();
+            std::thread::sleep(DEBOUNCE / 3);
+            if let Ok(Some(change)) = change_rx.try_next() {
+                first_change = Some((started.elapsed(), change));
+            }
+        }
+
+        let (after, change) = first_change.expect("no change while events kept coming");
+        assert!(after < MAX_BATCH_DELAY * 2, "first change took {after:?}");
+        assert!(matches!(change, DirChange::Entries(paths) if paths.contains(Path::new("/d/part"))));
+    }
+}
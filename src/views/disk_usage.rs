@@ -0,0 +1,619 @@
+use dioxus::prelude::*;
+use futures_util::StreamExt;
+use std::cmp::{Ordering, Reverse};
+use std::path::PathBuf;
+
+use crate::views::dir_size::{scan_tree, DirSize, ScanEvent, SizeWalk, UsageNode};
+use crate::views::{format_file_size, CurrentPath, CurrentSelection};
+use crate::Route;
+
+/// Tiles drawn in the treemap; smaller items share one grey tile.
+const MAX_TILES: usize = 48;
+/// Rows in the "Largest files" list.
+const MAX_LARGEST: usize = 100;
+/// Treemap coordinates run over this area; the container has the same shape.
+const MAP_WIDTH: f64 = 300.0;
+const MAP_HEIGHT: f64 = 100.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ItemList {
+    /// Direct children of the focused folder
+    Children,
+    /// Largest files anywhere below the focused folder
+    LargestFiles,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ItemSort {
+    Name,
+    Size,
+    Files,
+}
+
+/// One row of the item list.
+#[derive(Clone, Debug, PartialEq)]
+struct ItemRow {
+    path: PathBuf,
+    /// Relative to the focused folder
+    label: String,
+    is_dir: bool,
+    size: DirSize,
+}
+
+/// One rectangle of the treemap, in map coordinates.
+#[derive(Clone, Debug, PartialEq)]
+struct Tile {
+    /// None for the tile that collects the smallest items
+    path: Option<PathBuf>,
+    label: String,
+    is_dir: bool,
+    size: u64,
+    rect: Rect,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Rect {
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+}
+
+/// Scans a directory tree and shows what takes up its space, as a treemap and
+/// as a sortable list, with drill-down into folders.
+#[component]
+pub fn DiskUsage() -> Element {
+    let CurrentPath(mut current_path) = use_context();
+    let CurrentSelection(mut selection) = use_context();
+    let mut target = use_signal(|| current_path.peek().display().to_string());
+    let mut cross_mounts = use_signal(|| false);
+    let mut scan = use_signal(|| None::<SizeWalk>);
+    let mut progress = use_signal(|| None::<DirSize>);
+    let mut error = use_signal(|| None::<String>);
+    let mut root = use_signal(|| None::<(PathBuf, UsageNode)>);
+    // Names leading from the scanned root to the folder being looked at
+    let mut focus = use_signal(Vec::<String>::new);
+    let mut list = use_signal(|| ItemList::Children);
+    let mut sort = use_signal(|| (ItemSort::Size, true));
+
+    let mut start_scan = move || {
+        let path = PathBuf::from(target());
+        error.set(None);
+        focus.set(Vec::new());
+        progress.set(Some(DirSize::default()));
+        // Replacing the previous scan stops it
+        let (walk, mut events) = scan_tree(path.clone(), cross_mounts());
+        scan.set(Some(walk));
+
+        spawn(async move {
+            while let Some(event) = events.next().await {
+                match event {
+                    ScanEvent::Progress(size) => progress.set(Some(size)),
+                    ScanEvent::Done(tree) => {
+                        root.set(Some((path.clone(), tree)));
+                        progress.set(None);
+                        scan.set(None);
+                    }
+                    ScanEvent::Failed(message) => {
+                        error.set(Some(message));
+                        progress.set(None);
+                        scan.set(None);
+                    }
+                }
+            }
+        });
+    };
+
+    let focused_path = move || {
+        root.read()
+            .as_ref()
+            .map(|(path, _)| focus.read().iter().fold(path.clone(), |path, name| path.join(name)))
+    };
+
+    let rows = use_memo(move || {
+        let root = root.read();
+        let Some((_, tree)) = root.as_ref() else {
+            return Vec::new();
+        };
+        let Some(node) = tree.find(&focus.read()) else {
+            return Vec::new();
+        };
+        let base = focused_path().unwrap_or_default();
+        let mut rows: Vec<ItemRow> = match list() {
+            ItemList::Children => node
+                .children
+                .iter()
+                .map(|child| ItemRow {
+                    path: base.join(&child.name),
+                    label: child.name.clone(),
+                    is_dir: child.is_dir,
+                    size: child.size,
+                })
+                .collect(),
+            ItemList::LargestFiles => node
+                .largest_files(MAX_LARGEST)
+                .into_iter()
+                .map(|(relative, file)| ItemRow {
+                    path: base.join(&relative),
+                    label: relative.display().to_string(),
+                    is_dir: false,
+                    size: file.size,
+                })
+                .collect(),
+        };
+        let (column, descending) = sort();
+        rows.sort_by(|a, b| {
+            let order = match column {
+                ItemSort::Name => a.label.cmp(&b.label),
+                ItemSort::Size => a.size.disk_usage.cmp(&b.size.disk_usage),
+                ItemSort::Files => a.size.files.cmp(&b.size.files),
+            };
+            if descending {
+                order.reverse()
+            } else {
+                order
+            }
+        });
+        rows
+    });
+
+    let tiles = use_memo(move || {
+        let root = root.read();
+        let Some(node) = root.as_ref().and_then(|(_, tree)| tree.find(&focus.read())) else {
+            return Vec::new();
+        };
+        let base = focused_path().unwrap_or_default();
+        treemap_tiles(node, &base)
+    });
+
+    let focused_total = root
+        .read()
+        .as_ref()
+        .and_then(|(_, tree)| tree.find(&focus.read()).map(|node| node.size));
+
+    let mut sort_by = move |column: ItemSort| {
+        let (current, descending) = sort();
+        // Names read best A to Z, sizes largest first
+        let descending = if current == column { !descending } else { column != ItemSort::Name };
+        sort.set((column, descending));
+    };
+    let sort_marker = move |column: ItemSort| match sort() {
+        (current, true) if current == column => " ▼",
+        (current, false) if current == column => " ▲",
+        _ => "",
+    };
+
+    let mut drill_into = move |path: PathBuf| {
+        let Some(names) = root.read().as_ref().and_then(|(root, _)| {
+            path.strip_prefix(root)
+                .ok()
+                .map(|relative| relative.iter().map(|name| name.to_string_lossy().into_owned()).collect())
+        }) else {
+            return;
+        };
+        focus.set(names);
+    };
+
+    // Shows the item selected in its folder
+    let mut show_in_file_manager = move |path: PathBuf| {
+        let Some(parent) = path.parent() else {
+            return;
+        };
+        current_path.set(parent.to_path_buf());
+        selection.with_mut(|selection| selection.select_only(path.clone()));
+        navigator().push(Route::FileManager {});
+    };
+
+    rsx! {
+        div { class: "min-h-screen bg-gray-50 p-6",
+            div { class: "max-w-6xl mx-auto",
+                div { class: "mb-8",
+                    h1 { class: "text-3xl font-bold text-gray-900 mb-2", "Disk Usage" }
+                    p { class: "text-gray-600", "Find out what takes up the space in a folder" }
+                }
+
+                div { class: "bg-white rounded-lg shadow-sm border p-4 mb-6 flex items-center gap-4",
+                    input {
+                        class: "flex-1 px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500 focus:border-transparent",
+                        r#type: "text",
+                        value: "{target}",
+                        oninput: move |evt| target.set(evt.value()),
+                        onkeydown: move |evt| {
+                            if evt.key() == Key::Enter {
+                                start_scan();
+                            }
+                        },
+                    }
+                    label { class: "flex items-center gap-2 text-sm text-gray-700",
+                        input {
+                            r#type: "checkbox",
+                            checked: cross_mounts(),
+                            onchange: move |evt| cross_mounts.set(evt.checked()),
+                        }
+                        "Count other file systems"
+                    }
+                    if scan.read().is_some() {
+                        button {
+                            class: "px-4 py-2 rounded-md border bg-white text-gray-700 hover:bg-gray-50 transition-colors",
+                            onclick: move |_| {
+                                scan.set(None);
+                                progress.set(None);
+                            },
+                            "Stop"
+                        }
+                    } else {
+                        button {
+                            class: "px-4 py-2 bg-blue-500 text-white rounded-md hover:bg-blue-600 transition-colors",
+                            onclick: move |_| start_scan(),
+                            "Scan"
+                        }
+                    }
+                }
+
+                if let Some(message) = error() {
+                    div { class: "mb-4 p-3 text-sm text-red-800 bg-red-50 border border-red-200 rounded-md", "{message}" }
+                }
+                if let Some(size) = progress() {
+                    div { class: "mb-4 flex items-center gap-3 text-sm text-gray-600",
+                        div { class: "animate-spin rounded-full h-4 w-4 border-b-2 border-blue-500" }
+                        "Scanning… {size.files} files, {format_file_size(size.disk_usage)} so far"
+                    }
+                }
+
+                if let (Some(total), Some((root_path, _))) = (focused_total, root.read().as_ref()) {
+                    // Breadcrumb back up to the scanned folder
+                    div { class: "mb-2 flex flex-wrap items-center gap-1 text-sm",
+                        button {
+                            class: "text-blue-600 hover:text-blue-800",
+                            onclick: move |_| focus.set(Vec::new()),
+                            "{root_path.display()}"
+                        }
+                        for (depth, name) in focus.read().iter().enumerate() {
+                            span { key: "{depth}-sep", class: "text-gray-400", "/" }
+                            button {
+                                key: "{depth}",
+                                class: "text-blue-600 hover:text-blue-800",
+                                onclick: move |_| focus.write().truncate(depth + 1),
+                                "{name}"
+                            }
+                        }
+                        span { class: "ml-auto text-gray-500",
+                            "{format_file_size(total.disk_usage)} on disk · {format_file_size(total.apparent)} apparent · {total.files} files"
+                        }
+                    }
+
+                    div {
+                        class: "relative w-full mb-6 bg-white rounded-lg shadow-sm border overflow-hidden",
+                        style: "aspect-ratio: {MAP_WIDTH} / {MAP_HEIGHT};",
+                        for tile in tiles() {
+                            div {
+                                key: "{tile.label}",
+                                class: format!(
+                                    "absolute border border-white overflow-hidden p-1 text-xs {}",
+                                    match (&tile.path, tile.is_dir) {
+                                        (None, _) => "bg-gray-300 text-gray-700",
+                                        (Some(_), true) => "bg-blue-400 text-white cursor-pointer hover:bg-blue-500",
+                                        (Some(_), false) => "bg-amber-300 text-gray-900 cursor-pointer hover:bg-amber-400",
+                                    }
+                                ),
+                                style: format!(
+                                    "left: {}%; top: {}%; width: {}%; height: {}%;",
+                                    tile.rect.x / MAP_WIDTH * 100.0,
+                                    tile.rect.y / MAP_HEIGHT * 100.0,
+                                    tile.rect.w / MAP_WIDTH * 100.0,
+                                    tile.rect.h / MAP_HEIGHT * 100.0
+                                ),
+                                title: "{tile.label} · {format_file_size(tile.size)}",
+                                onclick: {
+                                    let tile = tile.clone();
+                                    move |_| match tile.path.clone() {
+                                        Some(path) if tile.is_dir => drill_into(path),
+                                        Some(path) => show_in_file_manager(path),
+                                        None => {}
+                                    }
+                                },
+                                // Labels only where they have room
+                                if tile.rect.w > 12.0 && tile.rect.h > 6.0 {
+                                    p { class: "truncate font-medium", "{tile.label}" }
+                                    p { class: "truncate opacity-80", "{format_file_size(tile.size)}" }
+                                }
+                            }
+                        }
+                    }
+
+                    div { class: "bg-white rounded-lg shadow-sm border overflow-hidden",
+                        div { class: "flex items-center gap-2 px-4 py-2 border-b bg-gray-50 text-sm",
+                            for (kind, label) in [(ItemList::Children, "In this folder"), (ItemList::LargestFiles, "Largest files")] {
+                                button {
+                                    key: "{label}",
+                                    class: if list() == kind { "px-3 py-1 rounded-md bg-blue-500 text-white" } else { "px-3 py-1 rounded-md text-gray-700 hover:bg-gray-100" },
+                                    onclick: move |_| list.set(kind),
+                                    "{label}"
+                                }
+                            }
+                        }
+                        table { class: "w-full text-sm",
+                            thead { class: "bg-gray-50",
+                                tr {
+                                    th {
+                                        class: "px-4 py-2 text-left font-medium text-gray-500 cursor-pointer select-none hover:text-gray-900",
+                                        onclick: move |_| sort_by(ItemSort::Name),
+                                        "Name{sort_marker(ItemSort::Name)}"
+                                    }
+                                    th {
+                                        class: "w-32 px-4 py-2 text-right font-medium text-gray-500 cursor-pointer select-none hover:text-gray-900",
+                                        onclick: move |_| sort_by(ItemSort::Size),
+                                        "Size{sort_marker(ItemSort::Size)}"
+                                    }
+                                    th { class: "w-40 px-4 py-2 text-left font-medium text-gray-500", "Share" }
+                                    th {
+                                        class: "w-24 px-4 py-2 text-right font-medium text-gray-500 cursor-pointer select-none hover:text-gray-900",
+                                        onclick: move |_| sort_by(ItemSort::Files),
+                                        "Files{sort_marker(ItemSort::Files)}"
+                                    }
+                                    th { class: "w-24" }
+                                }
+                            }
+                            tbody { class: "divide-y",
+                                for row in rows() {
+                                    tr { key: "{row.path.display()}", class: "hover:bg-gray-50",
+                                        td { class: "px-4 py-2 break-all",
+                                            if row.is_dir {
+                                                button {
+                                                    class: "text-blue-600 hover:text-blue-800 font-medium text-left",
+                                                    onclick: {
+                                                        let path = row.path.clone();
+                                                        move |_| drill_into(path.clone())
+                                                    },
+                                                    "📁 {row.label}"
+                                                }
+                                            } else {
+                                                span { class: "text-gray-900", "📄 {row.label}" }
+                                            }
+                                        }
+                                        td {
+                                            class: "px-4 py-2 text-right text-gray-700 whitespace-nowrap",
+                                            title: "{format_file_size(row.size.apparent)} apparent",
+                                            "{format_file_size(row.size.disk_usage)}"
+                                        }
+                                        td { class: "px-4 py-2",
+                                            div { class: "h-2 bg-gray-100 rounded",
+                                                div {
+                                                    class: "h-2 bg-blue-400 rounded",
+                                                    style: "width: {share(row.size.disk_usage, total.disk_usage)}%;",
+                                                }
+                                            }
+                                        }
+                                        td { class: "px-4 py-2 text-right text-gray-500", "{row.size.files}" }
+                                        td { class: "px-4 py-2 text-right",
+                                            button {
+                                                class: "text-xs text-gray-500 hover:text-gray-900",
+                                                onclick: {
+                                                    let path = row.path.clone();
+                                                    move |_| show_in_file_manager(path.clone())
+                                                },
+                                                "Show"
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                } else if scan.read().is_none() && error.read().is_none() {
+                    div { class: "p-8 text-center text-gray-500", "Choose a folder and press Scan" }
+                }
+            }
+        }
+    }
+}
+
+/// `part` as a percentage of `whole`.
+fn share(part: u64, whole: u64) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64 * 100.0
+    }
+}
+
+/// Tiles for the children of `node`, which lives at `base`. The largest
+/// children get their own tile, the rest are lumped together.
+fn treemap_tiles(node: &UsageNode, base: &std::path::Path) -> Vec<Tile> {
+    // Children come largest first
+    let sized: Vec<&UsageNode> = node.children.iter().filter(|child| child.size.disk_usage > 0).collect();
+    let (shown, rest) = sized.split_at(sized.len().min(MAX_TILES));
+
+    let mut tiles: Vec<Tile> = shown
+        .iter()
+        .map(|child| Tile {
+            path: Some(base.join(&child.name)),
+            label: child.name.clone(),
+            is_dir: child.is_dir,
+            size: child.size.disk_usage,
+            rect: Rect::default(),
+        })
+        .collect();
+    if !rest.is_empty() {
+        tiles.push(Tile {
+            path: None,
+            label: format!("{} smaller items", rest.len()),
+            is_dir: false,
+            size: rest.iter().map(|child| child.size.disk_usage).sum(),
+            rect: Rect::default(),
+        });
+    }
+    // The lumped tile may outgrow the last ones; squarifying wants them sorted
+    tiles.sort_by_key(|tile| Reverse(tile.size));
+
+    let sizes: Vec<u64> = tiles.iter().map(|tile| tile.size).collect();
+    let area = Rect {
+        x: 0.0,
+        y: 0.0,
+        w: MAP_WIDTH,
+        h: MAP_HEIGHT,
+    };
+    for (tile, rect) in tiles.iter_mut().zip(squarify(&sizes, area)) {
+        tile.rect = rect;
+    }
+    tiles
+}
+
+/// Squarified treemap layout (Bruls, Huizing and van Wijk): splits `area` into
+/// one rectangle per size, in order, keeping them as close to square as it can.
+/// `sizes` must be sorted largest first.
+fn squarify(sizes: &[u64], area: Rect) -> Vec<Rect> {
+    let total: u64 = sizes.iter().sum();
+    if total == 0 {
+        return Vec::new();
+    }
+    let scale = area.w * area.h / total as f64;
+    let areas: Vec<f64> = sizes.iter().map(|&size| size as f64 * scale).collect();
+
+    let mut rects = Vec::with_capacity(areas.len());
+    let mut free = area;
+    let mut start = 0;
+    while start < areas.len() {
+        let side = free.w.min(free.h);
+        // Grow the row while that makes its worst aspect ratio better
+        let mut end = start + 1;
+        while end < areas.len()
+            && worst_ratio(&areas[start..=end], side).partial_cmp(&worst_ratio(&areas[start..end], side))
+                != Some(Ordering::Greater)
+        {
+            end += 1;
+        }
+
+        let row = &areas[start..end];
+        let row_area: f64 = row.iter().sum();
+        if free.w >= free.h {
+            // A column along the left edge
+            let width = row_area / free.h;
+            let mut y = free.y;
+            for &item in row {
+                let height = item / width;
+                rects.push(Rect { x: free.x, y, w: width, h: height });
+                y += height;
+            }
+            free.x += width;
+            free.w -= width;
+        } else {
+            // A row along the top edge
+            let height = row_area / free.w;
+            let mut x = free.x;
+            for &item in row {
+                let width = item / height;
+                rects.push(Rect { x, y: free.y, w: width, h: height });
+                x += width;
+            }
+            free.y += height;
+            free.h -= height;
+        }
+        start = end;
+    }
+    rects
+}
+
+/// The most elongated aspect ratio in a row of `areas` laid along `side`.
+fn worst_ratio(areas: &[f64], side: f64) -> f64 {
+    let sum: f64 = areas.iter().sum();
+    let (min, max) = areas
+        .iter()
+        .fold((f64::MAX, 0.0_f64), |(min, max), &area| (min.min(area), max.max(area)));
+    let side = side * side;
+    (side * max / (sum * sum)).max(sum * sum / (side * min))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    const EPSILON: f64 = 1e-6;
+
+    fn area() -> Rect {
+        Rect { x: 0.0, y: 0.0, w: MAP_WIDTH, h: MAP_HEIGHT }
+    }
+
+    fn overlap(a: &Rect, b: &Rect) -> f64 {
+        let w = (a.x + a.w).min(b.x + b.w) - a.x.max(b.x);
+        let h = (a.y + a.h).min(b.y + b.h) - a.y.max(b.y);
+        w.max(0.0) * h.max(0.0)
+    }
+
+    fn child(name: &str, disk_usage: u64) -> UsageNode {
+        UsageNode {
+            name: name.to_string(),
+            size: DirSize { disk_usage, ..Default::default() },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn tiles_cover_the_map_without_overlapping() {
+        let sizes = [500, 300, 120, 80, 80, 40, 9, 1];
+        let rects = squarify(&sizes, area());
+        assert_eq!(rects.len(), sizes.len());
+
+        let covered: f64 = rects.iter().map(|rect| rect.w * rect.h).sum();
+        assert!((covered - MAP_WIDTH * MAP_HEIGHT).abs() < EPSILON);
+        for (i, rect) in rects.iter().enumerate() {
+            assert!(rect.x >= -EPSILON && rect.y >= -EPSILON, "{rect:?}");
+            assert!(rect.x + rect.w <= MAP_WIDTH + EPSILON && rect.y + rect.h <= MAP_HEIGHT + EPSILON, "{rect:?}");
+            for other in &rects[i + 1..] {
+                assert!(overlap(rect, other) < EPSILON, "{rect:?} overlaps {other:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn tile_areas_follow_sizes() {
+        let sizes = [600, 200, 100, 100];
+        let total: u64 = sizes.iter().sum();
+        for (size, rect) in sizes.iter().zip(squarify(&sizes, area())) {
+            let expected = MAP_WIDTH * MAP_HEIGHT * *size as f64 / total as f64;
+            assert!((rect.w * rect.h - expected).abs() < EPSILON, "{rect:?} for {size}");
+        }
+        assert!(squarify(&[0, 0], area()).is_empty());
+    }
+
+    #[test]
+    fn rows_stop_growing_once_tiles_get_thinner() {
+        // A lone square is as good as it gets
+        assert!((worst_ratio(&[100.0], 10.0) - 1.0).abs() < EPSILON);
+        assert!(worst_ratio(&[100.0, 100.0], 10.0) > worst_ratio(&[100.0], 10.0));
+        // Equal halves of a 2:1 area end up as two squares
+        let rects = squarify(&[1, 1], Rect { x: 0.0, y: 0.0, w: 20.0, h: 10.0 });
+        assert!(rects.iter().all(|rect| (rect.w - 10.0).abs() < EPSILON && (rect.h - 10.0).abs() < EPSILON));
+    }
+
+    #[test]
+    fn the_smallest_items_share_a_tile() {
+        let mut node = UsageNode { is_dir: true, ..Default::default() };
+        node.children = (0..MAX_TILES + 5).map(|i| child(&format!("f{i}"), 1000 - i as u64)).collect();
+        node.children.push(child("empty", 0));
+
+        let tiles = treemap_tiles(&node, Path::new("/base"));
+        assert_eq!(tiles.len(), MAX_TILES + 1);
+        let lumped: Vec<&Tile> = tiles.iter().filter(|tile| tile.path.is_none()).collect();
+        assert_eq!(lumped.len(), 1);
+        assert_eq!(lumped[0].label, "5 smaller items");
+        assert_eq!(lumped[0].size, (1000 - MAX_TILES as u64 - 4..=1000 - MAX_TILES as u64).sum::<u64>());
+        // Empty items get no tile at all, and the rest are laid out largest
+        // first, so five nearly full-size items together come before f0
+        assert!(tiles.iter().all(|tile| tile.label != "empty"));
+        assert_eq!(tiles[0].path, None);
+        assert_eq!(tiles[1].label, "f0");
+        assert!(tiles.windows(2).all(|pair| pair[0].size >= pair[1].size));
+    }
+
+    #[test]
+    fn few_items_get_a_tile_each() {
+        let mut node = UsageNode { is_dir: true, ..Default::default() };
+        node.children = vec![child("a", 3), child("b", 1)];
+
+        let tiles = treemap_tiles(&node, Path::new("/base"));
+        let paths: Vec<Option<PathBuf>> = tiles.into_iter().map(|tile| tile.path).collect();
+        assert_eq!(paths, [Some(PathBuf::from("/base/a")), Some(PathBuf::from("/base/b"))]);
+    }
+}
@@ -0,0 +1,337 @@
+use dioxus::html::input_data::MouseButton;
+use dioxus::prelude::*;
+use std::path::{Component, Path, PathBuf};
+
+use crate::views::text_file::{Encoding, LineEnding, TextFile};
+use crate::views::CurrentPath;
+use crate::Route;
+
+/// What the user asked for while there were unsaved changes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Pending {
+    Close,
+    Reload,
+    /// Going somewhere else in the app; the destination is in [`UnsavedEdits`]
+    Leave,
+}
+
+/// The editor route with unsaved changes, if any, and where the user tried to
+/// go from it. The router asks [`UnsavedEdits::hold`] before every navigation,
+/// so navbar links and search results can't drop the changes either.
+#[derive(Clone, Copy)]
+pub struct UnsavedEdits {
+    editor: Signal<Option<Route>>,
+    leaving: Signal<Option<Route>>,
+}
+
+impl UnsavedEdits {
+    /// Sends navigation to `to` back to the editor while it has unsaved
+    /// changes, remembering `to` so the editor can ask first.
+    pub fn hold(&self, to: Route) -> Option<NavigationTarget<Route>> {
+        let editor = self.editor.peek().clone()?;
+        if to == editor {
+            return None;
+        }
+        let mut leaving = self.leaving;
+        leaving.set(Some(to));
+        Some(editor.into())
+    }
+
+    /// Lets navigation through again, once the changes are saved or discarded.
+    fn release(&self) {
+        let (mut editor, mut leaving) = (self.editor, self.leaving);
+        editor.set(None);
+        leaving.set(None);
+    }
+}
+
+pub fn use_unsaved_edits_provider() -> UnsavedEdits {
+    use_context_provider(|| UnsavedEdits {
+        editor: Signal::new(None),
+        leaving: Signal::new(None),
+    })
+}
+
+impl Route {
+    /// The editor route for the file at the absolute `path`.
+    pub fn editor(path: &Path) -> Route {
+        let segments = path
+            .components()
+            .filter(|component| !matches!(component, Component::RootDir))
+            .map(|component| component.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        Route::Editor { segments }
+    }
+}
+
+/// Editor for small text files, opened from FileManager. The route's segments
+/// are the components of the file's absolute path.
+#[component]
+pub fn Editor(segments: Vec<String>) -> Element {
+    let path = format!("/{}", segments.join("/"));
+    let CurrentPath(mut current_path) = use_context();
+    let unsaved = use_context::<UnsavedEdits>();
+    // The route's path, kept in a signal so the handlers below stay Copy
+    let mut file_path = use_signal(PathBuf::new);
+    let mut file = use_signal(|| None::<Result<TextFile, String>>);
+    let mut text = use_signal(String::new);
+    let mut encoding = use_signal(|| Encoding::Utf8);
+    let mut line_ending = use_signal(|| LineEnding::Lf);
+    let mut message = use_signal(|| None::<Result<String, String>>);
+    let mut pending = use_signal(|| None::<Pending>);
+    // Set when saving found the file changed by another program
+    let mut conflict = use_signal(|| false);
+
+    let mut load = move || {
+        let opened = TextFile::open(&file_path.peek());
+        if let Ok(opened) = &opened {
+            text.set(opened.text.clone());
+            encoding.set(opened.encoding);
+            line_ending.set(opened.line_ending);
+        }
+        file.set(Some(opened));
+        pending.set(None);
+        conflict.set(false);
+    };
+    use_effect(use_reactive((&path,), move |(path,)| {
+        message.set(None);
+        file_path.set(PathBuf::from(path));
+        load();
+    }));
+
+    let dirty = use_memo(move || match &*file.read() {
+        Some(Ok(opened)) => {
+            *text.read() != opened.text || encoding() != opened.encoding || line_ending() != opened.line_ending
+        }
+        _ => false,
+    });
+
+    // Keep the router from leaving while there are unsaved changes, and ask
+    // when it was told to
+    use_effect(use_reactive((&segments,), move |(segments,)| {
+        let mut editor = unsaved.editor;
+        editor.set(dirty().then_some(Route::Editor { segments }));
+    }));
+    use_effect(move || {
+        if unsaved.leaving.read().is_some() {
+            pending.set(Some(Pending::Leave));
+        }
+    });
+    use_drop(move || unsaved.release());
+
+    let mut save = move |overwrite: bool| {
+        let mut file = file.write();
+        let Some(Ok(opened)) = file.as_mut() else {
+            return;
+        };
+        if !overwrite && opened.changed_on_disk() {
+            conflict.set(true);
+            return;
+        }
+        opened.encoding = encoding();
+        opened.line_ending = line_ending();
+        match opened.save(&text.read()) {
+            Ok(()) => message.set(Some(Ok(format!("Saved {}", opened.path.display())))),
+            Err(err) => message.set(Some(Err(format!("Could not save: {err}")))),
+        }
+        conflict.set(false);
+    };
+
+    let mut close = move || {
+        if let Some(parent) = file_path().parent() {
+            current_path.set(parent.to_path_buf());
+        }
+        navigator().push(Route::FileManager {});
+    };
+
+    // Carries out `action`, asking first if that would lose changes
+    let mut guarded = move |action: Pending, dirty: bool| {
+        if dirty && pending() != Some(action) {
+            pending.set(Some(action));
+            return;
+        }
+        pending.set(None);
+        match action {
+            Pending::Close => {
+                unsaved.release();
+                close();
+            }
+            Pending::Reload => {
+                message.set(None);
+                load();
+            }
+            Pending::Leave => {
+                let to = unsaved.leaving.peek().clone();
+                unsaved.release();
+                if let Some(to) = to {
+                    navigator().push(to);
+                }
+            }
+        }
+    };
+
+    let (line_count, mixed_endings) = match &*file.read() {
+        Some(Ok(opened)) => (text.read().lines().count(), opened.mixed_endings),
+        _ => (0, false),
+    };
+
+    rsx! {
+        div {
+            class: "min-h-screen bg-gray-50 p-6",
+            onmousedown: move |evt| {
+                if evt.trigger_button() == Some(MouseButton::Fourth) {
+                    guarded(Pending::Close, dirty());
+                }
+            },
+            div { class: "max-w-6xl mx-auto",
+                div { class: "mb-4 flex items-end justify-between gap-4",
+                    div { class: "min-w-0",
+                        h1 { class: "text-3xl font-bold text-gray-900 mb-2",
+                            "Edit"
+                            if dirty() {
+                                span { class: "text-gray-400", " •" }
+                            }
+                        }
+                        p { class: "text-gray-600 truncate", "{path}" }
+                    }
+                    div { class: "flex gap-2",
+                        button {
+                            class: "px-3 py-1 rounded-md border bg-white text-gray-700 hover:bg-gray-50 transition-colors",
+                            onclick: move |_| guarded(Pending::Reload, dirty()),
+                            "Reload"
+                        }
+                        button {
+                            class: "px-3 py-1 rounded-md border bg-white text-gray-700 hover:bg-gray-50 transition-colors",
+                            onclick: move |_| guarded(Pending::Close, dirty()),
+                            "Close"
+                        }
+                        button {
+                            class: "px-3 py-1 rounded-md bg-blue-500 text-white hover:bg-blue-600 transition-colors disabled:opacity-50 disabled:cursor-not-allowed",
+                            disabled: !dirty(),
+                            onclick: move |_| save(false),
+                            "Save"
+                        }
+                    }
+                }
+
+                if let Some(action) = pending() {
+                    div { class: "flex items-center justify-between gap-4 mb-2 p-3 text-sm bg-amber-50 border border-amber-200 rounded-md",
+                        span { class: "text-amber-800", "You have unsaved changes. Discard them?" }
+                        div { class: "flex gap-2",
+                            button {
+                                class: "px-3 py-1 rounded-md bg-red-600 text-white hover:bg-red-700 transition-colors",
+                                onclick: move |_| guarded(action, dirty()),
+                                match action {
+                                    Pending::Close => "Discard and close",
+                                    Pending::Reload => "Discard and reload",
+                                    Pending::Leave => "Discard and leave",
+                                }
+                            }
+                            button {
+                                class: "px-3 py-1 rounded-md border bg-white text-gray-700 hover:bg-gray-50 transition-colors",
+                                onclick: move |_| {
+                                    pending.set(None);
+                                    let mut leaving = unsaved.leaving;
+                                    leaving.set(None);
+                                },
+                                "Keep editing"
+                            }
+                        }
+                    }
+                }
+                if conflict() {
+                    div { class: "flex items-center justify-between gap-4 mb-2 p-3 text-sm bg-amber-50 border border-amber-200 rounded-md",
+                        span { class: "text-amber-800", "Another program changed the file since it was opened." }
+                        div { class: "flex gap-2",
+                            button {
+                                class: "px-3 py-1 rounded-md bg-red-600 text-white hover:bg-red-700 transition-colors",
+                                onclick: move |_| save(true),
+                                "Overwrite"
+                            }
+                            button {
+                                class: "px-3 py-1 rounded-md border bg-white text-gray-700 hover:bg-gray-50 transition-colors",
+                                onclick: move |_| conflict.set(false),
+                                "Cancel"
+                            }
+                        }
+                    }
+                }
+                match message() {
+                    Some(Ok(text)) => rsx! {
+                        div { class: "mb-2 p-3 text-sm text-green-800 bg-green-50 border border-green-200 rounded-md break-all", "{text}" }
+                    },
+                    Some(Err(text)) => rsx! {
+                        div { class: "mb-2 p-3 text-sm text-red-800 bg-red-50 border border-red-200 rounded-md break-all", "{text}" }
+                    },
+                    None => rsx! {},
+                }
+
+                match &*file.read() {
+                    None => rsx! {},
+                    Some(Err(err)) => rsx! {
+                        div { class: "p-4 text-red-600 bg-red-50 border border-red-200 rounded-md",
+                            "This file can't be edited here: {err}"
+                        }
+                    },
+                    Some(Ok(_)) => rsx! {
+                        div { class: "bg-white rounded-lg shadow-sm border overflow-hidden",
+                            textarea {
+                                class: "w-full h-[70vh] p-4 font-mono text-sm text-gray-900 resize-none focus:outline-none",
+                                spellcheck: false,
+                                value: "{text}",
+                                oninput: move |evt| text.set(evt.value()),
+                                onkeydown: move |evt| {
+                                    let modifiers = evt.modifiers();
+                                    let save_key = matches!(evt.key(), Key::Character(key) if key.eq_ignore_ascii_case("s"));
+                                    if save_key && (modifiers.ctrl() || modifiers.meta()) {
+                                        evt.prevent_default();
+                                        save(false);
+                                    }
+                                },
+                            }
+                            div { class: "flex items-center gap-4 px-4 py-2 border-t bg-gray-50 text-sm text-gray-600",
+                                span { "{line_count} lines" }
+                                if mixed_endings {
+                                    span { class: "text-amber-700", "Mixed line endings; saving makes them all the same" }
+                                }
+                                div { class: "flex-1" }
+                                select {
+                                    class: "px-2 py-0.5 border border-gray-300 rounded-md",
+                                    title: "Encoding",
+                                    onchange: move |evt| {
+                                        if let Some(chosen) = Encoding::ALL.into_iter().find(|e| e.label() == evt.value()) {
+                                            encoding.set(chosen);
+                                        }
+                                    },
+                                    for option_encoding in Encoding::ALL {
+                                        option {
+                                            value: option_encoding.label(),
+                                            selected: encoding() == option_encoding,
+                                            "{option_encoding.label()}"
+                                        }
+                                    }
+                                }
+                                select {
+                                    class: "px-2 py-0.5 border border-gray-300 rounded-md",
+                                    title: "Line endings",
+                                    onchange: move |evt| {
+                                        if let Some(chosen) = LineEnding::ALL.into_iter().find(|e| e.label() == evt.value()) {
+                                            line_ending.set(chosen);
+                                        }
+                                    },
+                                    for option_ending in LineEnding::ALL {
+                                        option {
+                                            value: option_ending.label(),
+                                            selected: line_ending() == option_ending,
+                                            "{option_ending.label()}"
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    },
+                }
+            }
+        }
+    }
+}
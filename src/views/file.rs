@@ -1,62 +1,1205 @@
+use dioxus::html::input_data::MouseButton;
+use dioxus::html::HasFileData;
 use dioxus::prelude::*;
+use futures_util::StreamExt;
+use notify::RecommendedWatcher;
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
+use std::os::unix::fs::MetadataExt;
 use std::path::{Path, PathBuf};
 use std::fs;
+use std::rc::Rc;
+use std::time::{Duration, Instant, SystemTime};
 
-use crate::components::AddFile;
+use crate::components::{AddFile, BatchRename, CommandPalette, FileInfo, JumpPalette, PathBar, PlacesSidebar, PreviewPane, TabBar};
+use crate::views::columns::{format_permissions, group_name, user_name, Column, ColumnLayouts, ListingLayout, SortOrder};
+use crate::views::commands::{Command, CommandState, Commands};
+use crate::views::dir_size::{measure_dirs, DirSize, DirSizeSettings, SizeWalk};
+use crate::views::dir_watcher::{watch_dir, DirChange};
+use crate::views::frecency::Visits;
+use crate::views::keybindings::{Chord, Keybindings, Lookup, Preset};
+use crate::views::time_format::{format_full, TimeFormat};
+use crate::views::operations::rename;
+use crate::views::operations::{unique_name, JobKind, JobQueue, JobRequest, JournalEntry, Operation};
+use crate::views::panes::{Clipboard, Panes, Side};
+use crate::views::places::Bookmarks;
+use crate::views::tabs::Tabs;
+use crate::views::{CurrentSelection, Selection};
+use crate::Route;
+
+/// Rows PageUp and PageDown move the cursor by.
+const PAGE_ROWS: isize = 10;
+
+/// Pause after which typed letters start a new type-ahead search.
+const TYPE_AHEAD_RESET: Duration = Duration::from_secs(1);
+
+/// The directory shown by [`FileManager`], shared so other views (e.g. the
+/// navbar search) can open a location in it.
+#[derive(Clone, Copy)]
+pub struct CurrentPath(pub Signal<PathBuf>);
 
 #[component]
 pub fn FileManager() -> Element {
-    let mut current_path = use_signal(|| PathBuf::from("."));
+    let panes = use_context::<Panes>();
+    let split = panes.split;
+    let commands = use_context::<Commands>();
+    let keybindings = use_context::<Keybindings>();
+    let keymap = keybindings.keymap;
+    let mut keys_error = keybindings.error;
+    let mut show_jump = use_signal(|| false);
+    let mut show_commands = use_signal(|| false);
+
+    use_effect(move || match commands.take_if(Command::is_page_wide) {
+        Some(Command::ToggleSplit) => panes.toggle_split(),
+        Some(Command::JumpTo) => show_jump.set(true),
+        Some(Command::ShowCommands) => show_commands.set(true),
+        _ => {}
+    });
+
+    let shortcut = move |command: Command| keymap.read().shortcut(command).map(|keys| format!(" ({keys})")).unwrap_or_default();
+
+    rsx! {
+        div {
+            class: "min-h-screen bg-gray-50 p-6",
+            // The palettes open from anywhere in the page; plain keys are
+            // left to whatever has the focus, which may be taking text
+            onkeydown: move |evt| {
+                let Some(chord) = Chord::from_event(&evt.key(), evt.modifiers()) else {
+                    return;
+                };
+                if !chord.has_command_modifier() {
+                    return;
+                }
+                let Lookup::Command(command) = keymap.read().lookup(&[chord]) else {
+                    return;
+                };
+                match command {
+                    Command::JumpTo => show_jump.toggle(),
+                    Command::ShowCommands => show_commands.toggle(),
+                    Command::ToggleSplit => panes.toggle_split(),
+                    _ => return,
+                }
+                evt.prevent_default();
+            },
+            div { class: if split() { "max-w-screen-2xl mx-auto" } else { "max-w-7xl mx-auto" },
+                // Header
+                div { class: "mb-8 flex items-end justify-between gap-4",
+                    div {
+                        h1 { class: "text-3xl font-bold text-gray-900 mb-2", "File Manager" }
+                        p { class: "text-gray-600", "Browse and navigate your files and directories" }
+                    }
+                    div { class: "flex gap-2",
+                        select {
+                            class: "px-2 py-1 rounded-md border bg-white text-gray-700",
+                            title: "Key bindings, on top of which keybindings.toml adds your own",
+                            onchange: move |evt| {
+                                if let Some(preset) = Preset::ALL.into_iter().find(|preset| preset.label() == evt.value()) {
+                                    keybindings.set_preset(preset);
+                                }
+                            },
+                            for preset in Preset::ALL {
+                                option {
+                                    value: preset.label(),
+                                    selected: *keybindings.preset.read() == preset,
+                                    "Keys: {preset.label()}"
+                                }
+                            }
+                        }
+                        button {
+                            class: "px-3 py-1 rounded-md border bg-white text-gray-700 hover:bg-gray-50 transition-colors",
+                            title: "Run any action{shortcut(Command::ShowCommands)}",
+                            onclick: move |_| show_commands.set(true),
+                            "Commands"
+                        }
+                        button {
+                            class: "px-3 py-1 rounded-md border bg-white text-gray-700 hover:bg-gray-50 transition-colors",
+                            title: "Jump to a visited folder{shortcut(Command::JumpTo)}",
+                            onclick: move |_| show_jump.set(true),
+                            "Jump to…"
+                        }
+                        button {
+                            class: if split() { "px-3 py-1 rounded-md border bg-blue-50 text-blue-700 border-blue-300 transition-colors" } else { "px-3 py-1 rounded-md border bg-white text-gray-700 hover:bg-gray-50 transition-colors" },
+                            title: "Show two folders side by side",
+                            onclick: move |_| panes.toggle_split(),
+                            "Split view"
+                        }
+                    }
+                }
+                if let Some(error) = keys_error() {
+                    div { class: "flex items-center justify-between gap-4 mb-4 p-3 text-sm bg-amber-50 border border-amber-200 rounded-md",
+                        span { class: "text-amber-800 break-all", "{error}" }
+                        button {
+                            class: "text-amber-600 hover:text-amber-800",
+                            onclick: move |_| keys_error.set(None),
+                            "✕"
+                        }
+                    }
+                }
+                if show_jump() {
+                    JumpPalette {
+                        current: panes.path(panes.active.cloned()).cloned(),
+                        on_jump: move |path| {
+                            let mut target = panes.path(*panes.active.peek());
+                            target.set(path);
+                            show_jump.set(false);
+                        },
+                        on_close: move |_| show_jump.set(false),
+                    }
+                }
+                if show_commands() {
+                    CommandPalette { on_close: move |_| show_commands.set(false) }
+                }
+
+                TabBar {}
+
+                div { class: "flex items-start gap-4",
+                    PlacesSidebar {}
+                    div { class: if split() { "flex-1 min-w-0 grid grid-cols-2 gap-4 items-start" } else { "flex-1 min-w-0" },
+                        FilePane { side: Side::Left }
+                        if split() {
+                            RightPane {}
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The right pane, listing its own location instead of the active tab's.
+#[component]
+fn RightPane() -> Element {
+    let panes = use_context::<Panes>();
+    use_context_provider(|| CurrentPath(panes.path(Side::Right)));
+    use_context_provider(|| CurrentSelection(panes.right_selection()));
+
+    rsx! {
+        FilePane { side: Side::Right }
+    }
+}
+
+/// One folder listing with its toolbar, for whichever location the nearest
+/// [`CurrentPath`] holds.
+#[component]
+fn FilePane(side: Side) -> Element {
+    let CurrentPath(mut current_path) = use_context();
+    let CurrentSelection(mut selection) = use_context();
+    let jobs = use_context::<JobQueue>();
+    let tabs = use_context::<Tabs>();
+    let panes = use_context::<Panes>();
+    let bookmarks = use_context::<Bookmarks>();
+    let visits = use_context::<Visits>();
+    let commands = use_context::<Commands>();
+    let keymap = use_context::<Keybindings>().keymap;
+    let shortcut = move |command: Command| keymap.read().shortcut(command).map(|keys| format!(" ({keys})")).unwrap_or_default();
+    let layouts = use_context::<ColumnLayouts>();
+    let layout = use_memo(move || layouts.layout_for(&current_path()));
+    let split = panes.split;
+    let mut active_pane = panes.active;
+    let mut dragged_folder = bookmarks.dragged;
+    let mut show_columns = use_signal(|| false);
+    let mut clipboard = panes.clipboard;
+    let mut confirm_delete = use_signal(|| false);
+    let mut action_error = use_signal(|| None::<String>);
+    let mut show_add_files = use_signal(|| false);
+    let mut drop_target = use_signal(|| false);
+    // Entry whose name is being edited in place, and the edited text
+    let mut renaming = use_signal(|| None::<PathBuf>);
+    let mut rename_text = use_signal(String::new);
+    let mut batch_rename = use_signal(|| None::<Vec<PathBuf>>);
+    let mut show_preview = use_signal(|| false);
     
-    let mut dir_contents = use_resource(move || {
+    let mut dir_contents = use_signal(|| None::<Result<Vec<DirEntry>, std::io::Error>>);
+    let mut dir_watch = use_signal(|| None::<RecommendedWatcher>);
+    // Recursive sizes of the listed directories, filled in as they are measured
+    let mut dir_sizes = use_signal(HashMap::<PathBuf, DirSize>::new);
+    let mut size_walk = use_signal(|| None::<SizeWalk>);
+    let mut listing = use_signal(|| None::<Rc<MountedData>>);
+    // Keys typed so far of a multi-key binding like "g g"
+    let mut pending_keys = use_signal(Vec::<Chord>::new);
+    // Type-ahead find: the name typed so far, when it was last typed, and
+    // whether Find holds every key until Enter or Escape
+    let mut find_text = use_signal(String::new);
+    let mut typed_at = use_signal(|| None::<Instant>);
+    let mut finding = use_signal(|| false);
+
+    // Switching panes from the keyboard moves the focus along with it
+    use_effect(move || {
+        if split() && active_pane() == side {
+            if let Some(listing) = listing.peek().clone() {
+                spawn(async move {
+                    let _ = listing.set_focus(true).await;
+                });
+            }
+        }
+    });
+
+    // Keep the keyboard cursor's row in view as it moves
+    use_effect(move || {
+        if selection.read().cursor().is_some() {
+            document::eval(&format!(
+                "document.querySelector('[data-pane=\"{side:?}\"] tr[data-cursor]')?.scrollIntoView({{ block: 'nearest' }})"
+            ));
+        }
+    });
+
+    // Replacing the walk stops the previous one, so whatever it hadn't
+    // finished is measured again after the newly requested folders
+    let mut measure = move |mut dirs: Vec<PathBuf>, cross_mounts: bool| {
+        if dirs.is_empty() {
+            return;
+        }
+        let path = current_path.peek().clone();
+        dir_sizes.with_mut(|sizes| {
+            let unfinished: Vec<PathBuf> = sizes
+                .iter()
+                .filter(|(dir, size)| !size.complete && !dirs.contains(dir))
+                .map(|(dir, _)| dir.clone())
+                .collect();
+            dirs.extend(unfinished);
+            for dir in &dirs {
+                sizes.insert(dir.clone(), DirSize::default());
+            }
+        });
+        let (walk, mut updates) = measure_dirs(dirs, cross_mounts);
+        size_walk.set(Some(walk));
+
+        spawn(async move {
+            while let Some((dir, size)) = updates.next().await {
+                if *current_path.peek() != path {
+                    break;
+                }
+                dir_sizes.write().insert(dir, size);
+            }
+        });
+    };
+    // What the Size column sorts a folder by, once it has been measured
+    let measured_size = move |path: &Path| dir_sizes.peek().get(path).map(|size| size.shown(layout.peek().dir_sizes));
+    let listed_dirs = move || match dir_contents.peek().as_ref() {
+        Some(Ok(entries)) => entries.iter().filter(|entry| entry.is_dir).map(|entry| entry.path.clone()).collect(),
+        _ => Vec::new(),
+    };
+
+    // Read the listing whenever the path changes, then keep it in sync with
+    // what other programs do to the directory
+    use_effect(move || {
         let path = current_path();
-        async move {
-            read_dir_contents(&path)
+        // Nothing here has been measured yet
+        dir_contents.set(Some(read_dir_contents(&path, &layout.peek().sort, &|_| None)));
+        if path.is_dir() {
+            visits.record(&path);
         }
+        // Keeps what was selected here before opening the folder, e.g. an
+        // item the disk usage view jumped to
+        let listed = listed_paths(dir_contents.peek().as_ref());
+        selection.with_mut(|selection| selection.retain_listed(&listed));
+        confirm_delete.set(false);
+        renaming.set(None);
+        size_walk.set(None);
+        dir_sizes.write().clear();
+        let settings = layout.peek().dir_sizes;
+        if settings.automatic {
+            measure(listed_dirs(), settings.cross_mounts);
+        }
+
+        // Replacing the watcher drops the previous one, which ends its stream
+        let Ok((watcher, mut changes)) = watch_dir(&path) else {
+            dir_watch.set(None);
+            return;
+        };
+        dir_watch.set(Some(watcher));
+
+        spawn(async move {
+            while let Some(change) = changes.next().await {
+                if *current_path.peek() != path {
+                    break;
+                }
+                let mut contents = dir_contents.write();
+                match (change, contents.as_mut()) {
+                    (DirChange::Entries(changed), Some(Ok(entries))) if path.is_dir() => {
+                        apply_dir_change(entries, &path, &changed, &layout.peek().sort, &measured_size);
+                    }
+                    _ => *contents = Some(read_dir_contents(&path, &layout.peek().sort, &measured_size)),
+                }
+                let listed = listed_paths(contents.as_ref());
+                selection.with_mut(|selection| selection.retain_listed(&listed));
+            }
+        });
     });
 
-    rsx! {
-        div { class: "min-h-screen bg-gray-50 p-6",
-            div { class: "max-w-6xl mx-auto",
-                // Header
-                div { class: "mb-8",
-                    h1 { class: "text-3xl font-bold text-gray-900 mb-2", "File Manager" }
-                    p { class: "text-gray-600", "Browse and navigate your files and directories" }
+    // Re-sort in place when the sort order changes, or when folder sizes
+    // come in while sorting by size
+    use_effect(move || {
+        let order = layout.read().sort.clone();
+        if order.column == Column::Size {
+            dir_sizes.read();
+        }
+        dir_contents.with_mut(|contents| {
+            if let Some(Ok(entries)) = contents {
+                sort_entries(entries, &order, &measured_size);
+            }
+        });
+    });
+
+    let history = panes.history(side);
+    let mut go_up = move || {
+        let parent = current_path.peek().parent().map(Path::to_path_buf);
+        if let Some(parent) = parent {
+            current_path.set(parent);
+        }
+    };
+
+    let update_layout = move |update: &dyn Fn(&mut ListingLayout)| {
+        let mut updated = layout();
+        update(&mut updated);
+        layouts.set_layout(&current_path(), updated);
+    };
+
+    // Row paths in display order, for range selection and select-all
+    let listed = move || listed_paths(dir_contents.peek().as_ref());
+
+    let mut on_row_click = move |path: PathBuf, modifiers: Modifiers| {
+        selection.with_mut(|selection| {
+            if modifiers.shift() {
+                selection.select_range(path, &listed());
+            } else if modifiers.ctrl() || modifiers.meta() {
+                selection.toggle(path);
+            } else {
+                selection.select_only(path);
+            }
+        });
+    };
+
+    let selected_paths = move || selection.read().paths().cloned().collect::<Vec<PathBuf>>();
+
+    // The preview follows the selection while exactly one file is selected
+    let preview_path = use_memo(move || {
+        let selection = selection.read();
+        let mut paths = selection.paths();
+        match (paths.next(), paths.next()) {
+            (Some(path), None) if !path.is_dir() => Some(path.clone()),
+            _ => None,
+        }
+    });
+
+    let mut put_on_clipboard = move |kind: JobKind| {
+        let paths = selected_paths();
+        if !paths.is_empty() {
+            clipboard.set(Some(Clipboard { kind, paths }));
+        }
+    };
+
+    let mut paste = {
+        let jobs = jobs.clone();
+        move || {
+            let Some(Clipboard { kind, paths }) = clipboard() else {
+                return;
+            };
+            jobs.submit(JobRequest {
+                kind,
+                sources: paths,
+                destination: Some(current_path()),
+            });
+            // Cut entries are gone from their old place once moved
+            if kind == JobKind::Move {
+                clipboard.set(None);
+            }
+        }
+    };
+
+    // Commander-style copy and move: the selection goes straight to the
+    // folder shown in the other pane
+    let other_path = panes.path(side.other());
+    let send_to_other_pane = {
+        let jobs = jobs.clone();
+        move |kind: JobKind| {
+            let sources = selected_paths();
+            if !sources.is_empty() && split() {
+                jobs.submit(JobRequest {
+                    kind,
+                    sources,
+                    destination: Some(other_path()),
+                });
+            }
+        }
+    };
+    let toward = if side == Side::Left { "→" } else { "←" };
+
+    let trash_selected = {
+        let jobs = jobs.clone();
+        move || {
+            jobs.submit(JobRequest {
+                kind: JobKind::Trash,
+                sources: selected_paths(),
+                destination: None,
+            });
+        }
+    };
+
+    // Without a watcher nothing tells the listing about files our own jobs
+    // wrote, so re-read it whenever one finishes
+    let finished_jobs = use_memo({
+        let jobs = jobs.clone();
+        move || jobs.jobs().read().iter().filter(|job| job.state.is_finished()).count()
+    });
+    use_effect(move || {
+        finished_jobs();
+        if dir_watch.peek().is_none() {
+            dir_contents.set(Some(read_dir_contents(&current_path.peek(), &layout.peek().sort, &measured_size)));
+        }
+    });
+
+    // Imports go through the job queue like any copy, for progress, conflict
+    // prompts and undo
+    let import_files = {
+        let jobs = jobs.clone();
+        move |sources: Vec<PathBuf>| {
+            if !sources.is_empty() {
+                jobs.submit(JobRequest {
+                    kind: JobKind::Copy,
+                    sources,
+                    destination: Some(current_path()),
+                });
+            }
+        }
+    };
+
+    // One selected entry is renamed in place, several get the batch dialog
+    let mut start_rename = move || {
+        let paths: Vec<PathBuf> = listed()
+            .into_iter()
+            .filter(|path| selection.read().contains(path))
+            .collect();
+        match paths.as_slice() {
+            [] => {}
+            [single] => {
+                rename_text.set(single.file_name().unwrap_or_default().to_string_lossy().into_owned());
+                renaming.set(Some(single.clone()));
+            }
+            _ => batch_rename.set(Some(paths)),
+        }
+    };
+
+    let commit_rename = {
+        let jobs = jobs.clone();
+        move || {
+            let Some(path) = renaming.take() else {
+                return;
+            };
+            let old_name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+            let new_name = rename_text();
+            match rename::rename(&path, &new_name) {
+                Ok(Some(operation)) => jobs.record(JournalEntry {
+                    description: format!("Rename \"{old_name}\" to \"{new_name}\""),
+                    operations: vec![operation],
+                }),
+                Ok(None) => {}
+                Err(err) => action_error.set(Some(format!("Could not rename \"{old_name}\": {err}"))),
+            }
+        }
+    };
+
+    let mut create_folder = {
+        let jobs = jobs.clone();
+        move || {
+            let target = current_path().join("New folder");
+            let target = if target.exists() { unique_name(&target) } else { target };
+            match fs::create_dir(&target) {
+                Ok(()) => jobs.record(JournalEntry {
+                    description: format!("Create folder \"{}\"", target.file_name().unwrap_or_default().to_string_lossy()),
+                    operations: vec![Operation::CreatedDir(target)],
+                }),
+                Err(err) => action_error.set(Some(format!("Could not create {}: {err}", target.display()))),
+            }
+        }
+    };
+
+    // Undo and redo are only offered once earlier jobs are done, so replays
+    // never race the jobs they revert
+    let jobs_busy = {
+        let jobs = jobs.clone();
+        move || jobs.jobs().read().iter().any(|job| !job.state.is_finished())
+    };
+    let next_undo = jobs.next_undo().filter(|_| !jobs_busy());
+    let next_redo = jobs.next_redo().filter(|_| !jobs_busy());
+
+    let undo = {
+        let jobs = jobs.clone();
+        let jobs_busy = jobs_busy.clone();
+        move || {
+            if !jobs_busy() {
+                jobs.undo();
+            }
+        }
+    };
+    let redo = {
+        let jobs = jobs.clone();
+        let jobs_busy = jobs_busy.clone();
+        move || {
+            if !jobs_busy() {
+                jobs.redo();
+            }
+        }
+    };
+
+    // Selected directories, or every listed one when none is selected
+    let mut calculate_sizes = move || {
+        let dirs: Vec<PathBuf> = listed_dirs();
+        let selected: Vec<PathBuf> = dirs.iter().filter(|dir| selection.read().contains(dir)).cloned().collect();
+        measure(if selected.is_empty() { dirs } else { selected }, layout().dir_sizes.cross_mounts);
+    };
+
+    let mut update_size_settings = move |update: &dyn Fn(&mut DirSizeSettings)| {
+        let before = layout().dir_sizes;
+        let mut after = before;
+        update(&mut after);
+        update_layout(&|layout| layout.dir_sizes = after);
+        if after.cross_mounts != before.cross_mounts {
+            // Totals measured the other way are wrong now
+            let measured: Vec<PathBuf> = dir_sizes.peek().keys().cloned().collect();
+            measure(measured, after.cross_mounts);
+        } else if after.automatic && !before.automatic {
+            let unmeasured = listed_dirs().into_iter().filter(|dir| !dir_sizes.peek().contains_key(dir)).collect();
+            measure(unmeasured, after.cross_mounts);
+        }
+    };
+
+    // The command palette offers what makes sense for the active pane
+    use_effect({
+        let jobs = jobs.clone();
+        let jobs_busy = jobs_busy.clone();
+        move || {
+            if active_pane() != side {
+                return;
+            }
+            let history = panes.history(side);
+            let mut state = commands.state;
+            state.set(CommandState {
+                listed: listed_paths(dir_contents.read().as_ref()).len(),
+                selected: selection.read().len(),
+                cursor: selection.read().cursor().is_some(),
+                single_file: preview_path.read().is_some(),
+                clipboard: clipboard.read().is_some(),
+                split: split(),
+                tabs: tabs.list().read().tabs.len(),
+                can_undo: jobs.next_undo().is_some() && !jobs_busy(),
+                can_redo: jobs.next_redo().is_some() && !jobs_busy(),
+                can_go_back: history.can_go_back(),
+                can_go_forward: history.can_go_forward(),
+                has_parent: current_path().parent().is_some(),
+            });
+        }
+    });
+
+    let mut move_cursor = move |offset: isize, extend: bool| {
+        selection.with_mut(|selection| selection.move_cursor(offset, extend, &listed()));
+    };
+    let mut cursor_to = move |path: Option<PathBuf>| {
+        if let Some(path) = path {
+            selection.with_mut(|selection| selection.select_only(path));
+        }
+    };
+
+    // Moves the cursor to the next row whose name starts with what was typed.
+    // A single letter starts looking after the cursor, so typing it again
+    // steps through the names starting with it.
+    let mut type_ahead = move |typed: char| {
+        let now = Instant::now();
+        let stale = typed_at.peek().is_none_or(|at| now.duration_since(at) > TYPE_AHEAD_RESET);
+        if stale && !finding() {
+            find_text.set(String::new());
+        }
+        typed_at.set(Some(now));
+        find_text.write().push(typed);
+
+        let text = find_text.peek().to_lowercase();
+        let found = match dir_contents.peek().as_ref() {
+            Some(Ok(entries)) if !entries.is_empty() => {
+                let cursor = selection.peek().cursor().and_then(|cursor| entries.iter().position(|entry| entry.path == *cursor));
+                let start = match cursor {
+                    Some(cursor) if text.chars().count() == 1 => cursor + 1,
+                    Some(cursor) => cursor,
+                    None => 0,
+                };
+                (0..entries.len())
+                    .map(|offset| &entries[(start + offset) % entries.len()])
+                    .find(|entry| entry.name.to_lowercase().starts_with(&text))
+                    .map(|entry| entry.path.clone())
+            }
+            _ => None,
+        };
+        cursor_to(found);
+    };
+
+    // Carries out a command, whether picked in the palette or bound to a key.
+    // Returns false if the command can't do anything here.
+    let run_command = {
+        let mut paste = paste.clone();
+        let trash_selected = trash_selected.clone();
+        let mut create_folder = create_folder.clone();
+        let undo = undo.clone();
+        let redo = redo.clone();
+        let send_to_other_pane = send_to_other_pane.clone();
+        move |command: Command| {
+            if !command.is_available(&commands.state.peek()) {
+                return false;
+            }
+            match command {
+                Command::CursorUp => move_cursor(-1, false),
+                Command::CursorDown => move_cursor(1, false),
+                Command::PageUp => move_cursor(-PAGE_ROWS, false),
+                Command::PageDown => move_cursor(PAGE_ROWS, false),
+                Command::CursorFirst => cursor_to(listed().first().cloned()),
+                Command::CursorLast => cursor_to(listed().last().cloned()),
+                Command::SelectUp => move_cursor(-1, true),
+                Command::SelectDown => move_cursor(1, true),
+                Command::ToggleMark => selection.with_mut(Selection::toggle_cursor),
+                Command::Open => {
+                    let Some(path) = selection.peek().cursor().cloned() else {
+                        return false;
+                    };
+                    if path.is_dir() {
+                        current_path.set(path);
+                    } else {
+                        cursor_to(Some(path));
+                        show_preview.set(true);
+                    }
+                }
+                Command::Find => {
+                    find_text.set(String::new());
+                    finding.set(true);
                 }
+                Command::SelectAll => selection.with_mut(|selection| selection.select_all(&listed())),
+                Command::InvertSelection => selection.with_mut(|selection| selection.invert(&listed())),
+                Command::ClearSelection => selection.with_mut(Selection::clear),
+                Command::Copy => put_on_clipboard(JobKind::Copy),
+                Command::Cut => put_on_clipboard(JobKind::Move),
+                Command::Paste => paste(),
+                Command::CopyToOtherPane => send_to_other_pane(JobKind::Copy),
+                Command::MoveToOtherPane => send_to_other_pane(JobKind::Move),
+                Command::Rename => start_rename(),
+                Command::NewFolder => create_folder(),
+                Command::AddFiles => show_add_files.set(true),
+                Command::MoveToTrash => trash_selected(),
+                Command::DeletePermanently => confirm_delete.set(true),
+                Command::Undo => undo(),
+                Command::Redo => redo(),
+                Command::Edit => {
+                    if let Some(path) = preview_path.peek().clone() {
+                        navigator().push(Route::editor(&path));
+                    }
+                }
+                Command::TogglePreview => show_preview.toggle(),
+                Command::CalculateSizes => calculate_sizes(),
+                Command::GoBack => panes.go_back(side),
+                Command::GoForward => panes.go_forward(side),
+                Command::GoUp => go_up(),
+                Command::SwitchPane => active_pane.set(side.other()),
+                Command::NewTab => tabs.open(current_path.peek().clone(), true),
+                Command::CloseTab => tabs.close(tabs.list().peek().active),
+                Command::BookmarkFolder => bookmarks.add(current_path.peek().clone()),
+                Command::ToggleSplit | Command::JumpTo | Command::ShowCommands => commands.run(command),
+            }
+            true
+        }
+    };
 
-                // Navigation controls
-                div { class: "bg-white rounded-lg shadow-sm border p-4 mb-6",
-                    div { class: "flex items-center gap-4",
-                        button {
-                            class: "flex items-center gap-2 px-4 py-2 bg-blue-500 text-white rounded-md hover:bg-blue-600 transition-colors disabled:opacity-50 disabled:cursor-not-allowed",
-                            disabled: current_path().parent().is_none(),
-                            onclick: move |_| {
-                                if let Some(parent) = current_path().parent() {
-                                    current_path.set(parent.to_path_buf());
-                                }
-                            },
-                            "↑ Up"
+    // ...and carries out the commands picked there
+    use_effect({
+        let mut run_command = run_command.clone();
+        move || {
+            if active_pane() != side {
+                return;
+            }
+            if let Some(command) = commands.take_if(|command| !command.is_page_wide()) {
+                run_command(command);
+            }
+        }
+    });
+
+    let delete_selected = move |_| {
+        jobs.submit(JobRequest {
+            kind: JobKind::Delete,
+            sources: selected_paths(),
+            destination: None,
+        });
+        confirm_delete.set(false);
+    };
+
+    let status = {
+        let contents = dir_contents.read();
+        let find = if finding() { format!(" · Find: {}", find_text.read()) } else { String::new() };
+        let selection = selection.read();
+        let sizes = dir_sizes.read();
+        match contents.as_ref() {
+            Some(Ok(entries)) if selection.is_empty() => format!("{} items{find}", entries.len()),
+            Some(Ok(entries)) => {
+                // Directories count once they have been measured
+                let selected_size: u64 = entries
+                    .iter()
+                    .filter(|entry| selection.contains(&entry.path))
+                    .map(|entry| match sizes.get(&entry.path) {
+                        _ if !entry.is_dir => entry.size,
+                        Some(size) => size.shown(layout.read().dir_sizes),
+                        None => 0,
+                    })
+                    .sum();
+                format!(
+                    "{} items · {} selected ({}){find}",
+                    entries.len(),
+                    selection.len(),
+                    format_file_size(selected_size)
+                )
+            }
+            _ => String::new(),
+        }
+    };
+
+    rsx! {
+        div {
+            class: match (split(), active_pane() == side) {
+                (false, _) => "",
+                (true, true) => "min-w-0 p-2 rounded-lg ring-2 ring-blue-300",
+                (true, false) => "min-w-0 p-2 rounded-lg ring-1 ring-gray-200",
+            },
+            onmousedown: move |evt| {
+                active_pane.set(side);
+                match evt.trigger_button() {
+                    Some(MouseButton::Fourth) => panes.go_back(side),
+                    Some(MouseButton::Fifth) => panes.go_forward(side),
+                    _ => {}
+                }
+            },
+            onfocusin: move |_| active_pane.set(side),
+            // Navigation controls
+            div { class: "bg-white rounded-lg shadow-sm border p-4 mb-6",
+                div { class: "flex items-center gap-2",
+                    button {
+                        class: "px-3 py-2 border rounded-md text-gray-700 hover:bg-gray-50 transition-colors disabled:opacity-50 disabled:cursor-not-allowed",
+                        disabled: !history.can_go_back(),
+                        title: "Back{shortcut(Command::GoBack)}",
+                        onclick: move |_| panes.go_back(side),
+                        "←"
+                    }
+                    button {
+                        class: "px-3 py-2 border rounded-md text-gray-700 hover:bg-gray-50 transition-colors disabled:opacity-50 disabled:cursor-not-allowed",
+                        disabled: !history.can_go_forward(),
+                        title: "Forward{shortcut(Command::GoForward)}",
+                        onclick: move |_| panes.go_forward(side),
+                        "→"
+                    }
+                    button {
+                        class: "flex items-center gap-2 px-4 py-2 bg-blue-500 text-white rounded-md hover:bg-blue-600 transition-colors disabled:opacity-50 disabled:cursor-not-allowed",
+                        disabled: current_path().parent().is_none(),
+                        title: "Up{shortcut(Command::GoUp)}",
+                        onclick: move |_| go_up(),
+                        "↑ Up"
+                    }
+                    div { class: "flex-1 min-w-0",
+                        PathBar {
+                            path: current_path(),
+                            on_navigate: move |path| current_path.set(path),
+                        }
+                    }
+                }
+            }
+            // Selection controls
+            div { class: "flex flex-wrap items-center gap-2 mb-2 text-sm",
+                button {
+                    class: "px-3 py-1 rounded-md border bg-white text-gray-700 hover:bg-gray-50 transition-colors",
+                    onclick: move |_| selection.with_mut(|selection| selection.select_all(&listed())),
+                    "Select all"
+                }
+                button {
+                    class: "px-3 py-1 rounded-md border bg-white text-gray-700 hover:bg-gray-50 transition-colors",
+                    onclick: move |_| selection.with_mut(|selection| selection.invert(&listed())),
+                    "Invert selection"
+                }
+                button {
+                    class: "px-3 py-1 rounded-md border bg-white text-gray-700 hover:bg-gray-50 transition-colors disabled:opacity-50 disabled:cursor-not-allowed",
+                    disabled: selection.read().is_empty(),
+                    onclick: move |_| selection.with_mut(Selection::clear),
+                    "Clear"
+                }
+                div { class: "flex-1" }
+                button {
+                    class: "px-3 py-1 rounded-md border bg-white text-gray-700 hover:bg-gray-50 transition-colors disabled:opacity-50 disabled:cursor-not-allowed",
+                    disabled: next_undo.is_none(),
+                    title: next_undo.as_ref().map(|action| format!("Undo {action}{}", shortcut(Command::Undo))),
+                    onclick: {
+                        let undo = undo.clone();
+                        move |_| undo()
+                    },
+                    "Undo"
+                }
+                button {
+                    class: "px-3 py-1 rounded-md border bg-white text-gray-700 hover:bg-gray-50 transition-colors disabled:opacity-50 disabled:cursor-not-allowed",
+                    disabled: next_redo.is_none(),
+                    title: next_redo.as_ref().map(|action| format!("Redo {action}{}", shortcut(Command::Redo))),
+                    onclick: {
+                        let redo = redo.clone();
+                        move |_| redo()
+                    },
+                    "Redo"
+                }
+                button {
+                    class: "px-3 py-1 rounded-md border bg-white text-gray-700 hover:bg-gray-50 transition-colors disabled:opacity-50 disabled:cursor-not-allowed",
+                    disabled: selection.read().is_empty(),
+                    title: "Rename{shortcut(Command::Rename)}",
+                    onclick: move |_| start_rename(),
+                    "Rename"
+                }
+                button {
+                    class: "px-3 py-1 rounded-md border bg-white text-gray-700 hover:bg-gray-50 transition-colors",
+                    onclick: move |_| create_folder(),
+                    "New folder"
+                }
+                button {
+                    class: "px-3 py-1 rounded-md border bg-white text-gray-700 hover:bg-gray-50 transition-colors",
+                    onclick: move |_| show_add_files.toggle(),
+                    "Add files"
+                }
+                button {
+                    class: "px-3 py-1 rounded-md border bg-white text-gray-700 hover:bg-gray-50 transition-colors",
+                    title: "Measure the selected folders, or all of them",
+                    onclick: move |_| calculate_sizes(),
+                    "Calculate sizes"
+                }
+                button {
+                    class: "px-3 py-1 rounded-md border bg-white text-gray-700 hover:bg-gray-50 transition-colors disabled:opacity-50 disabled:cursor-not-allowed",
+                    disabled: preview_path.read().is_none(),
+                    title: "Open the selected file in the text editor",
+                    onclick: move |_| {
+                        if let Some(path) = preview_path() {
+                            navigator().push(Route::editor(&path));
                         }
-                        div { class: "flex-1",
-                            label { class: "block text-sm font-medium text-gray-700 mb-1",
-                                "Current Path:"
+                    },
+                    "Edit"
+                }
+                button {
+                    class: if show_preview() { "px-3 py-1 rounded-md border bg-blue-50 text-blue-700 border-blue-300 transition-colors" } else { "px-3 py-1 rounded-md border bg-white text-gray-700 hover:bg-gray-50 transition-colors" },
+                    onclick: move |_| show_preview.toggle(),
+                    "Preview"
+                }
+                div { class: "relative",
+                    button {
+                        class: "px-3 py-1 rounded-md border bg-white text-gray-700 hover:bg-gray-50 transition-colors",
+                        onclick: move |_| show_columns.toggle(),
+                        "Columns"
+                    }
+                    if show_columns() {
+                        div { class: "absolute right-0 z-20 mt-1 w-64 bg-white border border-gray-200 rounded-md shadow-lg p-2 space-y-1",
+                            for column in Column::ALL {
+                                div { key: "{column.label()}", class: "flex items-center gap-2",
+                                    label { class: "flex-1 flex items-center gap-2 text-gray-700",
+                                        input {
+                                            r#type: "checkbox",
+                                            checked: layout.read().columns.contains(&column),
+                                            disabled: column == Column::Name,
+                                            onchange: move |_| update_layout(&|layout| layout.toggle(column)),
+                                        }
+                                        "{column.label()}"
+                                    }
+                                    if layout.read().columns.contains(&column) {
+                                        button {
+                                            class: "px-1 text-gray-500 hover:text-gray-900",
+                                            title: "Move left",
+                                            onclick: move |_| update_layout(&|layout| layout.shift(column, -1)),
+                                            "↑"
+                                        }
+                                        button {
+                                            class: "px-1 text-gray-500 hover:text-gray-900",
+                                            title: "Move right",
+                                            onclick: move |_| update_layout(&|layout| layout.shift(column, 1)),
+                                            "↓"
+                                        }
+                                    }
+                                }
+                            }
+                            div { class: "border-t pt-1 mt-1 space-y-1",
+                                label { class: "flex items-center gap-2 text-gray-700",
+                                    "Dates"
+                                    select {
+                                        class: "flex-1 px-1 py-0.5 border border-gray-300 rounded-md",
+                                        onchange: move |evt| {
+                                            if let Some(format) = TimeFormat::ALL.into_iter().find(|format| format.label() == evt.value()) {
+                                                update_layout(&|layout| layout.time_format = format)
+                                            }
+                                        },
+                                        for format in TimeFormat::ALL {
+                                            option {
+                                                value: format.label(),
+                                                selected: layout.read().time_format == format,
+                                                "{format.label()}"
+                                            }
+                                        }
+                                    }
+                                }
+                                label { class: "flex items-center gap-2 text-gray-700",
+                                    input {
+                                        r#type: "checkbox",
+                                        checked: layout.read().sort.natural,
+                                        onchange: move |evt| {
+                                            let natural = evt.checked();
+                                            update_layout(&|layout| layout.sort.natural = natural)
+                                        },
+                                    }
+                                    "Natural number order"
+                                }
+                                label { class: "flex items-center gap-2 text-gray-700",
+                                    input {
+                                        r#type: "checkbox",
+                                        checked: layout.read().sort.case_insensitive,
+                                        onchange: move |evt| {
+                                            let case_insensitive = evt.checked();
+                                            update_layout(&|layout| layout.sort.case_insensitive = case_insensitive)
+                                        },
+                                    }
+                                    "Ignore case"
+                                }
                             }
-                            input {
-                                class: "w-full px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500 focus:border-transparent",
-                                r#type: "text",
-                                value: "{current_path().display()}",
-                                onchange: move |e| {
-                                    let path = PathBuf::from(e.value());
-                                    if path.exists() && path.is_dir() {
-                                        current_path.set(path);
+                            div { class: "border-t pt-1 mt-1 space-y-1",
+                                label { class: "flex items-center gap-2 text-gray-700",
+                                    input {
+                                        r#type: "checkbox",
+                                        checked: layout.read().dir_sizes.automatic,
+                                        onchange: move |evt| {
+                                            let automatic = evt.checked();
+                                            update_size_settings(&|settings| settings.automatic = automatic)
+                                        },
                                     }
-                                },
+                                    "Folder sizes automatically"
+                                }
+                                label { class: "flex items-center gap-2 text-gray-700",
+                                    input {
+                                        r#type: "checkbox",
+                                        checked: layout.read().dir_sizes.disk_usage,
+                                        onchange: move |evt| {
+                                            let disk_usage = evt.checked();
+                                            update_size_settings(&|settings| settings.disk_usage = disk_usage)
+                                        },
+                                    }
+                                    "Size on disk"
+                                }
+                                label { class: "flex items-center gap-2 text-gray-700",
+                                    input {
+                                        r#type: "checkbox",
+                                        checked: layout.read().dir_sizes.cross_mounts,
+                                        onchange: move |evt| {
+                                            let cross_mounts = evt.checked();
+                                            update_size_settings(&|settings| settings.cross_mounts = cross_mounts)
+                                        },
+                                    }
+                                    "Count other file systems"
+                                }
+                                button {
+                                    class: "w-full text-left text-gray-500 hover:text-gray-900",
+                                    onclick: move |_| layouts.reset(&current_path()),
+                                    "Use parent folder's layout"
+                                }
                             }
                         }
                     }
                 }
+                button {
+                    class: "px-3 py-1 rounded-md border bg-white text-gray-700 hover:bg-gray-50 transition-colors disabled:opacity-50 disabled:cursor-not-allowed",
+                    disabled: selection.read().is_empty(),
+                    onclick: move |_| put_on_clipboard(JobKind::Copy),
+                    "Copy"
+                }
+                button {
+                    class: "px-3 py-1 rounded-md border bg-white text-gray-700 hover:bg-gray-50 transition-colors disabled:opacity-50 disabled:cursor-not-allowed",
+                    disabled: selection.read().is_empty(),
+                    onclick: move |_| put_on_clipboard(JobKind::Move),
+                    "Cut"
+                }
+                button {
+                    class: "px-3 py-1 rounded-md border bg-white text-gray-700 hover:bg-gray-50 transition-colors disabled:opacity-50 disabled:cursor-not-allowed",
+                    disabled: clipboard.read().is_none(),
+                    onclick: move |_| paste(),
+                    if let Some(board) = clipboard.read().as_ref() {
+                        "Paste ({board.paths.len()})"
+                    } else {
+                        "Paste"
+                    }
+                }
+                if split() {
+                    button {
+                        class: "px-3 py-1 rounded-md border bg-white text-gray-700 hover:bg-gray-50 transition-colors disabled:opacity-50 disabled:cursor-not-allowed",
+                        disabled: selection.read().is_empty() || other_path() == current_path(),
+                        title: "Copy the selection to the other pane{shortcut(Command::CopyToOtherPane)}",
+                        onclick: {
+                            let send_to_other_pane = send_to_other_pane.clone();
+                            move |_| send_to_other_pane(JobKind::Copy)
+                        },
+                        "Copy {toward}"
+                    }
+                    button {
+                        class: "px-3 py-1 rounded-md border bg-white text-gray-700 hover:bg-gray-50 transition-colors disabled:opacity-50 disabled:cursor-not-allowed",
+                        disabled: selection.read().is_empty() || other_path() == current_path(),
+                        title: "Move the selection to the other pane{shortcut(Command::MoveToOtherPane)}",
+                        onclick: {
+                            let send_to_other_pane = send_to_other_pane.clone();
+                            move |_| send_to_other_pane(JobKind::Move)
+                        },
+                        "Move {toward}"
+                    }
+                }
+                button {
+                    class: "px-3 py-1 rounded-md border bg-white text-gray-700 hover:bg-gray-50 transition-colors disabled:opacity-50 disabled:cursor-not-allowed",
+                    disabled: selection.read().is_empty(),
+                    onclick: move |_| trash_selected(),
+                    "Move to Trash"
+                }
+                button {
+                    class: "px-3 py-1 rounded-md border bg-white text-red-600 hover:bg-red-50 transition-colors disabled:opacity-50 disabled:cursor-not-allowed",
+                    disabled: selection.read().is_empty(),
+                    onclick: move |_| confirm_delete.set(true),
+                    "Delete permanently"
+                }
+            }
+            if confirm_delete() && !selection.read().is_empty() {
+                div { class: "flex items-center justify-between gap-4 mb-2 p-3 text-sm bg-red-50 border border-red-200 rounded-md",
+                    span { class: "text-red-800",
+                        "Permanently delete {selection.read().len()} selected items? This cannot be undone."
+                    }
+                    div { class: "flex gap-2",
+                        button {
+                            class: "px-3 py-1 rounded-md bg-red-600 text-white hover:bg-red-700 transition-colors",
+                            onclick: delete_selected,
+                            "Delete"
+                        }
+                        button {
+                            class: "px-3 py-1 rounded-md border bg-white text-gray-700 hover:bg-gray-50 transition-colors",
+                            onclick: move |_| confirm_delete.set(false),
+                            "Cancel"
+                        }
+                    }
+                }
+            }
+            if let Some(error) = action_error() {
+                div { class: "flex items-center justify-between gap-4 mb-2 p-3 text-sm bg-red-50 border border-red-200 rounded-md",
+                    span { class: "text-red-800 break-all", "{error}" }
+                    button {
+                        class: "text-red-600 hover:text-red-800",
+                        onclick: move |_| action_error.set(None),
+                        "✕"
+                    }
+                }
+            }
+            if let Some(paths) = batch_rename() {
+                BatchRename {
+                    paths,
+                    on_close: move |_| batch_rename.set(None),
+                }
+            }
+            if show_add_files() {
+                div { class: "bg-white rounded-lg shadow-sm border p-4 mb-2",
+                    AddFile {
+                        multiple: true,
+                        on_add: {
+                            let import_files = import_files.clone();
+                            move |files: Vec<FileInfo>| {
+                                import_files(files.into_iter().map(|file| file.path).collect());
+                                show_add_files.set(false);
+                            }
+                        },
+                    }
+                }
+            }
+            div { class: "flex items-start gap-4",
                 // File listing
-                div { class: "bg-white rounded-lg shadow-sm border overflow-hidden",
+                div {
+                    class: if drop_target() {
+                        "flex-1 min-w-0 bg-white rounded-lg shadow-sm border-2 border-dashed border-blue-400 overflow-hidden focus:outline-none"
+                    } else {
+                        "flex-1 min-w-0 bg-white rounded-lg shadow-sm border overflow-hidden focus:outline-none"
+                    },
+                    tabindex: 0,
+                    "data-pane": "{side:?}",
+                    onmounted: move |evt| listing.set(Some(evt.data())),
+                    // Dropping files from another program copies them here
+                    ondragover: move |evt: DragEvent| {
+                        // Folder rows dragged to the sidebar aren't imports
+                        if dragged_folder.peek().is_some() {
+                            return;
+                        }
+                        evt.prevent_default();
+                        drop_target.set(true);
+                    },
+                    ondragleave: move |_| drop_target.set(false),
+                    ondrop: move |evt: DragEvent| {
+                        evt.prevent_default();
+                        drop_target.set(false);
+                        if let Some(file_engine) = evt.files() {
+                            import_files(file_engine.files().into_iter().map(PathBuf::from).collect());
+                        }
+                    },
+                    onkeydown: {
+                        let mut run_command = run_command.clone();
+                        move |evt: KeyboardEvent| {
+                            let Some(chord) = Chord::from_event(&evt.key(), evt.modifiers()) else {
+                                return;
+                            };
+                            if finding() {
+                                let handled = match evt.key() {
+                                    Key::Enter | Key::Escape => {
+                                        finding.set(false);
+                                        true
+                                    }
+                                    Key::Backspace => {
+                                        find_text.write().pop();
+                                        true
+                                    }
+                                    _ => match chord.typed_char() {
+                                        Some(typed) => {
+                                            type_ahead(typed);
+                                            true
+                                        }
+                                        // Anything else ends Find and does what it's bound to
+                                        None => {
+                                            finding.set(false);
+                                            false
+                                        }
+                                    },
+                                };
+                                if handled {
+                                    evt.prevent_default();
+                                    evt.stop_propagation();
+                                    return;
+                                }
+                            }
+
+                            let mut keys = pending_keys.peek().clone();
+                            keys.push(chord.clone());
+                            let mut lookup = keymap.read().lookup(&keys);
+                            if lookup == Lookup::Unbound && keys.len() > 1 {
+                                // A key that doesn't continue the sequence starts over
+                                keys = vec![chord.clone()];
+                                lookup = keymap.read().lookup(&keys);
+                            }
+                            match lookup {
+                                Lookup::Command(command) => {
+                                    pending_keys.set(Vec::new());
+                                    // Otherwise the key keeps its usual meaning, so
+                                    // Tab still moves the focus without a split view
+                                    if !run_command(command) {
+                                        return;
+                                    }
+                                }
+                                Lookup::Prefix => pending_keys.set(keys),
+                                Lookup::Unbound => {
+                                    pending_keys.set(Vec::new());
+                                    match chord.typed_char() {
+                                        Some(typed) => type_ahead(typed),
+                                        None => return,
+                                    }
+                                }
+                            }
+                            evt.prevent_default();
+                            evt.stop_propagation();
+                        }
+                    },
                     if let Some(dir_result) = dir_contents.read().as_ref() {
                         match dir_result {
                             Ok(entries) => rsx! {
@@ -66,60 +1209,158 @@ pub fn FileManager() -> Element {
                                     table { class: "w-full",
                                         thead { class: "bg-gray-50 border-b",
                                             tr {
-                                                th { class: "px-6 py-3 text-left text-xs font-medium text-gray-500 uppercase tracking-wider w-12",
-                                                    "Type"
-                                                }
-                                                th { class: "px-6 py-3 text-left text-xs font-medium text-gray-500 uppercase tracking-wider",
-                                                    "Name"
-                                                }
-                                                th { class: "px-6 py-3 text-left text-xs font-medium text-gray-500 uppercase tracking-wider w-32",
-                                                    "Size"
-                                                }
-                                                th { class: "px-6 py-3 text-left text-xs font-medium text-gray-500 uppercase tracking-wider w-48",
-                                                    "Modified"
+                                                for column in layout.read().columns.iter().copied() {
+                                                    th {
+                                                        key: "{column.label()}",
+                                                        class: "px-6 py-3 text-left text-xs font-medium text-gray-500 uppercase tracking-wider cursor-pointer select-none hover:text-gray-800 {column.width_class()}",
+                                                        onclick: move |_| update_layout(&|layout| layout.sort_by(column)),
+                                                        "{column.label()}"
+                                                        if layout.read().sort.column == column {
+                                                            if layout.read().sort.descending { " ▼" } else { " ▲" }
+                                                        }
+                                                    }
                                                 }
                                             }
                                         }
-                                        tbody { class: "bg-white divide-y divide-gray-200",
+                                        tbody { class: "bg-white divide-y divide-gray-200 select-none",
                                             for entry in entries {
                                                 tr {
                                                     key: "{entry.path.display()}",
-                                                    class: "hover:bg-gray-50 transition-colors",
-                                                    td { class: "px-6 py-4 whitespace-nowrap text-2xl",
-                                                        if entry.is_dir {
-                                                            "📁"
-                                                        } else {
-                                                            "📄"
-                                                        }
-                                                    }
-                                                    td { class: "px-6 py-4 whitespace-nowrap",
-                                                        if entry.is_dir {
-                                                            button {
-                                                                class: "text-blue-600 hover:text-blue-800 font-medium transition-colors cursor-pointer",
-                                                                onclick: {
-                                                                    let entry_path = entry.path.clone();
-                                                                    move |_| {
-                                                                        current_path.set(entry_path.clone());
-                                                                    }
-                                                                },
-                                                                "{entry.name}"
+                                                    class: if selection.read().contains(&entry.path) { "group bg-blue-100 transition-colors cursor-default" } else { "group hover:bg-gray-50 transition-colors cursor-default" },
+                                                    class: if selection.read().cursor() == Some(&entry.path) { "ring-2 ring-inset ring-blue-400" },
+                                                    "data-cursor": (selection.read().cursor() == Some(&entry.path)).then_some("true"),
+                                                    onclick: {
+                                                        let entry_path = entry.path.clone();
+                                                        move |evt: MouseEvent| on_row_click(entry_path.clone(), evt.modifiers())
+                                                    },
+                                                    // Folders can be dragged onto the sidebar to bookmark them
+                                                    draggable: entry.is_dir,
+                                                    ondragstart: {
+                                                        let entry_path = entry.path.clone();
+                                                        move |_| dragged_folder.set(Some(entry_path.clone()))
+                                                    },
+                                                    ondragend: move |_| dragged_folder.set(None),
+                                                    ondoubleclick: {
+                                                        let entry_path = entry.path.clone();
+                                                        let is_dir = entry.is_dir;
+                                                        move |_| {
+                                                            if is_dir {
+                                                                current_path.set(entry_path.clone());
                                                             }
-                                                        } else {
-                                                            span { class: "text-gray-900", "{entry.name}" }
                                                         }
-                                                    }
-                                                    td { class: "px-6 py-4 whitespace-nowrap text-sm text-gray-500",
-                                                        if !entry.is_dir {
-                                                            "{format_file_size(entry.size)}"
-                                                        } else {
-                                                            "-"
-                                                        }
-                                                    }
-                                                    td { class: "px-6 py-4 whitespace-nowrap text-sm text-gray-500",
-                                                        if let Some(modified) = &entry.modified {
-                                                            "{modified}"
-                                                        } else {
-                                                            "-"
+                                                    },
+                                                    for column in layout.read().columns.iter().copied() {
+                                                        match column {
+                                                            Column::Type => rsx! {
+                                                                td { key: "{column.label()}", class: "px-6 py-4 whitespace-nowrap text-2xl",
+                                                                    if entry.is_dir {
+                                                                        "📁"
+                                                                    } else if entry.is_symlink {
+                                                                        "🔗"
+                                                                    } else {
+                                                                        "📄"
+                                                                    }
+                                                                }
+                                                            },
+                                                            Column::Name => rsx! {
+                                                                td { key: "{column.label()}", class: "px-6 py-4 whitespace-nowrap",
+                                                                    if renaming.read().as_ref() == Some(&entry.path) {
+                                                                        input {
+                                                                            class: "w-full px-2 py-1 border border-blue-400 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500",
+                                                                            value: "{rename_text}",
+                                                                            onmounted: move |evt| async move {
+                                                                                let _ = evt.set_focus(true).await;
+                                                                            },
+                                                                            oninput: move |evt| rename_text.set(evt.value()),
+                                                                            onclick: move |evt| evt.stop_propagation(),
+                                                                            ondoubleclick: move |evt| evt.stop_propagation(),
+                                                                            // Keys typed into the name must not reach the listing shortcuts
+                                                                            onkeydown: {
+                                                                                let mut commit_rename = commit_rename.clone();
+                                                                                move |evt: KeyboardEvent| {
+                                                                                    evt.stop_propagation();
+                                                                                    match evt.key() {
+                                                                                        Key::Enter => commit_rename(),
+                                                                                        Key::Escape => renaming.set(None),
+                                                                                        _ => {}
+                                                                                    }
+                                                                                }
+                                                                            },
+                                                                            onblur: {
+                                                                                let mut commit_rename = commit_rename.clone();
+                                                                                move |_| commit_rename()
+                                                                            },
+                                                                        }
+                                                                    } else if entry.is_dir {
+                                                                        // Ctrl-click or middle-click opens the folder in a new tab
+                                                                        button {
+                                                                            class: "text-blue-600 hover:text-blue-800 font-medium transition-colors cursor-pointer",
+                                                                            onclick: {
+                                                                                let entry_path = entry.path.clone();
+                                                                                move |evt: MouseEvent| {
+                                                                                    evt.stop_propagation();
+                                                                                    let modifiers = evt.modifiers();
+                                                                                    if modifiers.ctrl() || modifiers.meta() {
+                                                                                        tabs.open(entry_path.clone(), false);
+                                                                                    } else {
+                                                                                        current_path.set(entry_path.clone());
+                                                                                    }
+                                                                                }
+                                                                            },
+                                                                            onmousedown: {
+                                                                                let entry_path = entry.path.clone();
+                                                                                move |evt: MouseEvent| {
+                                                                                    if evt.trigger_button() == Some(MouseButton::Auxiliary) {
+                                                                                        evt.prevent_default();
+                                                                                        tabs.open(entry_path.clone(), false);
+                                                                                    }
+                                                                                }
+                                                                            },
+                                                                            "{entry.name}"
+                                                                        }
+                                                                        button {
+                                                                            class: "ml-2 text-xs text-gray-400 opacity-0 group-hover:opacity-100 hover:text-gray-900 transition-opacity",
+                                                                            title: "Open in new tab",
+                                                                            onclick: {
+                                                                                let entry_path = entry.path.clone();
+                                                                                move |evt: MouseEvent| {
+                                                                                    evt.stop_propagation();
+                                                                                    tabs.open(entry_path.clone(), true);
+                                                                                }
+                                                                            },
+                                                                            "⧉"
+                                                                        }
+                                                                    } else {
+                                                                        // The row click selects the file, which the pane then shows
+                                                                        button {
+                                                                            class: "text-gray-900 hover:text-blue-700 transition-colors cursor-pointer",
+                                                                            onclick: move |_| show_preview.set(true),
+                                                                            "{entry.name}"
+                                                                        }
+                                                                    }
+                                                                }
+                                                            },
+                                                            Column::Size if entry.is_dir => rsx! {
+                                                                td {
+                                                                    key: "{column.label()}",
+                                                                    class: "px-6 py-4 whitespace-nowrap text-sm text-gray-500",
+                                                                    title: dir_sizes.read().get(&entry.path).map(describe_dir_size),
+                                                                    match dir_sizes.read().get(&entry.path) {
+                                                                        Some(size) if size.complete => format_file_size(size.shown(layout.read().dir_sizes)),
+                                                                        Some(size) => format!("{}…", format_file_size(size.shown(layout.read().dir_sizes))),
+                                                                        None => "-".to_string(),
+                                                                    }
+                                                                }
+                                                            },
+                                                            _ => rsx! {
+                                                                td {
+                                                                    key: "{column.label()}",
+                                                                    class: "px-6 py-4 whitespace-nowrap text-sm text-gray-500",
+                                                                    // Time columns show the exact timestamp on hover
+                                                                    title: entry_time(entry, column).map(format_full),
+                                                                    "{column_text(entry, column, layout.read().time_format)}"
+                                                                }
+                                                            },
                                                         }
                                                     }
                                                 }
@@ -144,7 +1385,15 @@ pub fn FileManager() -> Element {
                         }
                     }
                 }
+                if show_preview() {
+                    PreviewPane {
+                        path: preview_path(),
+                        on_close: move |_| show_preview.set(false),
+                    }
+                }
             }
+            // Status bar
+            div { class: "mt-2 px-1 text-sm text-gray-500", "{status}" }
         }
     }
 }
@@ -156,74 +1405,170 @@ struct DirEntry {
     name: String,
     path: PathBuf,
     is_dir: bool,
+    is_symlink: bool,
     size: u64,
-    modified: Option<String>,
+    modified: Option<SystemTime>,
+    created: Option<SystemTime>,
+    accessed: Option<SystemTime>,
+    extension: String,
+    mode: u32,
+    owner: String,
+    group: String,
+    inode: u64,
+    link_target: Option<PathBuf>,
 }
 
-fn read_dir_contents(path: &Path) -> Result<Vec<DirEntry>, std::io::Error> {
+impl DirEntry {
+    fn new(path: PathBuf, metadata: &fs::Metadata) -> Self {
+        let link_target = if metadata.is_symlink() {
+            fs::read_link(&path).ok()
+        } else {
+            None
+        };
+
+        DirEntry {
+            name: path.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+            extension: path.extension().map(|ext| ext.to_string_lossy().into_owned()).unwrap_or_default(),
+            path,
+            is_dir: metadata.is_dir(),
+            is_symlink: metadata.is_symlink(),
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+            created: metadata.created().ok(),
+            accessed: metadata.accessed().ok(),
+            mode: metadata.mode(),
+            owner: user_name(metadata.uid()),
+            group: group_name(metadata.gid()),
+            inode: metadata.ino(),
+            link_target,
+        }
+    }
+}
+
+/// The timestamp shown in a time column.
+fn entry_time(entry: &DirEntry, column: Column) -> Option<SystemTime> {
+    match column {
+        Column::Modified => entry.modified,
+        Column::Created => entry.created,
+        Column::Accessed => entry.accessed,
+        _ => None,
+    }
+}
+
+/// Tooltip of a measured directory's size.
+fn describe_dir_size(size: &DirSize) -> String {
+    let files = if size.files == 1 { "file" } else { "files" };
+    format!(
+        "{} {files} · {} apparent · {} on disk{}",
+        size.files,
+        format_file_size(size.apparent),
+        format_file_size(size.disk_usage),
+        if size.complete { "" } else { " · still counting" }
+    )
+}
+
+/// Cell text of the columns that show plain text.
+fn column_text(entry: &DirEntry, column: Column, time_format: TimeFormat) -> String {
+    let or_dash = |text: Option<String>| text.unwrap_or_else(|| "-".to_string());
+    match column {
+        Column::Type | Column::Name => String::new(),
+        Column::Size if entry.is_dir => "-".to_string(),
+        Column::Size => format_file_size(entry.size),
+        Column::Modified | Column::Created | Column::Accessed => {
+            or_dash(entry_time(entry, column).map(|time| time_format.format(time)))
+        }
+        Column::Extension => or_dash(Some(entry.extension.clone()).filter(|ext| !ext.is_empty())),
+        Column::Permissions => format_permissions(entry.mode, entry.is_dir, entry.is_symlink),
+        Column::Owner => entry.owner.clone(),
+        Column::Group => entry.group.clone(),
+        Column::Inode => entry.inode.to_string(),
+        Column::LinkTarget => or_dash(entry.link_target.as_ref().map(|target| target.display().to_string())),
+    }
+}
+
+fn read_dir_contents(
+    path: &Path,
+    order: &SortOrder,
+    measured_size: &dyn Fn(&Path) -> Option<u64>,
+) -> Result<Vec<DirEntry>, std::io::Error> {
     let mut entries = Vec::new();
     
     for entry in fs::read_dir(path)? {
         let entry = entry?;
-        let path = entry.path();
         let metadata = entry.metadata()?;
-        
-        let modified = metadata.modified()
-            .ok()
-            .and_then(|time| {
-                use std::time::{SystemTime, UNIX_EPOCH};
-                let now = SystemTime::now();
-                if let (Ok(file_time), Ok(current_time)) = (
-                    time.duration_since(UNIX_EPOCH),
-                    now.duration_since(UNIX_EPOCH)
-                ) {
-                    if current_time > file_time {
-                        let diff_secs = current_time.as_secs() - file_time.as_secs();
-                        let days = diff_secs / 86400;
-                        let hours = (diff_secs % 86400) / 3600;
-                        let minutes = (diff_secs % 3600) / 60;
-                        
-                        if days > 0 {
-                            Some(format!("{} days ago", days))
-                        } else if hours > 0 {
-                            Some(format!("{} hours ago", hours))
-                        } else if minutes > 0 {
-                            Some(format!("{} minutes ago", minutes))
-                        } else {
-                            Some("Just now".to_string())
-                        }
-                    } else {
-                        Some("Recently".to_string())
-                    }
-                } else {
-                    None
-                }
-            });
-        
-        entries.push(DirEntry {
-            name: entry.file_name().to_string_lossy().into_owned(),
-            path: path.clone(),
-            is_dir: metadata.is_dir(),
-            size: metadata.len(),
-            modified,
-        });
+        entries.push(DirEntry::new(entry.path(), &metadata));
     }
     
-    // Sort directories first, then by name
+    sort_entries(&mut entries, order, measured_size);
+    Ok(entries)
+}
+
+/// Updates `entries` in place for the children of `dir` named in `changed`,
+/// so rows that didn't change keep their DOM nodes (and the scroll position).
+fn apply_dir_change(
+    entries: &mut Vec<DirEntry>,
+    dir: &Path,
+    changed: &BTreeSet<PathBuf>,
+    order: &SortOrder,
+    measured_size: &dyn Fn(&Path) -> Option<u64>,
+) {
+    let children = changed.iter().filter(|path| path.as_path() != dir);
+    for name in children.filter_map(|path| path.file_name()) {
+        let path = dir.join(name);
+        let existing = entries.iter().position(|entry| entry.path == path);
+        // Same as DirEntry::metadata in read_dir_contents: symlinks aren't followed
+        match (fs::symlink_metadata(&path), existing) {
+            (Ok(metadata), Some(i)) => entries[i] = DirEntry::new(path, &metadata),
+            (Ok(metadata), None) => entries.push(DirEntry::new(path, &metadata)),
+            (Err(_), Some(i)) => {
+                entries.remove(i);
+            }
+            (Err(_), None) => {}
+        }
+    }
+    sort_entries(entries, order, measured_size);
+}
+
+fn listed_paths(contents: Option<&Result<Vec<DirEntry>, std::io::Error>>) -> Vec<PathBuf> {
+    match contents {
+        Some(Ok(entries)) => entries.iter().map(|entry| entry.path.clone()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Sorts `entries` by `order`. Folders sort by size as `measured_size` gives
+/// it, falling back to the size of the folder entry itself until measured.
+fn sort_entries(entries: &mut [DirEntry], order: &SortOrder, measured_size: &dyn Fn(&Path) -> Option<u64>) {
+    let size = |entry: &DirEntry| entry.is_dir.then(|| measured_size(&entry.path)).flatten().unwrap_or(entry.size);
+    // Directories stay first whichever way the chosen column sorts
     entries.sort_by(|a, b| {
-        if a.is_dir == b.is_dir {
-            a.name.cmp(&b.name)
-        } else if a.is_dir {
-            std::cmp::Ordering::Less
+        let by_column = match order.column {
+            Column::Type | Column::Name => Ordering::Equal,
+            Column::Size => size(a).cmp(&size(b)),
+            Column::Modified => a.modified.cmp(&b.modified),
+            Column::Created => a.created.cmp(&b.created),
+            Column::Accessed => a.accessed.cmp(&b.accessed),
+            Column::Extension => order.compare_names(&a.extension, &b.extension),
+            Column::Permissions => (a.mode & 0o7777).cmp(&(b.mode & 0o7777)),
+            Column::Owner => order.compare_names(&a.owner, &b.owner),
+            Column::Group => order.compare_names(&a.group, &b.group),
+            Column::Inode => a.inode.cmp(&b.inode),
+            Column::LinkTarget => a.link_target.cmp(&b.link_target),
+        };
+        let by_column = by_column.then_with(|| order.compare_names(&a.name, &b.name));
+        let by_column = if order.descending { by_column.reverse() } else { by_column };
+        let dirs_first = b.is_dir.cmp(&a.is_dir);
+        // Sorting by type descending is the one way to get files first
+        let dirs_first = if order.column == Column::Type && order.descending {
+            dirs_first.reverse()
         } else {
-            std::cmp::Ordering::Greater
-        }
+            dirs_first
+        };
+        dirs_first.then(by_column)
     });
-    
-    Ok(entries)
 }
 
-fn format_file_size(size: u64) -> String {
+pub fn format_file_size(size: u64) -> String {
     const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
     let mut size = size as f64;
     let mut unit_index = 0;
@@ -0,0 +1,237 @@
+//! Visited folders ranked by frecency, a mix of how often and how recently
+//! they were opened, for the jump palette. Ranking and matching follow
+//! zoxide: every keyword has to appear in the path in order, and the last
+//! one in the folder's own name.
+
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::config;
+
+const VISITS_FILE: &str = "visits.toml";
+/// Once all ranks add up to more than this, they are scaled down and the
+/// least visited folders forgotten.
+const MAX_TOTAL_RANK: f64 = 10_000.0;
+
+const HOUR: u64 = 60 * 60;
+const DAY: u64 = 24 * HOUR;
+const WEEK: u64 = 7 * DAY;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Visit {
+    /// Number of visits, scaled down as the store ages
+    pub rank: f64,
+    /// Seconds since the Unix epoch
+    pub last: u64,
+}
+
+impl Visit {
+    /// Rank weighted by how long ago the folder was last visited.
+    pub fn score(&self, now: u64) -> f64 {
+        let age = now.saturating_sub(self.last);
+        let weight = if age < HOUR {
+            4.0
+        } else if age < DAY {
+            2.0
+        } else if age < WEEK {
+            0.5
+        } else {
+            0.25
+        };
+        self.rank * weight
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VisitLog {
+    visits: HashMap<PathBuf, Visit>,
+}
+
+impl VisitLog {
+    fn record(&mut self, path: &Path, now: u64) {
+        let visit = self.visits.entry(path.to_path_buf()).or_insert(Visit { rank: 0.0, last: now });
+        visit.rank += 1.0;
+        visit.last = now;
+
+        let total: f64 = self.visits.values().map(|visit| visit.rank).sum();
+        if total > MAX_TOTAL_RANK {
+            let factor = 0.9 * MAX_TOTAL_RANK / total;
+            self.visits.retain(|_, visit| {
+                visit.rank *= factor;
+                visit.rank >= 1.0
+            });
+        }
+    }
+
+    /// A copy the settings file can hold, without folders that aren't UTF-8.
+    fn savable(&self) -> VisitLog {
+        let visits = self.visits.iter().filter(|(path, _)| path.to_str().is_some()).map(|(path, visit)| (path.clone(), *visit));
+        VisitLog { visits: visits.collect() }
+    }
+
+    /// Folders matching `query`, best first. An empty query lists the most
+    /// recent ones. Folders that no longer exist are left out.
+    pub fn query(&self, query: &str, skip: &Path, limit: usize, now: u64) -> Vec<PathBuf> {
+        let keywords: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+        let mut matches: Vec<(&PathBuf, f64)> = self
+            .visits
+            .iter()
+            .filter(|(path, _)| path.as_path() != skip && matches_keywords(path, &keywords))
+            .map(|(path, visit)| {
+                let order = if keywords.is_empty() { visit.last as f64 } else { visit.score(now) };
+                (path, order)
+            })
+            .collect();
+        matches.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        matches
+            .into_iter()
+            .map(|(path, _)| path)
+            .filter(|path| path.is_dir())
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+}
+
+fn matches_keywords(path: &Path, keywords: &[String]) -> bool {
+    let Some(last) = keywords.last() else {
+        return true;
+    };
+    let text = path.to_string_lossy().to_lowercase();
+    let name = path.file_name().map(|name| name.to_string_lossy().to_lowercase()).unwrap_or_default();
+    if !name.contains(last.as_str()) {
+        return false;
+    }
+    let mut rest = text.as_str();
+    for keyword in keywords {
+        match rest.find(keyword.as_str()) {
+            Some(found) => rest = &rest[found + keyword.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|since| since.as_secs()).unwrap_or_default()
+}
+
+/// The visited folders, provided as context by [`use_visits_provider`].
+#[derive(Clone, Copy)]
+pub struct Visits(Signal<VisitLog>);
+
+impl Visits {
+    /// Counts a visit to `path`.
+    pub fn record(&self, path: &Path) {
+        let mut log = self.0;
+        log.write().record(path, now());
+        config::save_toml_later(VISITS_FILE, &log.peek().savable());
+    }
+
+    /// Up to `limit` folders matching `query`, other than `current`.
+    pub fn query(&self, query: &str, current: &Path, limit: usize) -> Vec<PathBuf> {
+        self.0.read().query(query, current, limit, now())
+    }
+}
+
+/// Loads the visit log and provides [`Visits`].
+pub fn use_visits_provider() {
+    use_context_provider(|| Visits(Signal::new(config::load_toml(VISITS_FILE).unwrap_or_default())));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    const NOW: u64 = 1_700_000_000;
+
+    /// Folders under a fresh temporary directory, since queries skip folders
+    /// that no longer exist.
+    fn folders(name: &str, paths: &[&str]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("frecency-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for path in paths {
+            fs::create_dir_all(dir.join(path)).unwrap();
+        }
+        dir
+    }
+
+    fn visit(log: &mut VisitLog, path: &Path, times: usize, at: u64) {
+        for _ in 0..times {
+            log.record(path, at);
+        }
+    }
+
+    #[test]
+    fn frequent_and_recent_folders_rank_first() {
+        let dir = folders("rank", &["src/app", "src/lib", "docs/app"]);
+        let mut log = VisitLog::default();
+        visit(&mut log, &dir.join("src/app"), 3, NOW - 2 * WEEK);
+        visit(&mut log, &dir.join("src/lib"), 2, NOW - 2 * WEEK);
+        visit(&mut log, &dir.join("docs/app"), 1, NOW - 60);
+
+        let ranked = log.query("", Path::new("/"), 10, NOW);
+        assert_eq!(ranked, [dir.join("docs/app"), dir.join("src/app"), dir.join("src/lib")]);
+        // 1 × 4 for the last hour beats 3 × 0.25 for two weeks ago
+        let ranked = log.query("app", Path::new("/"), 10, NOW);
+        assert_eq!(ranked, [dir.join("docs/app"), dir.join("src/app")]);
+        assert_eq!(log.query("", &dir.join("docs/app"), 1, NOW), [dir.join("src/app")]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn keywords_match_in_order_and_the_last_in_the_name() {
+        let dir = folders("keywords", &["Projects/web/client", "web/projects", "client/web"]);
+        let mut log = VisitLog::default();
+        for path in ["Projects/web/client", "web/projects", "client/web"] {
+            visit(&mut log, &dir.join(path), 1, NOW);
+        }
+
+        assert_eq!(log.query("proj client", Path::new("/"), 10, NOW), [dir.join("Projects/web/client")]);
+        assert_eq!(log.query("PROJECTS", Path::new("/"), 10, NOW), [dir.join("web/projects")]);
+        assert!(log.query("client proj", Path::new("/"), 10, NOW).is_empty());
+        // "web" is in every path but only one folder's name
+        assert_eq!(log.query("web", Path::new("/"), 10, NOW).len(), 1);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn deleted_folders_are_left_out() {
+        let dir = folders("deleted", &["kept", "gone"]);
+        let mut log = VisitLog::default();
+        visit(&mut log, &dir.join("kept"), 1, NOW);
+        visit(&mut log, &dir.join("gone"), 5, NOW);
+        fs::remove_dir(dir.join("gone")).unwrap();
+
+        assert_eq!(log.query("", Path::new("/"), 1, NOW), [dir.join("kept")]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn ranks_age_once_the_total_grows_too_large() {
+        let mut log = VisitLog::default();
+        visit(&mut log, Path::new("/once"), 1, NOW);
+        visit(&mut log, Path::new("/often"), MAX_TOTAL_RANK as usize, NOW);
+
+        let total: f64 = log.visits.values().map(|visit| visit.rank).sum();
+        assert!(total <= MAX_TOTAL_RANK);
+        assert!(!log.visits.contains_key(Path::new("/once")));
+        assert!(log.visits.contains_key(Path::new("/often")));
+    }
+
+    #[test]
+    fn visit_log_round_trips_through_the_settings_file() {
+        let mut log = VisitLog::default();
+        visit(&mut log, Path::new("/home/me/src"), 2, NOW);
+        let text = toml::to_string_pretty(&log.savable()).unwrap();
+        assert_eq!(toml::from_str::<VisitLog>(&text).unwrap(), log);
+    }
+}
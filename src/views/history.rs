@@ -0,0 +1,117 @@
+//! Back and forward stacks of visited locations, kept per tab and for the
+//! right pane.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Locations remembered in each direction.
+const MAX_HISTORY: usize = 50;
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct History {
+    /// Earlier locations, most recent last
+    back: Vec<PathBuf>,
+    /// Locations left by going back, most recent last
+    forward: Vec<PathBuf>,
+}
+
+impl History {
+    pub fn can_go_back(&self) -> bool {
+        !self.back.is_empty()
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        !self.forward.is_empty()
+    }
+
+    /// Records leaving `previous` for somewhere new, which ends the forward
+    /// stack like in a browser.
+    pub fn left(&mut self, previous: PathBuf) {
+        self.back.push(previous);
+        if self.back.len() > MAX_HISTORY {
+            self.back.remove(0);
+        }
+        self.forward.clear();
+    }
+
+    /// Steps back from `current`, returning where to go.
+    pub fn go_back(&mut self, current: &Path) -> Option<PathBuf> {
+        let target = self.back.pop()?;
+        self.forward.push(current.to_path_buf());
+        Some(target)
+    }
+
+    /// Steps forward from `current`, returning where to go.
+    pub fn go_forward(&mut self, current: &Path) -> Option<PathBuf> {
+        let target = self.forward.pop()?;
+        self.back.push(current.to_path_buf());
+        Some(target)
+    }
+
+    /// Forgets the locations in both directions that `keep` rejects.
+    pub fn retain(&mut self, keep: impl Fn(&Path) -> bool) {
+        self.back.retain(|path| keep(path));
+        self.forward.retain(|path| keep(path));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn back_and_forward_retrace_the_visits() {
+        let mut history = History::default();
+        assert!(!history.can_go_back() && !history.can_go_forward());
+        history.left(PathBuf::from("/a"));
+        history.left(PathBuf::from("/b"));
+
+        assert_eq!(history.go_back(Path::new("/c")), Some(PathBuf::from("/b")));
+        assert_eq!(history.go_back(Path::new("/b")), Some(PathBuf::from("/a")));
+        assert_eq!(history.go_back(Path::new("/a")), None);
+        assert!(history.can_go_forward());
+        assert_eq!(history.go_forward(Path::new("/a")), Some(PathBuf::from("/b")));
+        assert_eq!(history.go_forward(Path::new("/b")), Some(PathBuf::from("/c")));
+        assert_eq!(history.go_forward(Path::new("/c")), None);
+    }
+
+    #[test]
+    fn going_somewhere_new_ends_the_forward_stack() {
+        let mut history = History::default();
+        history.left(PathBuf::from("/a"));
+        history.go_back(Path::new("/b"));
+        assert!(history.can_go_forward());
+        history.left(PathBuf::from("/a"));
+        assert!(!history.can_go_forward());
+        assert_eq!(history.go_back(Path::new("/d")), Some(PathBuf::from("/a")));
+    }
+
+    #[test]
+    fn only_the_latest_locations_are_kept() {
+        let mut history = History::default();
+        for i in 0..MAX_HISTORY + 10 {
+            history.left(PathBuf::from(format!("/{i}")));
+        }
+        let mut current = PathBuf::from("/now");
+        let mut steps = 0;
+        while let Some(previous) = history.go_back(&current) {
+            current = previous;
+            steps += 1;
+        }
+        assert_eq!(steps, MAX_HISTORY);
+        assert_eq!(current, PathBuf::from("/10"));
+    }
+
+    #[test]
+    fn retain_filters_both_directions() {
+        let mut history = History::default();
+        for path in ["/keep1", "/drop", "/keep2"] {
+            history.left(PathBuf::from(path));
+        }
+        history.go_back(Path::new("/drop2"));
+        history.retain(|path| !path.to_string_lossy().contains("drop"));
+        assert!(!history.can_go_forward());
+        assert_eq!(history.go_back(Path::new("/x")), Some(PathBuf::from("/keep1")));
+    }
+}
@@ -0,0 +1,241 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{PoisonError, RwLock};
+
+use super::index::IndexEntry;
+use super::{rescan_subtree, FileIndex};
+
+/// Bump whenever [`IndexEntry`] or the layout below changes; older caches are
+/// then ignored and rebuilt.
+const FORMAT_VERSION: u32 = 1;
+const MAGIC: &[u8; 8] = b"FMINDEX\0";
+
+/// Written after [`MAGIC`], followed by `entries` serialized [`IndexEntry`] values.
+#[derive(Serialize, Deserialize)]
+struct Header {
+    version: u32,
+    root: PathBuf,
+    entries: u64,
+}
+
+/// Cache file for `root`: one file per indexed root, named by a hash of its path.
+fn cache_file(root: &Path) -> Option<PathBuf> {
+    let dir = dirs::cache_dir()?.join("file-manager").join("index");
+    Some(dir.join(format!("{:016x}.bin", fnv1a(root.as_os_str().as_encoded_bytes()))))
+}
+
+/// FNV-1a, chosen over `DefaultHasher` because cache names must not change
+/// between Rust releases.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Writes `index` to its cache file. The data goes to a temporary file first so
+/// a crash mid-write never leaves a truncated cache behind.
+pub fn save(index: &FileIndex) -> io::Result<()> {
+    let path = cache_file(index.root())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no cache directory"))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let tmp = path.with_extension("tmp");
+    let mut out = BufWriter::new(File::create(&tmp)?);
+    write_index(&mut out, index)?;
+    out.into_inner().map_err(io::IntoInnerError::into_error)?.sync_all()?;
+
+    fs::rename(&tmp, &path)
+}
+
+fn write_index(out: &mut impl Write, index: &FileIndex) -> io::Result<()> {
+    out.write_all(MAGIC)?;
+    let header = Header {
+        version: FORMAT_VERSION,
+        root: index.root().to_path_buf(),
+        entries: index.len() as u64,
+    };
+    bincode::serialize_into(&mut *out, &header).map_err(io::Error::other)?;
+    for entry in index.entries() {
+        bincode::serialize_into(&mut *out, &entry).map_err(io::Error::other)?;
+    }
+    Ok(())
+}
+
+/// Loads the cached index for `root`. Returns `None` when there is no cache or
+/// it was written by another format version or for another root.
+pub fn load(root: &Path) -> Option<FileIndex> {
+    let mut input = BufReader::new(File::open(cache_file(root)?).ok()?);
+    read_index(&mut input, root)
+}
+
+fn read_index(input: &mut impl Read, root: &Path) -> Option<FileIndex> {
+    let mut magic = [0; MAGIC.len()];
+    input.read_exact(&mut magic).ok()?;
+    if &magic != MAGIC {
+        return None;
+    }
+
+    let header: Header = bincode::deserialize_from(&mut *input).ok()?;
+    if header.version != FORMAT_VERSION || header.root != root {
+        return None;
+    }
+
+    let mut index = FileIndex::new(root);
+    for _ in 0..header.entries {
+        let entry: IndexEntry = bincode::deserialize_from(&mut *input).ok()?;
+        index.restore(entry);
+    }
+    Some(index)
+}
+
+/// Brings an index restored from cache up to date with the disk.
+///
+/// A directory whose mtime still matches the cache has the same entries as
+/// when it was saved, so only changed directories are listed again. Files are
+/// stat'ed to pick up in-place edits, which don't touch the directory mtime.
+/// Anything read from disk is read before the write lock is taken.
+pub fn reconcile(root: &Path, index: &RwLock<FileIndex>) {
+    let (dirs, files) = {
+        let index = index.read().unwrap_or_else(PoisonError::into_inner);
+        (index.directories(), index.files())
+    };
+
+    for dir in std::iter::once(root.to_path_buf()).chain(dirs) {
+        let cached = {
+            let index = index.read().unwrap_or_else(PoisonError::into_inner);
+            if dir != root && !index.contains(&dir) {
+                // Already dropped along with a vanished parent
+                continue;
+            }
+            index.modified(&dir)
+        };
+
+        let metadata = match fs::symlink_metadata(&dir) {
+            Ok(metadata) if metadata.is_dir() => metadata,
+            _ => {
+                rescan_subtree(root, &dir, index);
+                continue;
+            }
+        };
+        if cached.is_some() && metadata.modified().ok() == cached {
+            continue;
+        }
+
+        let Ok(listing) = fs::read_dir(&dir) else {
+            continue;
+        };
+        let on_disk: HashSet<PathBuf> = listing.flatten().map(|entry| entry.path()).collect();
+        let known: HashSet<PathBuf> = index
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .children(&dir)
+            .into_iter()
+            .collect();
+
+        for gone in known.difference(&on_disk) {
+            index.write().unwrap_or_else(PoisonError::into_inner).remove_tree(gone);
+        }
+        for new in on_disk.difference(&known) {
+            rescan_subtree(root, new, index);
+        }
+        if dir != root {
+            let entry = FileIndex::read_entry(dir, &metadata);
+            index.write().unwrap_or_else(PoisonError::into_inner).restore(entry);
+        }
+    }
+
+    for file in files {
+        let cached = index.read().unwrap_or_else(PoisonError::into_inner).modified(&file);
+        let Some(cached) = cached else {
+            continue;
+        };
+        match fs::symlink_metadata(&file) {
+            Ok(metadata) if metadata.modified().ok() == Some(cached) => {}
+            Ok(metadata) => {
+                let entry = FileIndex::read_entry(file, &metadata);
+                index.write().unwrap_or_else(PoisonError::into_inner).restore(entry);
+            }
+            Err(_) => index
+                .write()
+                .unwrap_or_else(PoisonError::into_inner)
+                .remove_tree(&file),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    fn scratch(case: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("index-cache-{}-{case}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Backdates `path` so that a later change gives it a different mtime even
+    /// on filesystems with coarse timestamps.
+    fn backdate(path: &Path) {
+        let old = SystemTime::now() - Duration::from_secs(3600);
+        File::open(path).unwrap().set_modified(old).unwrap();
+    }
+
+    #[test]
+    fn saved_index_loads_back_unchanged() {
+        let root = scratch("round-trip");
+        fs::create_dir(root.join("src")).unwrap();
+        fs::write(root.join("src/main.rs"), "fn main() { println!(\"hello\"); }").unwrap();
+        fs::write(root.join("notes.txt"), "hello again").unwrap();
+        fs::write(root.join("image.png"), [0x89, b'P', b'N', b'G']).unwrap();
+        let index = FileIndex::build(&root);
+
+        let mut bytes = Vec::new();
+        write_index(&mut bytes, &index).unwrap();
+        let loaded = read_index(&mut bytes.as_slice(), &root).unwrap();
+
+        assert_eq!(loaded.len(), 4);
+        assert_eq!(loaded.entries().collect::<Vec<_>>(), index.entries().collect::<Vec<_>>());
+        assert!(loaded.entries().any(|entry| entry.path.ends_with("notes.txt") && entry.terms.is_some()));
+        // A cache written for another root or in another format is ignored
+        assert!(read_index(&mut bytes.as_slice(), Path::new("/elsewhere")).is_none());
+        bytes[0] = b'X';
+        assert!(read_index(&mut bytes.as_slice(), &root).is_none());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn reconcile_picks_up_added_removed_and_modified_files() {
+        let root = scratch("reconcile");
+        fs::create_dir(root.join("docs")).unwrap();
+        fs::write(root.join("docs/kept.txt"), "unchanged").unwrap();
+        fs::write(root.join("docs/edited.txt"), "before").unwrap();
+        fs::write(root.join("gone.txt"), "soon deleted").unwrap();
+        for path in ["docs/kept.txt", "docs/edited.txt", "gone.txt", "docs", ""] {
+            backdate(&root.join(path));
+        }
+        let index = RwLock::new(FileIndex::build(&root));
+
+        fs::remove_file(root.join("gone.txt")).unwrap();
+        fs::write(root.join("docs/added.txt"), "brand new").unwrap();
+        fs::write(root.join("docs/edited.txt"), "after the edit").unwrap();
+        reconcile(&root, &index);
+
+        let index = index.into_inner().unwrap();
+        assert!(!index.contains(&root.join("gone.txt")));
+        assert!(index.contains(&root.join("docs/added.txt")));
+        assert!(index.contains(&root.join("docs/kept.txt")));
+        let edited = index.entries().find(|entry| entry.path.ends_with("edited.txt")).unwrap();
+        assert!(edited.terms.unwrap().iter().any(|(term, _)| term == "after"));
+        assert_eq!(index.len(), 4);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
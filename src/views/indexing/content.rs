@@ -0,0 +1,449 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, Metadata};
+use std::path::{Path, PathBuf};
+
+/// Files larger than this are not tokenized.
+const MAX_FILE_SIZE: u64 = 1024 * 1024;
+/// Leading bytes inspected for NUL to reject binary files with a text extension.
+const BINARY_SNIFF_LEN: usize = 8 * 1024;
+const MAX_SNIPPETS: usize = 3;
+const MAX_SNIPPET_LEN: usize = 160;
+
+// BM25 tuning, the usual defaults
+const K1: f32 = 1.2;
+const B: f32 = 0.75;
+
+const TEXT_EXTENSIONS: &[&str] = &[
+    // source
+    "rs", "c", "h", "cc", "cpp", "hpp", "cs", "go", "java", "kt", "js", "jsx", "ts", "tsx", "mjs",
+    "py", "rb", "php", "swift", "scala", "lua", "sh", "bash", "zsh", "fish", "ps1", "sql", "html",
+    "htm", "css", "scss", "vue", "svelte",
+    // docs
+    "md", "markdown", "rst", "txt", "adoc", "org", "tex",
+    // logs
+    "log", "out",
+    // config
+    "toml", "yaml", "yml", "json", "jsonc", "ini", "cfg", "conf", "config", "env", "properties",
+    "xml", "lock", "gradle", "csv", "tsv",
+];
+
+const TEXT_FILE_NAMES: &[&str] = &[
+    "makefile", "dockerfile", "license", "readme", "changelog", ".gitignore", ".gitattributes",
+    ".editorconfig", ".env",
+];
+
+type DocId = usize;
+
+#[derive(Debug)]
+struct Document {
+    path: PathBuf,
+    /// Distinct terms of the document with their frequencies, kept so removal
+    /// doesn't scan every posting list and the document can be cached
+    terms: Vec<(String, u32)>,
+    len: u32,
+}
+
+/// Filters and free text for a content search.
+///
+/// Parsed from the navbar search box: `ext:rs,toml` restricts extensions and
+/// `path:src/views` restricts to a path prefix; everything else is searched for.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ContentQuery {
+    pub text: String,
+    pub extensions: Vec<String>,
+    pub path_prefix: Option<PathBuf>,
+}
+
+impl ContentQuery {
+    pub fn parse(input: &str) -> Self {
+        let mut query = Self::default();
+        let mut text = Vec::new();
+
+        for word in input.split_whitespace() {
+            if let Some(exts) = word.strip_prefix("ext:") {
+                query.extensions.extend(
+                    exts.split(',')
+                        .map(|ext| ext.trim_start_matches('.').to_lowercase())
+                        .filter(|ext| !ext.is_empty()),
+                );
+            } else if let Some(prefix) = word.strip_prefix("path:") {
+                if !prefix.is_empty() {
+                    query.path_prefix = Some(PathBuf::from(prefix));
+                }
+            } else {
+                text.push(word);
+            }
+        }
+
+        query.text = text.join(" ");
+        query
+    }
+
+    fn accepts(&self, path: &Path) -> bool {
+        if let Some(prefix) = &self.path_prefix {
+            if !path.starts_with(prefix) {
+                return false;
+            }
+        }
+        self.extensions.is_empty()
+            || path
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase())
+                .is_some_and(|ext| self.extensions.contains(&ext))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snippet {
+    /// 1-based line number
+    pub line: usize,
+    pub text: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContentMatch {
+    pub path: PathBuf,
+    pub score: f32,
+}
+
+/// Inverted index over the contents of text-like files.
+#[derive(Debug, Default)]
+pub struct ContentIndex {
+    docs: Vec<Option<Document>>,
+    ids: HashMap<PathBuf, DocId>,
+    free: Vec<DocId>,
+    /// Term -> (document -> term frequency). Ordered so the last query word can
+    /// be expanded as a prefix while the user is still typing it.
+    postings: BTreeMap<String, HashMap<DocId, u32>>,
+    total_len: u64,
+}
+
+impl ContentIndex {
+    /// Whether `path` looks like a text file worth tokenizing.
+    pub fn is_indexable(path: &Path, metadata: &Metadata) -> bool {
+        if !metadata.is_file() || metadata.len() > MAX_FILE_SIZE {
+            return false;
+        }
+        let ext_match = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .is_some_and(|ext| TEXT_EXTENSIONS.contains(&ext.as_str()));
+        let name_match = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+            .is_some_and(|name| TEXT_FILE_NAMES.contains(&name.as_str()));
+        ext_match || name_match
+    }
+
+    /// Reads and counts the terms of `path` if it is a text file. Touches the
+    /// disk but not the index, so it can run before taking the index lock.
+    pub fn read_terms(path: &Path, metadata: &Metadata) -> Option<Vec<(String, u32)>> {
+        if !Self::is_indexable(path, metadata) {
+            return None;
+        }
+        let text = read_text(path)?;
+
+        let mut counts: HashMap<String, u32> = HashMap::new();
+        for token in tokenize(&text) {
+            *counts.entry(token).or_default() += 1;
+        }
+        Some(counts.into_iter().collect())
+    }
+
+    /// Term frequencies recorded for `path`, if it is indexed.
+    pub fn terms(&self, path: &Path) -> Option<&[(String, u32)]> {
+        let id = self.ids.get(path)?;
+        self.docs[*id].as_ref().map(|doc| doc.terms.as_slice())
+    }
+
+    /// Adds a document from already counted terms, e.g. when restoring from cache.
+    pub fn insert_terms(&mut self, path: &Path, terms: Vec<(String, u32)>) {
+        self.remove(path);
+
+        let id = self.free.pop().unwrap_or(self.docs.len());
+        let mut len = 0;
+        for (term, tf) in &terms {
+            self.postings.entry(term.clone()).or_default().insert(id, *tf);
+            len += tf;
+        }
+
+        let doc = Document {
+            path: path.to_path_buf(),
+            terms,
+            len,
+        };
+        if id == self.docs.len() {
+            self.docs.push(Some(doc));
+        } else {
+            self.docs[id] = Some(doc);
+        }
+        self.ids.insert(path.to_path_buf(), id);
+        self.total_len += u64::from(len);
+    }
+
+    pub fn remove(&mut self, path: &Path) {
+        let Some(id) = self.ids.remove(path) else {
+            return;
+        };
+        let Some(doc) = self.docs[id].take() else {
+            return;
+        };
+
+        for (term, _) in &doc.terms {
+            if let Some(docs) = self.postings.get_mut(term) {
+                docs.remove(&id);
+                if docs.is_empty() {
+                    self.postings.remove(term);
+                }
+            }
+        }
+        self.total_len -= u64::from(doc.len);
+        self.free.push(id);
+    }
+
+    /// Ranks documents containing every query word (the last one as a prefix)
+    /// by BM25. Only the index is consulted; [`read_snippets`] goes to the disk
+    /// for the matching lines.
+    pub fn search(&self, query: &ContentQuery, limit: usize) -> Vec<ContentMatch> {
+        let words: Vec<String> = tokenize(&query.text).collect();
+        if words.is_empty() || self.ids.is_empty() {
+            return Vec::new();
+        }
+
+        let doc_count = self.ids.len() as f32;
+        let avg_len = (self.total_len as f32 / doc_count).max(1.0);
+        let mut scores: Option<HashMap<DocId, f32>> = None;
+
+        for (i, word) in words.iter().enumerate() {
+            let is_last = i + 1 == words.len();
+            let mut word_scores: HashMap<DocId, f32> = HashMap::new();
+
+            for (term, docs) in self.matching_terms(word, is_last) {
+                let df = docs.len() as f32;
+                let idf = ((doc_count - df + 0.5) / (df + 0.5) + 1.0).ln();
+                for (&id, &tf) in docs {
+                    let Some(doc) = &self.docs[id] else {
+                        continue;
+                    };
+                    let tf = tf as f32;
+                    let norm = K1 * (1.0 - B + B * doc.len as f32 / avg_len);
+                    // A prefix match on a longer term is worth a bit less than an exact hit
+                    let weight = if term == word { 1.0 } else { 0.8 };
+                    *word_scores.entry(id).or_default() += weight * idf * tf * (K1 + 1.0) / (tf + norm);
+                }
+            }
+
+            scores = Some(match scores {
+                None => word_scores,
+                Some(mut acc) => {
+                    acc.retain(|id, _| word_scores.contains_key(id));
+                    for (id, score) in acc.iter_mut() {
+                        *score += word_scores[id];
+                    }
+                    acc
+                }
+            });
+        }
+
+        let mut ranked: Vec<(&Document, f32)> = scores
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(id, score)| self.docs[id].as_ref().map(|doc| (doc, score)))
+            .filter(|(doc, _)| query.accepts(&doc.path))
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.path.cmp(&b.0.path)));
+
+        ranked
+            .into_iter()
+            .take(limit)
+            .map(|(doc, score)| ContentMatch {
+                path: doc.path.clone(),
+                score,
+            })
+            .collect()
+    }
+
+    fn matching_terms<'a>(
+        &'a self,
+        word: &'a str,
+        as_prefix: bool,
+    ) -> Box<dyn Iterator<Item = (&'a String, &'a HashMap<DocId, u32>)> + 'a> {
+        if as_prefix {
+            Box::new(
+                self.postings
+                    .range(word.to_string()..)
+                    .take_while(move |(term, _)| term.starts_with(word)),
+            )
+        } else {
+            Box::new(self.postings.get_key_value(word).into_iter())
+        }
+    }
+}
+
+/// Lowercased runs of alphanumerics and underscores, so `max_connections`
+/// stays one searchable token.
+fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|token| !token.is_empty() && token.len() <= 64)
+        .map(str::to_lowercase)
+}
+
+fn read_text(path: &Path) -> Option<String> {
+    let bytes = fs::read(path).ok()?;
+    if bytes[..bytes.len().min(BINARY_SNIFF_LEN)].contains(&0) {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// The first lines of `path` containing a word of `query`. Reads the file, so
+/// keep it off the UI thread.
+pub fn read_snippets(path: &Path, query: &ContentQuery) -> Vec<Snippet> {
+    let Some(text) = read_text(path) else {
+        return Vec::new();
+    };
+    let words: Vec<String> = tokenize(&query.text).collect();
+
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            let line = line.to_lowercase();
+            words.iter().any(|word| line.contains(word.as_str()))
+        })
+        .take(MAX_SNIPPETS)
+        .map(|(i, line)| {
+            let line = line.trim();
+            let text = match line.char_indices().nth(MAX_SNIPPET_LEN) {
+                Some((cut, _)) => format!("{}…", &line[..cut]),
+                None => line.to_string(),
+            };
+            Snippet { line: i + 1, text }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(docs: &[(&str, &str)]) -> ContentIndex {
+        let mut index = ContentIndex::default();
+        for (path, text) in docs {
+            let mut counts: HashMap<String, u32> = HashMap::new();
+            for token in tokenize(text) {
+                *counts.entry(token).or_default() += 1;
+            }
+            index.insert_terms(Path::new(path), counts.into_iter().collect());
+        }
+        index
+    }
+
+    fn paths(matches: Vec<ContentMatch>) -> Vec<PathBuf> {
+        matches.into_iter().map(|hit| hit.path).collect()
+    }
+
+    #[test]
+    fn parses_filters_out_of_the_query() {
+        let query = ContentQuery::parse("ext:.RS,toml max path:src/views conn");
+        assert_eq!(query.text, "max conn");
+        assert_eq!(query.extensions, ["rs", "toml"]);
+        assert_eq!(query.path_prefix, Some(PathBuf::from("src/views")));
+    }
+
+    #[test]
+    fn tokens_keep_underscores_and_ignore_case() {
+        let tokens: Vec<String> = tokenize("Max_Connections = 10; // max").collect();
+        assert_eq!(tokens, ["max_connections", "10", "max"]);
+    }
+
+    #[test]
+    fn more_frequent_terms_rank_higher() {
+        let index = index(&[
+            ("/a.txt", "retry once"),
+            ("/b.txt", "retry retry retry"),
+            ("/c.txt", "nothing here"),
+        ]);
+
+        let hits = index.search(&ContentQuery::parse("retry"), 10);
+        assert_eq!(paths(hits.clone()), [PathBuf::from("/b.txt"), PathBuf::from("/a.txt")]);
+        assert!(hits[0].score > hits[1].score);
+    }
+
+    #[test]
+    fn rarer_terms_weigh_more() {
+        let index = index(&[
+            ("/a.txt", "common rare"),
+            ("/b.txt", "common common"),
+            ("/c.txt", "common"),
+        ]);
+
+        let rare = index.search(&ContentQuery::parse("rare"), 10);
+        let common = index.search(&ContentQuery::parse("common"), 10);
+        assert!(rare[0].score > common[0].score);
+    }
+
+    #[test]
+    fn every_word_must_match_and_the_last_is_a_prefix() {
+        let index = index(&[
+            ("/a.txt", "timeout connection"),
+            ("/b.txt", "timeout only"),
+            ("/c.txt", "connect timeout"),
+        ]);
+
+        assert_eq!(
+            paths(index.search(&ContentQuery::parse("timeout conn"), 10)),
+            [PathBuf::from("/a.txt"), PathBuf::from("/c.txt")]
+        );
+        // Only the last word is completed
+        assert!(index.search(&ContentQuery::parse("conn timeout"), 10).is_empty());
+    }
+
+    #[test]
+    fn exact_terms_beat_prefix_matches() {
+        let index = index(&[("/a.txt", "config"), ("/b.txt", "conf")]);
+
+        assert_eq!(
+            paths(index.search(&ContentQuery::parse("conf"), 10)),
+            [PathBuf::from("/b.txt"), PathBuf::from("/a.txt")]
+        );
+    }
+
+    #[test]
+    fn filters_by_extension_and_path() {
+        let index = index(&[
+            ("/src/a.rs", "token"),
+            ("/src/b.toml", "token"),
+            ("/docs/c.rs", "token"),
+        ]);
+
+        assert_eq!(paths(index.search(&ContentQuery::parse("token ext:rs path:/src"), 10)), [PathBuf::from("/src/a.rs")]);
+    }
+
+    #[test]
+    fn removed_documents_stop_matching() {
+        let mut index = index(&[("/a.txt", "alpha"), ("/b.txt", "alpha beta")]);
+
+        index.remove(Path::new("/b.txt"));
+        assert_eq!(paths(index.search(&ContentQuery::parse("alpha"), 10)), [PathBuf::from("/a.txt")]);
+        assert!(index.search(&ContentQuery::parse("beta"), 10).is_empty());
+        assert!(!index.postings.contains_key("beta"));
+        assert_eq!(index.total_len, 1);
+    }
+
+    #[test]
+    fn snippets_are_the_first_matching_lines() {
+        let path = std::env::temp_dir().join(format!("content-snippets-{}.txt", std::process::id()));
+        fs::write(&path, "first\n  Retry here  \nnone\nretry again\nretry more\nretry last\n").unwrap();
+
+        let snippets = read_snippets(&path, &ContentQuery::parse("retry"));
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            snippets,
+            [
+                Snippet { line: 2, text: "Retry here".to_string() },
+                Snippet { line: 4, text: "retry again".to_string() },
+                Snippet { line: 5, text: "retry more".to_string() },
+            ]
+        );
+    }
+}
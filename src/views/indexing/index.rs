@@ -1,16 +1,368 @@
-struct FileIndex {
-    trie: RadixTrie<PathBuf>,      // Prefix search
-    content_index: TantivyIndex,    // Full-text
-    last_modified: BTreeMap<SystemTime, PathBuf>
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, Metadata};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use super::content::{ContentIndex, ContentMatch, ContentQuery};
+use super::trie::RadixTrie;
+
+/// A serializable index entry, as stored in the on-disk cache.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub path: PathBuf,
+    pub is_dir: bool,
+    pub modified: SystemTime,
+    /// Term frequencies, for files that are in the content index
+    pub terms: Option<Vec<(String, u32)>>,
+}
+
+/// A file name match, with what the result list needs to show it without
+/// going back to the disk.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchHit {
+    pub path: PathBuf,
+    pub is_dir: bool,
+}
+
+#[derive(Debug, Clone)]
+struct IndexedFile {
+    /// Lowercased file name, used for case-insensitive matching
+    name: String,
+    is_dir: bool,
+    modified: SystemTime,
+}
+
+/// In-memory index of every file and directory below a root.
+#[derive(Debug)]
+pub struct FileIndex {
+    root: PathBuf,
+    trie: RadixTrie<PathBuf>,      // Prefix search on file names
+    content_index: ContentIndex,   // Full-text
+    files: BTreeMap<PathBuf, IndexedFile>,
+    last_modified: BTreeMap<SystemTime, BTreeSet<PathBuf>>,
 }
 
 impl FileIndex {
-    fn search(&self, query: &str) -> Vec<PathBuf> {
-        // SIMD-accelerated search
-        if query.len() < 4 {
-            self.trie.get_prefix(query) 
-        } else {
-            self.content_index.search(query)
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            trie: RadixTrie::new(),
+            content_index: ContentIndex::default(),
+            files: BTreeMap::new(),
+            last_modified: BTreeMap::new(),
         }
     }
-}
\ No newline at end of file
+
+    /// Crawls `root` and indexes everything below it. Symlinked directories are
+    /// not followed and unreadable directories are skipped.
+    pub fn build(root: impl Into<PathBuf>) -> Self {
+        let mut index = Self::new(root);
+        let root = index.root.clone();
+        index.crawl(&root);
+        index
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        self.files.contains_key(path)
+    }
+
+    fn crawl(&mut self, dir: &Path) {
+        for (path, metadata) in Self::walk(dir) {
+            self.insert(path, &metadata);
+        }
+    }
+
+    /// Lists everything below `dir` without touching the index, so callers can
+    /// do the slow directory walk before taking a write lock.
+    pub fn walk(dir: &Path) -> Vec<(PathBuf, Metadata)> {
+        let mut found = Vec::new();
+        let mut pending = vec![dir.to_path_buf()];
+
+        while let Some(dir) = pending.pop() {
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+
+            for entry in entries.flatten() {
+                // DirEntry::metadata does not traverse symlinks
+                let Ok(metadata) = entry.metadata() else {
+                    continue;
+                };
+                let path = entry.path();
+                if metadata.is_dir() {
+                    pending.push(path.clone());
+                }
+                found.push((path, metadata));
+            }
+        }
+        found
+    }
+
+    /// Adds `path` to the index, replacing any previous entry for it.
+    pub fn insert(&mut self, path: PathBuf, metadata: &Metadata) {
+        self.restore(Self::read_entry(path, metadata));
+    }
+
+    /// Everything the index keeps about `path`, read from disk without
+    /// touching the index. Text files are read whole, so callers holding the
+    /// index behind a lock read entries first and [`restore`](Self::restore)
+    /// them under the lock.
+    pub fn read_entry(path: PathBuf, metadata: &Metadata) -> IndexEntry {
+        IndexEntry {
+            is_dir: metadata.is_dir(),
+            modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            terms: ContentIndex::read_terms(&path, metadata),
+            path,
+        }
+    }
+
+    /// Records the name and timestamp of `path` without reading its contents.
+    fn insert_entry(&mut self, path: PathBuf, is_dir: bool, modified: SystemTime) -> bool {
+        self.remove(&path);
+
+        let Some(name) = path.file_name() else {
+            return false;
+        };
+        let name = name.to_string_lossy().to_lowercase();
+
+        self.trie.insert(&name, path.clone());
+        self.last_modified
+            .entry(modified)
+            .or_default()
+            .insert(path.clone());
+        self.files.insert(path, IndexedFile { name, is_dir, modified });
+        true
+    }
+
+    /// Adds an entry from [`FileIndex::read_entry`] or one saved by
+    /// [`FileIndex::entries`], replacing any previous entry for its path.
+    pub fn restore(&mut self, entry: IndexEntry) {
+        let IndexEntry { path, is_dir, modified, terms } = entry;
+        if self.insert_entry(path.clone(), is_dir, modified) {
+            if let Some(terms) = terms {
+                self.content_index.insert_terms(&path, terms);
+            }
+        }
+    }
+
+    /// Everything needed to rebuild the index without touching the filesystem.
+    pub fn entries(&self) -> impl Iterator<Item = IndexEntry> + '_ {
+        self.files.iter().map(|(path, file)| IndexEntry {
+            path: path.clone(),
+            is_dir: file.is_dir,
+            modified: file.modified,
+            terms: self.content_index.terms(path).map(<[_]>::to_vec),
+        })
+    }
+
+    /// Timestamp recorded for `path` when it was last indexed.
+    pub fn modified(&self, path: &Path) -> Option<SystemTime> {
+        self.files.get(path).map(|file| file.modified)
+    }
+
+    /// Indexed directories, shallowest first.
+    pub fn directories(&self) -> Vec<PathBuf> {
+        let mut dirs: Vec<PathBuf> = self
+            .files
+            .iter()
+            .filter(|(_, file)| file.is_dir)
+            .map(|(path, _)| path.clone())
+            .collect();
+        dirs.sort_by_key(|path| path.components().count());
+        dirs
+    }
+
+    /// Indexed paths that are not directories.
+    pub fn files(&self) -> Vec<PathBuf> {
+        self.files
+            .iter()
+            .filter(|(_, file)| !file.is_dir)
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+
+    /// Indexed entries directly inside `dir`.
+    pub fn children(&self, dir: &Path) -> Vec<PathBuf> {
+        self.files
+            .range(dir.to_path_buf()..)
+            .map(|(path, _)| path)
+            .skip_while(|path| *path == dir)
+            .take_while(|path| path.starts_with(dir))
+            .filter(|path| path.parent() == Some(dir))
+            .cloned()
+            .collect()
+    }
+
+    /// Drops `path` from the index. Returns false if it was not indexed.
+    pub fn remove(&mut self, path: &Path) -> bool {
+        let Some(file) = self.files.remove(path) else {
+            return false;
+        };
+
+        self.trie.remove(&file.name, |p| p == path);
+        self.content_index.remove(path);
+        if let Some(paths) = self.last_modified.get_mut(&file.modified) {
+            paths.remove(path);
+            if paths.is_empty() {
+                self.last_modified.remove(&file.modified);
+            }
+        }
+        true
+    }
+
+    /// Drops `path` and everything indexed below it.
+    pub fn remove_tree(&mut self, path: &Path) {
+        // Paths order component-wise, so descendants directly follow their parent
+        let doomed: Vec<PathBuf> = self
+            .files
+            .range(path.to_path_buf()..)
+            .map(|(p, _)| p)
+            .take_while(|p| p.starts_with(path))
+            .cloned()
+            .collect();
+        for path in doomed {
+            self.remove(&path);
+        }
+    }
+
+    /// Files whose name starts with `prefix`, shortest names first.
+    pub fn search_prefix(&self, prefix: &str, limit: usize) -> Vec<PathBuf> {
+        let prefix = prefix.to_lowercase();
+        let mut hits: Vec<&PathBuf> = self.trie.get_prefix(&prefix);
+        hits.sort_by_key(|path| (self.files[*path].name.len(), *path));
+        hits.into_iter().take(limit).cloned().collect()
+    }
+
+    /// Files whose name contains `needle` anywhere, shortest names first.
+    pub fn search_substring(&self, needle: &str, limit: usize) -> Vec<PathBuf> {
+        let needle = needle.to_lowercase();
+        let mut hits: Vec<(&PathBuf, &IndexedFile)> = self
+            .files
+            .iter()
+            .filter(|(_, file)| file.name.contains(&needle))
+            .collect();
+        hits.sort_by_key(|(path, file)| (file.name.len(), *path));
+        hits.into_iter().take(limit).map(|(path, _)| path.clone()).collect()
+    }
+
+    /// Filename lookup: prefix matches first, then substring matches for
+    /// queries long enough to be selective.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchHit> {
+        let query = query.trim();
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        let mut results = self.search_prefix(query, limit);
+        if query.chars().count() >= 3 && results.len() < limit {
+            for path in self.search_substring(query, limit) {
+                if results.len() == limit {
+                    break;
+                }
+                if !results.contains(&path) {
+                    results.push(path);
+                }
+            }
+        }
+        results.into_iter().map(|path| self.hit(path)).collect()
+    }
+
+    /// Full-text search over the indexed text files. A relative path filter is
+    /// resolved against the index root.
+    pub fn search_content(&self, query: &ContentQuery, limit: usize) -> Vec<ContentMatch> {
+        let mut query = query.clone();
+        if let Some(prefix) = query.path_prefix.take() {
+            query.path_prefix = Some(self.root.join(prefix));
+        }
+        self.content_index.search(&query, limit)
+    }
+
+    /// The most recently modified entries, newest first.
+    pub fn recently_modified(&self, limit: usize) -> Vec<SearchHit> {
+        self.last_modified
+            .values()
+            .rev()
+            .flatten()
+            .take(limit)
+            .map(|path| self.hit(path.clone()))
+            .collect()
+    }
+
+    fn hit(&self, path: PathBuf) -> SearchHit {
+        let is_dir = self.files.get(&path).is_some_and(|file| file.is_dir);
+        SearchHit { path, is_dir }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Paths ending in `/` are indexed as directories.
+    fn index(paths: &[&str]) -> FileIndex {
+        let mut index = FileIndex::new("/r");
+        for (i, path) in paths.iter().enumerate() {
+            let modified = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(i as u64);
+            index.insert_entry(PathBuf::from(path.trim_end_matches('/')), path.ends_with('/'), modified);
+        }
+        index
+    }
+
+    fn paths(hits: Vec<SearchHit>) -> Vec<PathBuf> {
+        hits.into_iter().map(|hit| hit.path).collect()
+    }
+
+    #[test]
+    fn prefix_matches_come_first_shortest_name_first() {
+        let index = index(&["/r/Cargo.toml", "/r/src/car.rs", "/r/src/scar.rs", "/r/notes.txt"]);
+
+        assert_eq!(
+            paths(index.search("car", 10)),
+            [PathBuf::from("/r/src/car.rs"), PathBuf::from("/r/Cargo.toml"), PathBuf::from("/r/src/scar.rs")]
+        );
+        // Short queries only match prefixes
+        assert_eq!(index.search("ca", 10).len(), 2);
+        assert_eq!(paths(index.search("car", 1)), [PathBuf::from("/r/src/car.rs")]);
+        assert!(index.search("  ", 10).is_empty());
+    }
+
+    #[test]
+    fn remove_tree_drops_descendants_only() {
+        let mut index = index(&["/r/a", "/r/a/one", "/r/a/two", "/r/ab"]);
+
+        index.remove_tree(Path::new("/r/a"));
+        assert_eq!(index.len(), 1);
+        assert!(index.contains(Path::new("/r/ab")));
+        assert!(index.search("one", 10).is_empty());
+    }
+
+    #[test]
+    fn recently_modified_is_newest_first() {
+        let index = index(&["/r/old", "/r/mid", "/r/new"]);
+
+        assert_eq!(paths(index.recently_modified(2)), [PathBuf::from("/r/new"), PathBuf::from("/r/mid")]);
+    }
+
+    #[test]
+    fn hits_know_whether_they_are_folders() {
+        let index = index(&["/r/docs/", "/r/docs.txt"]);
+
+        assert_eq!(
+            index.search("docs", 10),
+            [
+                SearchHit { path: PathBuf::from("/r/docs"), is_dir: true },
+                SearchHit { path: PathBuf::from("/r/docs.txt"), is_dir: false },
+            ]
+        );
+        assert!(index.recently_modified(1)[0].path.ends_with("docs.txt"));
+    }
+}
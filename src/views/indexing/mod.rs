@@ -0,0 +1,141 @@
+use dioxus::prelude::*;
+use futures_channel::mpsc::{self, UnboundedSender};
+use futures_util::StreamExt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard};
+
+mod cache;
+mod content;
+mod index;
+mod trie;
+mod watcher;
+
+pub use content::{read_snippets, ContentMatch, ContentQuery, Snippet};
+pub use index::{FileIndex, SearchHit};
+use index::IndexEntry;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IndexStatus {
+    Building,
+    Ready { files: usize },
+}
+
+/// Shared handle to the application's [`FileIndex`], provided as context by `App`.
+#[derive(Clone)]
+pub struct SearchIndex {
+    root: PathBuf,
+    index: Arc<RwLock<FileIndex>>,
+    status: Signal<IndexStatus>,
+}
+
+impl SearchIndex {
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Current build status. Reading it subscribes the caller, so memos that
+    /// query the index re-run once a build completes.
+    pub fn status(&self) -> IndexStatus {
+        (self.status)()
+    }
+
+    pub fn read(&self) -> RwLockReadGuard<'_, FileIndex> {
+        self.index.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Loads or crawls the root on a background thread, then keeps the index
+    /// current from filesystem change notifications for as long as the app runs.
+    fn start(&self) {
+        let (tx, mut rx) = mpsc::unbounded();
+        let root = self.root.clone();
+        let index = self.index.clone();
+        std::thread::spawn(move || run_indexer(root, &index, tx));
+
+        let mut status = self.status;
+        spawn(async move {
+            while let Some(files) = rx.next().await {
+                status.set(IndexStatus::Ready { files });
+            }
+        });
+    }
+}
+
+/// Creates the [`SearchIndex`] for `root`, starts the initial crawl and
+/// provides the handle to every component below the caller.
+pub fn use_search_index_provider(root: PathBuf) -> SearchIndex {
+    use_context_provider(move || {
+        let handle = SearchIndex {
+            index: Arc::new(RwLock::new(FileIndex::new(root.clone()))),
+            root,
+            status: Signal::new(IndexStatus::Building),
+        };
+        handle.start();
+        handle
+    })
+}
+
+/// Background side of [`SearchIndex`].
+///
+/// The watch is set up before anything is read from disk, so changes made while
+/// loading or crawling are queued and replayed afterwards instead of being lost.
+/// A cached index is published immediately and reconciled with the disk after.
+fn run_indexer(root: PathBuf, index: &RwLock<FileIndex>, updates: UnboundedSender<usize>) {
+    let events = watcher::watch(&root);
+
+    let publish = |index: &RwLock<FileIndex>| {
+        let files = index.read().unwrap_or_else(PoisonError::into_inner).len();
+        updates.unbounded_send(files).is_ok()
+    };
+
+    match cache::load(&root) {
+        Some(cached) => {
+            *index.write().unwrap_or_else(PoisonError::into_inner) = cached;
+            if !publish(index) {
+                return;
+            }
+            cache::reconcile(&root, index);
+        }
+        None => {
+            *index.write().unwrap_or_else(PoisonError::into_inner) = FileIndex::build(root.clone());
+        }
+    }
+    if !publish(index) {
+        return;
+    }
+    save(index);
+
+    if let Some((_watcher, rx)) = events {
+        watcher::process_events(&root, &rx, index, &updates);
+    }
+}
+
+fn save(index: &RwLock<FileIndex>) {
+    let index = index.read().unwrap_or_else(PoisonError::into_inner);
+    if let Err(err) = cache::save(&index) {
+        eprintln!("Could not save the index of {}: {err}", index.root().display());
+    }
+}
+
+/// Re-indexes the subtree at `dir`. The walk and the reading of text files
+/// happen before taking the write lock, which searches on the UI thread wait for.
+fn rescan_subtree(root: &Path, dir: &Path, index: &RwLock<FileIndex>) {
+    let metadata = fs::symlink_metadata(dir).ok();
+    let mut entries = match &metadata {
+        Some(metadata) if metadata.is_dir() => FileIndex::walk(dir),
+        _ => Vec::new(),
+    };
+    if let Some(metadata) = metadata.filter(|_| dir != root) {
+        entries.push((dir.to_path_buf(), metadata));
+    }
+    let entries: Vec<IndexEntry> = entries
+        .into_iter()
+        .map(|(path, metadata)| FileIndex::read_entry(path, &metadata))
+        .collect();
+
+    let mut index = index.write().unwrap_or_else(PoisonError::into_inner);
+    index.remove_tree(dir);
+    for entry in entries {
+        index.restore(entry);
+    }
+}
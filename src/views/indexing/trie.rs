@@ -0,0 +1,219 @@
+/// A compressed prefix tree mapping string keys to any number of values.
+///
+/// Edges carry whole string fragments instead of single characters, so a tree
+/// holding every file name of a large directory stays shallow.
+#[derive(Debug)]
+pub struct RadixTrie<T> {
+    root: Node<T>,
+}
+
+#[derive(Debug)]
+struct Node<T> {
+    children: Vec<(String, Node<T>)>,
+    values: Vec<T>,
+}
+
+impl<T> Default for Node<T> {
+    fn default() -> Self {
+        Self {
+            children: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+}
+
+impl<T> Default for RadixTrie<T> {
+    fn default() -> Self {
+        Self {
+            root: Node::default(),
+        }
+    }
+}
+
+impl<T> RadixTrie<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, key: &str, value: T) {
+        let mut node = &mut self.root;
+        let mut rest = key;
+
+        loop {
+            if rest.is_empty() {
+                node.values.push(value);
+                return;
+            }
+
+            let found = node
+                .children
+                .iter()
+                .position(|(label, _)| common_prefix_len(label, rest) > 0);
+
+            let Some(i) = found else {
+                node.children.push((
+                    rest.to_string(),
+                    Node {
+                        children: Vec::new(),
+                        values: vec![value],
+                    },
+                ));
+                return;
+            };
+
+            let common = common_prefix_len(&node.children[i].0, rest);
+            if common < node.children[i].0.len() {
+                // Split the edge so the shared part becomes its own node
+                let (label, child) = &mut node.children[i];
+                let suffix = label[common..].to_string();
+                label.truncate(common);
+                let old = std::mem::take(child);
+                child.children.push((suffix, old));
+            }
+
+            node = &mut node.children[i].1;
+            rest = &rest[common..];
+        }
+    }
+
+    /// Removes the values stored under `key` for which `predicate` returns true.
+    /// Returns the number of values removed.
+    pub fn remove(&mut self, key: &str, predicate: impl Fn(&T) -> bool) -> usize {
+        remove_from(&mut self.root, key, &predicate)
+    }
+
+    /// Returns every value whose key starts with `prefix`.
+    pub fn get_prefix(&self, prefix: &str) -> Vec<&T> {
+        let mut node = &self.root;
+        let mut rest = prefix;
+
+        while !rest.is_empty() {
+            let next = node.children.iter().find_map(|(label, child)| {
+                if rest.starts_with(label.as_str()) {
+                    Some((child, &rest[label.len()..]))
+                } else if label.starts_with(rest) {
+                    Some((child, ""))
+                } else {
+                    None
+                }
+            });
+
+            match next {
+                Some((child, remaining)) => {
+                    node = child;
+                    rest = remaining;
+                }
+                None => return Vec::new(),
+            }
+        }
+
+        let mut out = Vec::new();
+        collect(node, &mut out);
+        out
+    }
+}
+
+fn remove_from<T>(node: &mut Node<T>, key: &str, predicate: &impl Fn(&T) -> bool) -> usize {
+    if key.is_empty() {
+        let before = node.values.len();
+        node.values.retain(|value| !predicate(value));
+        return before - node.values.len();
+    }
+
+    let Some(i) = node
+        .children
+        .iter()
+        .position(|(label, _)| key.starts_with(label.as_str()))
+    else {
+        return 0;
+    };
+
+    let label_len = node.children[i].0.len();
+    let removed = remove_from(&mut node.children[i].1, &key[label_len..], predicate);
+
+    let child = &node.children[i].1;
+    if child.values.is_empty() && child.children.is_empty() {
+        node.children.swap_remove(i);
+    }
+
+    removed
+}
+
+fn collect<'a, T>(node: &'a Node<T>, out: &mut Vec<&'a T>) {
+    out.extend(node.values.iter());
+    for (_, child) in &node.children {
+        collect(child, out);
+    }
+}
+
+/// Length in bytes of the longest common prefix, always on a char boundary.
+fn common_prefix_len(a: &str, b: &str) -> usize {
+    a.char_indices()
+        .zip(b.chars())
+        .find(|((_, x), y)| x != y)
+        .map(|((i, _), _)| i)
+        .unwrap_or_else(|| a.len().min(b.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(values: Vec<&u32>) -> Vec<u32> {
+        let mut values: Vec<u32> = values.into_iter().copied().collect();
+        values.sort();
+        values
+    }
+
+    #[test]
+    fn prefix_lookup_splits_shared_edges() {
+        let mut trie = RadixTrie::new();
+        trie.insert("main.rs", 1);
+        trie.insert("mod.rs", 2);
+        trie.insert("main.rs.bak", 3);
+        trie.insert("readme.md", 4);
+
+        assert_eq!(sorted(trie.get_prefix("m")), [1, 2, 3]);
+        assert_eq!(sorted(trie.get_prefix("mai")), [1, 3]);
+        assert_eq!(sorted(trie.get_prefix("main.rs")), [1, 3]);
+        assert_eq!(sorted(trie.get_prefix("main.rs.")), [3]);
+        assert_eq!(sorted(trie.get_prefix("")), [1, 2, 3, 4]);
+        assert!(trie.get_prefix("x").is_empty());
+        assert!(trie.get_prefix("main.rx").is_empty());
+    }
+
+    #[test]
+    fn same_key_holds_several_values() {
+        let mut trie = RadixTrie::new();
+        trie.insert("lib.rs", 1);
+        trie.insert("lib.rs", 2);
+
+        assert_eq!(trie.remove("lib.rs", |value| *value == 1), 1);
+        assert_eq!(sorted(trie.get_prefix("lib")), [2]);
+    }
+
+    #[test]
+    fn remove_prunes_empty_nodes() {
+        let mut trie = RadixTrie::new();
+        trie.insert("test", 1);
+        trie.insert("team", 2);
+
+        assert_eq!(trie.remove("test", |_| true), 1);
+        assert_eq!(trie.remove("test", |_| true), 0);
+        assert_eq!(trie.remove("te", |_| true), 0);
+        assert_eq!(sorted(trie.get_prefix("te")), [2]);
+        assert_eq!(trie.remove("team", |_| true), 1);
+        assert!(trie.root.children.is_empty());
+    }
+
+    #[test]
+    fn splits_on_char_boundaries() {
+        let mut trie = RadixTrie::new();
+        trie.insert("café", 1);
+        trie.insert("cafè", 2);
+
+        assert_eq!(sorted(trie.get_prefix("caf")), [1, 2]);
+        assert_eq!(sorted(trie.get_prefix("café")), [1]);
+        assert_eq!(common_prefix_len("café", "cafè"), 3);
+    }
+}
@@ -0,0 +1,175 @@
+use futures_channel::mpsc::UnboundedSender;
+use notify::event::{AccessKind, AccessMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{PoisonError, RwLock};
+use std::time::{Duration, Instant};
+
+use super::{rescan_subtree, save, FileIndex};
+
+/// Quiet period after the last event before a batch is applied.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+/// Upper bound on how long a constantly busy tree can delay a batch.
+const MAX_BATCH_DELAY: Duration = Duration::from_secs(2);
+/// How long applied changes may stay unsaved before the cache is rewritten.
+const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Filesystem changes collected during one debounce window.
+struct Batch {
+    started: Instant,
+    /// Paths whose index entry must be refreshed or dropped
+    touched: BTreeSet<PathBuf>,
+    /// Directories whose whole subtree must be walked again
+    rescan: BTreeSet<PathBuf>,
+}
+
+impl Batch {
+    fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            touched: BTreeSet::new(),
+            rescan: BTreeSet::new(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.touched.is_empty() && self.rescan.is_empty()
+    }
+
+    fn add(&mut self, event: Event, root: &Path) {
+        if self.is_empty() {
+            self.started = Instant::now();
+        }
+
+        // The kernel queue overflowed and events were lost. inotify doesn't say
+        // where, so unless the event names paths the whole root is suspect.
+        if event.need_rescan() {
+            if event.paths.is_empty() {
+                self.rescan.insert(root.to_path_buf());
+            } else {
+                self.rescan.extend(event.paths);
+            }
+            return;
+        }
+
+        match event.kind {
+            EventKind::Access(AccessKind::Close(AccessMode::Write)) => {}
+            EventKind::Access(_) => return,
+            _ => {}
+        }
+        self.touched.extend(event.paths);
+    }
+
+    /// Whether `path` lies inside a directory that is rescanned anyway.
+    fn covered(&self, path: &Path) -> bool {
+        self.rescan.iter().any(|dir| path.starts_with(dir))
+    }
+}
+
+/// Starts watching `root` recursively. Returns `None` when the platform
+/// refuses, e.g. because the inotify watch limit is exhausted.
+pub fn watch(root: &Path) -> Option<(RecommendedWatcher, Receiver<notify::Result<Event>>)> {
+    let (tx, rx) = mpsc::channel();
+    let watcher = notify::recommended_watcher(tx).and_then(|mut watcher: RecommendedWatcher| {
+        watcher.watch(root, RecursiveMode::Recursive)?;
+        Ok(watcher)
+    });
+    match watcher {
+        Ok(watcher) => Some((watcher, rx)),
+        Err(err) => {
+            eprintln!("Live index updates disabled for {}: {err}", root.display());
+            None
+        }
+    }
+}
+
+/// Applies debounced change notifications to `index` until the receiving end
+/// of `updates` is dropped, saving the index to the cache now and then.
+///
+/// Sends the indexed file count on `updates` after every applied batch.
+pub fn process_events(
+    root: &Path,
+    rx: &Receiver<notify::Result<Event>>,
+    index: &RwLock<FileIndex>,
+    updates: &UnboundedSender<usize>,
+) {
+    let mut batch = Batch::new();
+    let mut unsaved_since: Option<Instant> = None;
+
+    loop {
+        let save_due = unsaved_since.map(|since| SAVE_INTERVAL.saturating_sub(since.elapsed()));
+        let received = match (batch.is_empty(), save_due) {
+            (false, _) => rx.recv_timeout(DEBOUNCE),
+            (true, Some(due)) => rx.recv_timeout(due),
+            (true, None) => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+
+        let quiet = matches!(received, Err(RecvTimeoutError::Timeout));
+        match received {
+            Ok(Ok(event)) => batch.add(event, root),
+            Ok(Err(err)) => {
+                eprintln!("File watcher error, rescanning {}: {err}", root.display());
+                batch.rescan.insert(root.to_path_buf());
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+
+        if !batch.is_empty() && (quiet || batch.started.elapsed() >= MAX_BATCH_DELAY) {
+            let files = apply(root, std::mem::replace(&mut batch, Batch::new()), index);
+            if updates.unbounded_send(files).is_err() {
+                return;
+            }
+            unsaved_since.get_or_insert_with(Instant::now);
+        }
+
+        if unsaved_since.is_some_and(|since| since.elapsed() >= SAVE_INTERVAL) {
+            save(index);
+            unsaved_since = None;
+        }
+    }
+}
+
+/// Applies a batch to the index and returns the new indexed file count.
+fn apply(root: &Path, batch: Batch, index: &RwLock<FileIndex>) -> usize {
+    // Drop rescans nested inside another rescan of the same batch
+    let rescans: Vec<&PathBuf> = batch
+        .rescan
+        .iter()
+        .filter(|dir| !batch.rescan.iter().any(|other| other != *dir && dir.starts_with(other)))
+        .collect();
+    for dir in rescans {
+        rescan_subtree(root, dir, index);
+    }
+
+    for path in &batch.touched {
+        if path == root || !path.starts_with(root) || batch.covered(path) {
+            continue;
+        }
+
+        match fs::symlink_metadata(path) {
+            Err(_) => index
+                .write()
+                .unwrap_or_else(PoisonError::into_inner)
+                .remove_tree(path),
+            Ok(metadata) => {
+                let known = index
+                    .read()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .contains(path);
+                if metadata.is_dir() && !known {
+                    // Created or moved in: its children may never produce events
+                    rescan_subtree(root, path, index);
+                } else {
+                    let entry = FileIndex::read_entry(path.clone(), &metadata);
+                    index.write().unwrap_or_else(PoisonError::into_inner).restore(entry);
+                }
+            }
+        }
+    }
+
+    index.read().unwrap_or_else(PoisonError::into_inner).len()
+}
@@ -0,0 +1,552 @@
+//! Keys for FileManager commands, from a preset plus the user's own bindings
+//! in `keybindings.toml`:
+//!
+//! ```toml
+//! preset = "vim"            # "default", "vim" or "emacs"
+//!
+//! [bindings]
+//! "Ctrl+E" = "edit"         # any command, named in kebab-case
+//! "g h" = "go-up"           # chords separated by spaces are typed in turn
+//! "d d" = "none"            # removes a preset binding
+//! ```
+//!
+//! Letters and other characters are matched as typed, so `G` means Shift+g;
+//! Shift only counts as a modifier for named keys like `Shift+Down`.
+
+use dioxus::prelude::*;
+use serde::de::IntoDeserializer;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::commands::Command;
+use super::config;
+
+const KEYBINDINGS_FILE: &str = "keybindings.toml";
+
+/// Keys pressed together, e.g. Ctrl+Shift+P.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Chord {
+    ctrl: bool,
+    alt: bool,
+    shift: bool,
+    /// A character as typed, or a named key as [`Key`] displays it
+    key: String,
+}
+
+impl Chord {
+    /// The chord of a key press, or `None` for a lone modifier key.
+    pub fn from_event(key: &Key, modifiers: Modifiers) -> Option<Chord> {
+        let key = match key {
+            Key::Shift | Key::Control | Key::Alt | Key::Meta | Key::AltGraph | Key::CapsLock => return None,
+            Key::Character(text) => text.clone(),
+            named => named.to_string(),
+        };
+        Some(Chord::new(modifiers.ctrl() || modifiers.meta(), modifiers.alt(), modifiers.shift(), key))
+    }
+
+    fn new(ctrl: bool, alt: bool, shift: bool, key: String) -> Chord {
+        let mut chars = key.chars();
+        match (chars.next(), chars.next()) {
+            // Shift is part of the character; Ctrl can turn it lowercase though
+            (Some(c), None) if c.is_ascii_alphabetic() => Chord {
+                ctrl,
+                alt,
+                shift: false,
+                key: if shift { c.to_ascii_uppercase() } else { c.to_ascii_lowercase() }.to_string(),
+            },
+            (Some(_), None) => Chord { ctrl, alt, shift: false, key },
+            _ => Chord { ctrl, alt, shift, key },
+        }
+    }
+
+    /// Whether Ctrl or Alt is held, so the chord can't be text being typed.
+    pub fn has_command_modifier(&self) -> bool {
+        self.ctrl || self.alt
+    }
+
+    /// A typed character with no modifiers, which type-ahead find can use.
+    pub fn typed_char(&self) -> Option<char> {
+        let mut chars = self.key.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) if !self.ctrl && !self.alt && !c.is_control() => Some(c),
+            _ => None,
+        }
+    }
+
+    /// Parses `Ctrl+Shift+P`, `Alt+Left`, `G`, `Space`, `Ctrl++` and the like.
+    fn parse(text: &str) -> Result<Chord, String> {
+        let (modifiers, key) = if text == "+" {
+            ("", "+")
+        } else if let Some(modifiers) = text.strip_suffix("++") {
+            (modifiers, "+")
+        } else {
+            text.rsplit_once('+').unwrap_or(("", text))
+        };
+        let (mut ctrl, mut alt, mut shift) = (false, false, false);
+        for modifier in modifiers.split('+').filter(|modifier| !modifier.is_empty()) {
+            match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" | "cmd" => ctrl = true,
+                "alt" | "option" | "meta" => alt = true,
+                "shift" => shift = true,
+                _ => return Err(format!("unknown modifier \"{modifier}\" in \"{text}\"")),
+            }
+        }
+        let key = match key.to_ascii_lowercase().as_str() {
+            "" => return Err(format!("no key in \"{text}\"")),
+            "up" => "ArrowUp".to_string(),
+            "down" => "ArrowDown".to_string(),
+            "left" => "ArrowLeft".to_string(),
+            "right" => "ArrowRight".to_string(),
+            "pgup" | "pageup" => "PageUp".to_string(),
+            "pgdn" | "pgdown" | "pagedown" => "PageDown".to_string(),
+            "esc" | "escape" => "Escape".to_string(),
+            "del" | "delete" => "Delete".to_string(),
+            "space" => " ".to_string(),
+            "return" | "enter" => "Enter".to_string(),
+            "tab" => "Tab".to_string(),
+            "home" => "Home".to_string(),
+            "end" => "End".to_string(),
+            "backspace" => "Backspace".to_string(),
+            _ if key.chars().count() == 1 => {
+                // A lone `G` is typed with Shift, while `Ctrl+G` is written
+                // in capitals but means the plain letter
+                shift |= !ctrl && !alt && key.chars().all(|c| c.is_ascii_uppercase());
+                key.to_string()
+            }
+            function if function.starts_with('f') && function[1..].parse::<u8>().is_ok() => function.to_uppercase(),
+            // Other named keys as browsers call them, e.g. Insert
+            _ => match key.parse::<Key>() {
+                Ok(Key::Character(_) | Key::Unidentified) | Err(_) => {
+                    return Err(format!("unknown key \"{key}\" in \"{text}\""))
+                }
+                Ok(named) => named.to_string(),
+            },
+        };
+        Ok(Chord::new(ctrl, alt, shift, key))
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let upper = self.key.len() == 1 && self.key.chars().all(|c| c.is_ascii_uppercase());
+        if self.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        if self.alt {
+            write!(f, "Alt+")?;
+        }
+        if self.shift || upper {
+            write!(f, "Shift+")?;
+        }
+        match self.key.as_str() {
+            "ArrowUp" => write!(f, "Up"),
+            "ArrowDown" => write!(f, "Down"),
+            "ArrowLeft" => write!(f, "Left"),
+            "ArrowRight" => write!(f, "Right"),
+            "Escape" => write!(f, "Esc"),
+            " " => write!(f, "Space"),
+            key if self.ctrl || self.alt || upper => write!(f, "{}", key.to_uppercase()),
+            key => write!(f, "{key}"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Preset {
+    #[default]
+    Default,
+    Vim,
+    Emacs,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 3] = [Preset::Default, Preset::Vim, Preset::Emacs];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Preset::Default => "Default",
+            Preset::Vim => "Vim",
+            Preset::Emacs => "Emacs",
+        }
+    }
+
+    fn bindings(self) -> &'static [(&'static str, Command)] {
+        match self {
+            Preset::Default => DEFAULT_BINDINGS,
+            Preset::Vim => VIM_BINDINGS,
+            Preset::Emacs => EMACS_BINDINGS,
+        }
+    }
+}
+
+/// Keys every preset shares.
+const COMMON_BINDINGS: &[(&str, Command)] = &[
+    ("Up", Command::CursorUp),
+    ("Down", Command::CursorDown),
+    ("Shift+Up", Command::SelectUp),
+    ("Shift+Down", Command::SelectDown),
+    ("PageUp", Command::PageUp),
+    ("PageDown", Command::PageDown),
+    ("Home", Command::CursorFirst),
+    ("End", Command::CursorLast),
+    ("Enter", Command::Open),
+    ("Backspace", Command::GoUp),
+    ("Alt+Up", Command::GoUp),
+    ("Alt+Left", Command::GoBack),
+    ("Alt+Right", Command::GoForward),
+    ("Tab", Command::SwitchPane),
+    ("F2", Command::Rename),
+    ("F3", Command::TogglePreview),
+    ("F4", Command::Edit),
+    ("F5", Command::CopyToOtherPane),
+    ("F6", Command::MoveToOtherPane),
+    ("F7", Command::NewFolder),
+    ("Delete", Command::MoveToTrash),
+    ("Shift+Delete", Command::DeletePermanently),
+    ("Ctrl+J", Command::JumpTo),
+    ("Ctrl+Shift+P", Command::ShowCommands),
+];
+
+const DEFAULT_BINDINGS: &[(&str, Command)] = &[
+    ("Space", Command::ToggleMark),
+    ("Escape", Command::ClearSelection),
+    ("Ctrl+A", Command::SelectAll),
+    ("Ctrl+I", Command::InvertSelection),
+    ("Ctrl+C", Command::Copy),
+    ("Ctrl+X", Command::Cut),
+    ("Ctrl+V", Command::Paste),
+    ("Ctrl+Z", Command::Undo),
+    ("Ctrl+Y", Command::Redo),
+    ("Ctrl+Shift+Z", Command::Redo),
+    ("Ctrl+F", Command::Find),
+    ("Ctrl+Shift+N", Command::NewFolder),
+    ("Ctrl+T", Command::NewTab),
+    ("Ctrl+W", Command::CloseTab),
+    ("Ctrl+D", Command::BookmarkFolder),
+];
+
+const VIM_BINDINGS: &[(&str, Command)] = &[
+    ("j", Command::CursorDown),
+    ("k", Command::CursorUp),
+    ("Shift+J", Command::SelectDown),
+    ("Shift+K", Command::SelectUp),
+    ("Ctrl+F", Command::PageDown),
+    ("Ctrl+B", Command::PageUp),
+    ("g g", Command::CursorFirst),
+    ("Shift+G", Command::CursorLast),
+    ("l", Command::Open),
+    ("h", Command::GoUp),
+    ("Shift+H", Command::GoBack),
+    ("Shift+L", Command::GoForward),
+    ("v", Command::ToggleMark),
+    ("Space", Command::ToggleMark),
+    ("Escape", Command::ClearSelection),
+    ("/", Command::Find),
+    ("y y", Command::Copy),
+    ("d d", Command::Cut),
+    ("p", Command::Paste),
+    ("Shift+D", Command::MoveToTrash),
+    ("c w", Command::Rename),
+    ("o", Command::NewFolder),
+    ("u", Command::Undo),
+    ("Ctrl+R", Command::Redo),
+    ("Ctrl+W w", Command::SwitchPane),
+    ("Ctrl+W v", Command::ToggleSplit),
+    ("g t", Command::NewTab),
+    ("Ctrl+W c", Command::CloseTab),
+    ("m", Command::BookmarkFolder),
+    (":", Command::ShowCommands),
+];
+
+const EMACS_BINDINGS: &[(&str, Command)] = &[
+    ("Ctrl+N", Command::CursorDown),
+    ("Ctrl+P", Command::CursorUp),
+    ("Ctrl+V", Command::PageDown),
+    ("Alt+V", Command::PageUp),
+    ("Alt+<", Command::CursorFirst),
+    ("Alt+>", Command::CursorLast),
+    ("^", Command::GoUp),
+    ("Ctrl+Space", Command::ToggleMark),
+    ("m", Command::ToggleMark),
+    ("Ctrl+G", Command::ClearSelection),
+    ("Ctrl+S", Command::Find),
+    ("Alt+W", Command::Copy),
+    ("Ctrl+W", Command::Cut),
+    ("Ctrl+Y", Command::Paste),
+    ("Ctrl+/", Command::Undo),
+    ("Ctrl+X u", Command::Undo),
+    ("Ctrl+?", Command::Redo),
+    ("Shift+R", Command::Rename),
+    ("+", Command::NewFolder),
+    ("Shift+D", Command::MoveToTrash),
+    ("Ctrl+X o", Command::SwitchPane),
+    ("Ctrl+X 3", Command::ToggleSplit),
+    ("Ctrl+X 1", Command::ToggleSplit),
+    ("Ctrl+X Ctrl+F", Command::JumpTo),
+    ("Ctrl+X t", Command::NewTab),
+    ("Ctrl+X k", Command::CloseTab),
+    ("Alt+X", Command::ShowCommands),
+];
+
+/// What the keybindings file holds.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct KeybindingsFile {
+    preset: Preset,
+    /// Key sequence to command name, or "none"
+    bindings: BTreeMap<String, String>,
+}
+
+/// The outcome of looking up the keys typed so far.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Lookup {
+    Command(Command),
+    /// The keys start a longer binding; wait for the next one
+    Prefix,
+    Unbound,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Keymap {
+    /// Later entries come from the user and win
+    bindings: Vec<(Vec<Chord>, Command)>,
+}
+
+impl Keymap {
+    /// The keymap of `preset` with the user's `overrides`, and any that
+    /// couldn't be understood.
+    fn new(preset: Preset, overrides: &BTreeMap<String, String>) -> (Keymap, Vec<String>) {
+        let mut keymap = Keymap::default();
+        for &(keys, command) in COMMON_BINDINGS.iter().chain(preset.bindings()) {
+            let sequence = parse_sequence(keys).expect("preset bindings are valid");
+            keymap.bind(sequence, Some(command));
+        }
+        let mut errors = Vec::new();
+        for (keys, name) in overrides {
+            let command = match name.as_str() {
+                "none" => Ok(None),
+                name => Command::deserialize(name.into_deserializer())
+                    .map(Some)
+                    .map_err(|_: serde::de::value::Error| format!("unknown command \"{name}\"")),
+            };
+            match (parse_sequence(keys), command) {
+                (Ok(sequence), Ok(command)) => keymap.bind(sequence, command),
+                (Err(err), _) | (_, Err(err)) => errors.push(err),
+            }
+        }
+        (keymap, errors)
+    }
+
+    fn bind(&mut self, sequence: Vec<Chord>, command: Option<Command>) {
+        self.bindings.retain(|(bound, _)| *bound != sequence);
+        if let Some(command) = command {
+            self.bindings.push((sequence, command));
+        }
+    }
+
+    /// What the keys typed so far, `sequence`, do.
+    pub fn lookup(&self, sequence: &[Chord]) -> Lookup {
+        let mut prefix = false;
+        for (bound, command) in self.bindings.iter().rev() {
+            if bound == sequence {
+                return Lookup::Command(*command);
+            }
+            prefix |= bound.starts_with(sequence);
+        }
+        if prefix {
+            Lookup::Prefix
+        } else {
+            Lookup::Unbound
+        }
+    }
+
+    /// The first key sequence bound to `command`, for display.
+    pub fn shortcut(&self, command: Command) -> Option<String> {
+        let (sequence, _) = self.bindings.iter().find(|(_, bound)| *bound == command)?;
+        Some(sequence.iter().map(Chord::to_string).collect::<Vec<_>>().join(" "))
+    }
+}
+
+fn parse_sequence(keys: &str) -> Result<Vec<Chord>, String> {
+    let sequence = keys.split_whitespace().map(Chord::parse).collect::<Result<Vec<_>, _>>()?;
+    if sequence.is_empty() {
+        return Err("empty key binding".to_string());
+    }
+    Ok(sequence)
+}
+
+/// The keymap in use, provided as context by [`use_keybindings_provider`].
+#[derive(Clone, Copy)]
+pub struct Keybindings {
+    pub keymap: Signal<Keymap>,
+    pub preset: Signal<Preset>,
+    /// Why the keybindings file couldn't be used, fully or partly
+    pub error: Signal<Option<String>>,
+}
+
+impl Keybindings {
+    /// Switches to `preset`, keeping the user's own bindings, and saves it.
+    pub fn set_preset(&self, preset: Preset) {
+        let (mut keymap, mut current, mut error) = (self.keymap, self.preset, self.error);
+        let file = match config::load_toml::<KeybindingsFile>(KEYBINDINGS_FILE) {
+            Ok(file) => file,
+            Err(err) => {
+                // Saving now would throw away what the user wrote
+                error.set(Some(err));
+                return;
+            }
+        };
+        let (loaded, errors) = Keymap::new(preset, &file.bindings);
+        keymap.set(loaded);
+        current.set(preset);
+        error.set(describe_errors(errors));
+        // Only the preset changes; the rest of the file is the user's
+        if let Err(err) = config::set_toml_key(KEYBINDINGS_FILE, "preset", &preset) {
+            error.set(Some(format!("Could not save key bindings: {err}")));
+        }
+    }
+}
+
+fn describe_errors(errors: Vec<String>) -> Option<String> {
+    (!errors.is_empty()).then(|| format!("Some key bindings were ignored: {}", errors.join("; ")))
+}
+
+/// Loads the keybindings file and provides [`Keybindings`].
+pub fn use_keybindings_provider() {
+    use_context_provider(|| {
+        let (file, error) = match config::load_toml::<KeybindingsFile>(KEYBINDINGS_FILE) {
+            Ok(file) => (file, None),
+            Err(err) => (KeybindingsFile::default(), Some(format!("Key bindings not loaded: {err}"))),
+        };
+        let (keymap, errors) = Keymap::new(file.preset, &file.bindings);
+        Keybindings {
+            keymap: Signal::new(keymap),
+            preset: Signal::new(file.preset),
+            error: Signal::new(error.or(describe_errors(errors))),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(text: &str) -> Chord {
+        Chord::parse(text).unwrap()
+    }
+
+    fn keys(text: &str) -> Vec<Chord> {
+        parse_sequence(text).unwrap()
+    }
+
+    #[test]
+    fn parsed_chords_match_key_presses() {
+        let pressed = |key: Key, modifiers| Chord::from_event(&key, modifiers).unwrap();
+        let character = |text: &str| Key::Character(text.to_string());
+
+        assert_eq!(chord("Ctrl+Shift+P"), pressed(character("P"), Modifiers::CONTROL | Modifiers::SHIFT));
+        assert_eq!(chord("ctrl+shift+p"), chord("Ctrl+Shift+P"));
+        assert_eq!(chord("G"), pressed(character("G"), Modifiers::SHIFT));
+        assert_eq!(chord("Shift+g"), chord("G"));
+        assert_eq!(chord("Shift+Down"), pressed(Key::ArrowDown, Modifiers::SHIFT));
+        assert_ne!(chord("Shift+Down"), chord("Down"));
+        assert_eq!(chord("Alt+Left"), pressed(Key::ArrowLeft, Modifiers::ALT));
+        assert_eq!(chord("Cmd+C"), pressed(character("c"), Modifiers::META));
+        assert_eq!(chord("Space"), pressed(character(" "), Modifiers::empty()));
+        assert_eq!(chord("esc"), pressed(Key::Escape, Modifiers::empty()));
+        assert_eq!(chord("F5"), pressed(Key::F5, Modifiers::empty()));
+        assert_eq!(chord("Insert"), pressed(Key::Insert, Modifiers::empty()));
+        // Shift is part of a typed character
+        assert_eq!(chord("?"), pressed(character("?"), Modifiers::SHIFT));
+        assert_eq!(Chord::from_event(&Key::Shift, Modifiers::SHIFT), None);
+    }
+
+    #[test]
+    fn plus_can_be_the_key() {
+        assert_eq!(chord("+"), Chord::new(false, false, false, "+".to_string()));
+        assert_eq!(chord("Ctrl++"), Chord::new(true, false, false, "+".to_string()));
+    }
+
+    #[test]
+    fn unknown_chords_are_reported() {
+        assert_eq!(Chord::parse("Hyper+X").unwrap_err(), "unknown modifier \"Hyper\" in \"Hyper+X\"");
+        assert_eq!(Chord::parse("Ctrl+").unwrap_err(), "no key in \"Ctrl+\"");
+        assert_eq!(Chord::parse("Ctrl+Bogus").unwrap_err(), "unknown key \"Bogus\" in \"Ctrl+Bogus\"");
+        assert!(parse_sequence("  ").is_err());
+    }
+
+    #[test]
+    fn chords_display_the_way_they_are_written() {
+        for text in ["Ctrl+Shift+P", "Shift+G", "Alt+Left", "Space", "Esc", "F2", "Shift+Delete", "j", "/"] {
+            assert_eq!(chord(text).to_string(), text);
+        }
+        assert_eq!(chord("ctrl+j").to_string(), "Ctrl+J");
+    }
+
+    #[test]
+    fn typed_characters_have_no_command_modifier() {
+        assert_eq!(chord("G").typed_char(), Some('G'));
+        assert_eq!(chord("Space").typed_char(), Some(' '));
+        assert_eq!(chord("Ctrl+G").typed_char(), None);
+        assert_eq!(chord("Enter").typed_char(), None);
+        assert!(chord("Alt+X").has_command_modifier());
+        assert!(!chord("Shift+Down").has_command_modifier());
+    }
+
+    #[test]
+    fn lookup_follows_key_sequences() {
+        let (keymap, errors) = Keymap::new(Preset::Vim, &BTreeMap::new());
+        assert!(errors.is_empty());
+        assert_eq!(keymap.lookup(&keys("j")), Lookup::Command(Command::CursorDown));
+        assert_eq!(keymap.lookup(&keys("Down")), Lookup::Command(Command::CursorDown));
+        assert_eq!(keymap.lookup(&keys("g")), Lookup::Prefix);
+        assert_eq!(keymap.lookup(&keys("g g")), Lookup::Command(Command::CursorFirst));
+        assert_eq!(keymap.lookup(&keys("g x")), Lookup::Unbound);
+        assert_eq!(keymap.lookup(&keys("Ctrl+W")), Lookup::Prefix);
+        assert_eq!(keymap.lookup(&keys("x")), Lookup::Unbound);
+
+        let (keymap, _) = Keymap::new(Preset::Default, &BTreeMap::new());
+        assert_eq!(keymap.lookup(&keys("j")), Lookup::Unbound);
+        assert_eq!(keymap.lookup(&keys("Ctrl+Z")), Lookup::Command(Command::Undo));
+    }
+
+    #[test]
+    fn user_bindings_override_the_preset() {
+        let overrides = BTreeMap::from([
+            ("j".to_string(), "go-up".to_string()),
+            ("d d".to_string(), "none".to_string()),
+            ("Ctrl+E".to_string(), "edit".to_string()),
+            ("Ctrl+Q".to_string(), "quit".to_string()),
+            ("Hyper+Q".to_string(), "edit".to_string()),
+        ]);
+        let (keymap, errors) = Keymap::new(Preset::Vim, &overrides);
+        assert_eq!(keymap.lookup(&keys("j")), Lookup::Command(Command::GoUp));
+        assert_eq!(keymap.lookup(&keys("d")), Lookup::Unbound);
+        assert_eq!(keymap.lookup(&keys("Ctrl+E")), Lookup::Command(Command::Edit));
+        assert_eq!(keymap.shortcut(Command::Edit).as_deref(), Some("F4"));
+        assert_eq!(errors.len(), 2);
+        assert!(errors.iter().any(|err| err.contains("\"quit\"")));
+        assert!(errors.iter().any(|err| err.contains("\"Hyper\"")));
+    }
+
+    #[test]
+    fn shortcuts_show_the_first_binding() {
+        let (keymap, _) = Keymap::new(Preset::Emacs, &BTreeMap::new());
+        assert_eq!(keymap.shortcut(Command::JumpTo).as_deref(), Some("Ctrl+J"));
+        assert_eq!(keymap.shortcut(Command::Undo).as_deref(), Some("Ctrl+/"));
+        assert_eq!(keymap.shortcut(Command::CloseTab).as_deref(), Some("Ctrl+X k"));
+    }
+
+    #[test]
+    fn every_preset_parses_and_saves_by_name() {
+        for preset in Preset::ALL {
+            let (_, errors) = Keymap::new(preset, &BTreeMap::new());
+            assert!(errors.is_empty());
+            let file: KeybindingsFile = toml::from_str(&format!("preset = {}", toml::Value::try_from(preset).unwrap())).unwrap();
+            assert_eq!(file.preset, preset);
+        }
+        assert_eq!(toml::Value::try_from(Preset::Vim).unwrap().as_str(), Some("vim"));
+    }
+}
@@ -9,4 +9,50 @@ mod navbar;
 pub use navbar::Navbar;
 
 mod file;
-pub use file::FileManager;
\ No newline at end of file
+pub use file::{format_file_size, CurrentPath, FileManager};
+
+mod trash;
+pub use trash::Trash;
+
+mod disk_usage;
+pub use disk_usage::DiskUsage;
+
+mod editor;
+pub use editor::{use_unsaved_edits_provider, Editor};
+
+mod selection;
+pub use selection::{CurrentSelection, Selection};
+
+pub mod tabs;
+
+pub mod panes;
+
+pub mod places;
+
+pub mod frecency;
+
+pub mod commands;
+
+pub mod keybindings;
+
+mod history;
+
+pub mod path_input;
+
+pub mod columns;
+
+mod config;
+
+pub mod indexing;
+
+pub mod operations;
+
+pub mod preview;
+
+mod dir_size;
+
+mod dir_watcher;
+
+mod text_file;
+
+mod time_format;
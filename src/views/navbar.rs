@@ -1,8 +1,92 @@
-use crate::{components::Search, Route};
+use crate::components::{JobsPanel, Search, SearchResults};
+use crate::views::indexing::{read_snippets, ContentQuery, IndexStatus, SearchIndex, Snippet};
+use crate::views::CurrentPath;
+use crate::Route;
 use dioxus::prelude::*;
+use futures_channel::oneshot;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+const MAX_RESULTS: usize = 50;
+const MAX_CONTENT_RESULTS: usize = 20;
 
 #[component]
 pub fn Navbar() -> Element {
+    let index = use_context::<SearchIndex>();
+    let CurrentPath(mut current_path) = use_context();
+    let mut query = use_signal(String::new);
+    let mut search_focused = use_signal(|| false);
+
+    let results = use_memo({
+        let index = index.clone();
+        move || {
+            // Re-run once the background crawl finishes
+            index.status();
+            let query = ContentQuery::parse(&query());
+            if query.text.is_empty() {
+                index.read().recently_modified(MAX_RESULTS)
+            } else {
+                index.read().search(&query.text, MAX_RESULTS)
+            }
+        }
+    });
+
+    let content_results = use_memo({
+        let index = index.clone();
+        move || {
+            index.status();
+            let query = ContentQuery::parse(&query());
+            if query.text.chars().count() < 3 {
+                return Vec::new();
+            }
+            index.read().search_content(&query, MAX_CONTENT_RESULTS)
+        }
+    });
+
+    // Matching lines mean reading every hit from disk, so they are gathered on
+    // a thread and filled in once the hits are already listed
+    let snippets = use_resource(move || {
+        let hits = content_results();
+        let query = ContentQuery::parse(&query());
+        async move {
+            let (tx, rx) = oneshot::channel();
+            std::thread::spawn(move || {
+                let snippets: HashMap<PathBuf, Vec<Snippet>> = hits
+                    .into_iter()
+                    .map(|hit| {
+                        let snippets = read_snippets(&hit.path, &query);
+                        (hit.path, snippets)
+                    })
+                    .collect();
+                // Nobody is waiting if the query changed meanwhile
+                let _ = tx.send(snippets);
+            });
+            rx.await.unwrap_or_default()
+        }
+    });
+
+    let mut open_result = move |path: PathBuf| {
+        let dir = if path.is_dir() {
+            Some(path)
+        } else {
+            path.parent().map(|parent| parent.to_path_buf())
+        };
+        if let Some(dir) = dir {
+            current_path.set(dir);
+            query.set(String::new());
+            search_focused.set(false);
+            navigator().push(Route::FileManager {});
+        }
+    };
+
+    let results_title = match index.status() {
+        IndexStatus::Building => "Indexing…".to_string(),
+        IndexStatus::Ready { files } if query().trim().is_empty() => {
+            format!("Recently modified · {files} indexed")
+        }
+        IndexStatus::Ready { files } => format!("Matches · {files} indexed"),
+    };
+
     rsx! {
         nav { class: "bg-white shadow-sm border-b",
             div { class: "max-w-6xl mx-auto px-6 py-4",
@@ -24,14 +108,42 @@ pub fn Navbar() -> Element {
                                 class: "text-gray-600 hover:text-gray-900 px-3 py-2 rounded-md transition-colors",
                                 "Browse Files"
                             }
-                            Search {
-                                value: "".to_string(),
-                                on_change: move |query| {
-                                    println!("Search query: {}", query);
-                                },
-                                on_submit: move |query| {
-                                    println!("Search submitted: {}", query);
-                                },
+                            Link {
+                                to: Route::DiskUsage {},
+                                class: "text-gray-600 hover:text-gray-900 px-3 py-2 rounded-md transition-colors",
+                                "Disk Usage"
+                            }
+                            Link {
+                                to: Route::Trash {},
+                                class: "text-gray-600 hover:text-gray-900 px-3 py-2 rounded-md transition-colors",
+                                "Trash"
+                            }
+                            div {
+                                class: "relative",
+                                onfocusin: move |_| search_focused.set(true),
+                                onfocusout: move |_| search_focused.set(false),
+                                Search {
+                                    value: query(),
+                                    on_change: move |value| query.set(value),
+                                    on_submit: move |_| {
+                                        let first = results.read().first().map(|hit| hit.path.clone()).or_else(|| {
+                                            content_results.read().first().map(|hit| hit.path.clone())
+                                        });
+                                        if let Some(first) = first {
+                                            open_result(first);
+                                        }
+                                    },
+                                }
+                                if search_focused() {
+                                    SearchResults {
+                                        title: results_title,
+                                        results: results(),
+                                        content_matches: content_results(),
+                                        snippets: snippets.cloned().unwrap_or_default(),
+                                        root: index.root().to_path_buf(),
+                                        on_select: open_result,
+                                    }
+                                }
                             }
                         }
                     }
@@ -39,5 +151,6 @@ pub fn Navbar() -> Element {
             }
         }
         Outlet::<Route> {}
+        JobsPanel {}
     }
 }
@@ -0,0 +1,136 @@
+use futures_channel::mpsc::UnboundedSender;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+
+use super::journal::{self, Operation};
+use super::transfer::{self, JobContext};
+use super::JobRequest;
+
+pub type JobId = u64;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct JobProgress {
+    pub files_done: u64,
+    pub files_total: u64,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    /// Entry being processed right now
+    pub current: Option<PathBuf>,
+}
+
+impl JobProgress {
+    /// Completion in percent, by bytes when there are any and by files otherwise.
+    pub fn percent(&self) -> u64 {
+        let (done, total) = if self.bytes_total > 0 {
+            (self.bytes_done, self.bytes_total)
+        } else {
+            (self.files_done, self.files_total)
+        };
+        (done.min(total) * 100).checked_div(total).unwrap_or(0)
+    }
+}
+
+/// A copy or move target that already exists.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Conflict {
+    pub source: PathBuf,
+    pub target: PathBuf,
+    /// Both sides are directories, so they can be merged
+    pub both_dirs: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConflictChoice {
+    Overwrite,
+    Skip,
+    /// Keep both by giving the new entry a free `name (2)` style name
+    Rename,
+    /// Copy into the existing directory, resolving conflicts inside it one by one
+    Merge,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ConflictAnswer {
+    pub choice: ConflictChoice,
+    pub apply_to_all: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum JobState {
+    Queued,
+    Running,
+    AwaitingAnswer(Conflict),
+    /// Finished; the errors of entries that could not be processed, if any
+    Done(Vec<String>),
+    Cancelled,
+}
+
+impl JobState {
+    pub fn is_finished(&self) -> bool {
+        matches!(self, JobState::Done(_) | JobState::Cancelled)
+    }
+}
+
+/// Sent from the worker thread to the UI.
+pub enum JobEvent {
+    Started(JobId),
+    Progress(JobId, JobProgress),
+    Conflict(JobId, Conflict),
+    /// The final state, the operations the job carried out and, for undo and
+    /// redo, the ones it didn't get to
+    Finished(JobId, JobState, Vec<Operation>, Vec<Operation>),
+}
+
+impl JobEvent {
+    pub fn job_id(&self) -> JobId {
+        match self {
+            JobEvent::Started(id)
+            | JobEvent::Progress(id, _)
+            | JobEvent::Conflict(id, _)
+            | JobEvent::Finished(id, ..) => *id,
+        }
+    }
+}
+
+/// What the worker should do for a job.
+pub enum Task {
+    Run(JobRequest),
+    Undo(Vec<Operation>),
+    Redo(Vec<Operation>),
+}
+
+/// A job handed to the worker thread.
+pub struct QueuedJob {
+    pub id: JobId,
+    pub task: Task,
+    pub cancel: Arc<AtomicBool>,
+    pub answers: Receiver<ConflictAnswer>,
+}
+
+/// Runs queued jobs one after another until the UI side goes away.
+pub fn worker(queue: Receiver<QueuedJob>, events: UnboundedSender<JobEvent>) {
+    for job in queue {
+        if events.unbounded_send(JobEvent::Started(job.id)).is_err() {
+            return;
+        }
+
+        let mut ctx = JobContext::new(job.id, &job.cancel, &job.answers, &events);
+        let result = match &job.task {
+            Task::Run(request) => transfer::run(&mut ctx, request),
+            Task::Undo(operations) => journal::undo(&mut ctx, operations),
+            Task::Redo(operations) => journal::redo(&mut ctx, operations),
+        };
+        let state = match result {
+            Ok(()) => JobState::Done(std::mem::take(&mut ctx.errors)),
+            Err(transfer::Cancelled) => JobState::Cancelled,
+        };
+
+        let operations = std::mem::take(&mut ctx.journal);
+        let unfinished = std::mem::take(&mut ctx.unfinished);
+        if events.unbounded_send(JobEvent::Finished(job.id, state, operations, unfinished)).is_err() {
+            return;
+        }
+    }
+}
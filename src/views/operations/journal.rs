@@ -0,0 +1,347 @@
+//! The operation journal behind undo and redo.
+//!
+//! Jobs record what they actually did, after conflicts were resolved, as a
+//! list of [`Operation`]s. Undoing replays their inverses in reverse order;
+//! redoing replays them again. Every step first checks that the filesystem
+//! still looks the way the operation left it, and stops with an error instead
+//! of touching anything that has changed since. The steps not replayed go
+//! back on their stack, so nothing is forgotten because one file moved.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::transfer::{self, tree_stats, Cancelled, JobContext};
+use super::trash::{self, TrashedItem};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Operation {
+    /// `target` was created as a copy of `source`
+    Copied { source: PathBuf, target: PathBuf },
+    /// An entry was moved or renamed from `from` to `to`
+    Moved { from: PathBuf, to: PathBuf },
+    Trashed(TrashedItem),
+    CreatedDir(PathBuf),
+}
+
+/// One user action as it appears in the undo and redo stacks.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JournalEntry {
+    /// Imperative summary such as `Copy "a.txt" to /tmp`
+    pub description: String,
+    pub operations: Vec<Operation>,
+}
+
+fn exists(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok()
+}
+
+/// Reverts `operations`, last first. On return `ctx.journal` holds the
+/// operations that were reverted, in their original order, ready for redo,
+/// and `ctx.unfinished` the ones before the step that stopped the replay.
+pub fn undo(ctx: &mut JobContext, operations: &[Operation]) -> Result<(), Cancelled> {
+    set_totals(ctx, operations.iter().filter_map(|operation| match operation {
+        Operation::Copied { target, .. } => Some(target.clone()),
+        Operation::Moved { to, .. } => Some(to.clone()),
+        Operation::Trashed(item) => Some(item.file_path()),
+        Operation::CreatedDir(_) => None,
+    }));
+
+    let mut left = operations.len();
+    let result = undo_steps(ctx, operations, &mut left);
+    ctx.unfinished = operations[..left].to_vec();
+
+    ctx.progress.current = None;
+    ctx.report(true);
+    result
+}
+
+/// Reverts operations from the end of `operations`, counting down `left` as
+/// each one succeeds.
+fn undo_steps(ctx: &mut JobContext, operations: &[Operation], left: &mut usize) -> Result<(), Cancelled> {
+    for operation in operations.iter().rev() {
+        ctx.check_cancel()?;
+        let reverted = match operation {
+            Operation::Copied { target, .. } => {
+                if !exists(target) {
+                    diverged(ctx, target, "no longer exists")
+                } else {
+                    // The copy may have been edited since, so it goes to the
+                    // trash rather than away for good
+                    let stats = tree_stats(target);
+                    let trashed = trash::trash(target).map_err(|err| ctx.error(target, err));
+                    ctx.advance(stats);
+                    trashed.is_ok()
+                }
+            }
+            Operation::Moved { from, to } => {
+                if !exists(to) {
+                    diverged(ctx, to, "no longer exists")
+                } else if exists(from) {
+                    diverged(ctx, from, "is in the way")
+                } else {
+                    move_back(ctx, to, from)?
+                }
+            }
+            Operation::Trashed(item) => {
+                if !exists(&item.file_path()) {
+                    diverged(ctx, &item.original_path, "is no longer in the trash")
+                } else if exists(&item.original_path) {
+                    diverged(ctx, &item.original_path, "is in the way")
+                } else {
+                    let stats = tree_stats(&item.file_path());
+                    let restored = trash::restore(item).map_err(|err| ctx.error(&item.original_path, err));
+                    ctx.advance(stats);
+                    restored.is_ok()
+                }
+            }
+            Operation::CreatedDir(dir) => match fs::read_dir(dir).map(|mut entries| entries.next().is_none()) {
+                Ok(true) => {
+                    let removed = fs::remove_dir(dir).map_err(|err| ctx.error(dir, err));
+                    removed.is_ok()
+                }
+                Ok(false) => diverged(ctx, dir, "is no longer empty"),
+                Err(_) => diverged(ctx, dir, "no longer exists"),
+            },
+        };
+        if !reverted {
+            break;
+        }
+        ctx.journal.insert(0, operation.clone());
+        *left -= 1;
+    }
+    Ok(())
+}
+
+/// Performs `operations` again, first first. On return `ctx.journal` holds
+/// the operations as they were redone, ready to be undone once more, and
+/// `ctx.unfinished` the ones from the step that stopped the replay on.
+pub fn redo(ctx: &mut JobContext, operations: &[Operation]) -> Result<(), Cancelled> {
+    set_totals(ctx, operations.iter().filter_map(|operation| match operation {
+        Operation::Copied { source, .. } => Some(source.clone()),
+        Operation::Moved { from, .. } => Some(from.clone()),
+        Operation::Trashed(item) => Some(item.original_path.clone()),
+        Operation::CreatedDir(_) => None,
+    }));
+
+    let mut done = 0;
+    let result = redo_steps(ctx, operations, &mut done);
+    ctx.unfinished = operations[done..].to_vec();
+
+    ctx.progress.current = None;
+    ctx.report(true);
+    result
+}
+
+/// Performs operations from the start of `operations`, counting up `done` as
+/// each one succeeds.
+fn redo_steps(ctx: &mut JobContext, operations: &[Operation], done: &mut usize) -> Result<(), Cancelled> {
+    for operation in operations {
+        ctx.check_cancel()?;
+        let redone = match operation {
+            Operation::Copied { source, target } => {
+                if !exists(source) {
+                    diverged(ctx, source, "no longer exists")
+                } else if exists(target) {
+                    diverged(ctx, target, "is in the way")
+                } else {
+                    let errors = ctx.errors.len();
+                    transfer::transfer(ctx, source, target, false, true)?;
+                    ctx.errors.len() == errors
+                }
+            }
+            Operation::Moved { from, to } => {
+                if !exists(from) {
+                    diverged(ctx, from, "no longer exists")
+                } else if exists(to) {
+                    diverged(ctx, to, "is in the way")
+                } else {
+                    let errors = ctx.errors.len();
+                    transfer::transfer(ctx, from, to, true, true)?;
+                    ctx.errors.len() == errors
+                }
+            }
+            Operation::Trashed(item) => {
+                if !exists(&item.original_path) {
+                    diverged(ctx, &item.original_path, "no longer exists")
+                } else {
+                    let errors = ctx.errors.len();
+                    transfer::move_to_trash(ctx, &item.original_path)?;
+                    ctx.errors.len() == errors
+                }
+            }
+            Operation::CreatedDir(dir) => {
+                if exists(dir) {
+                    diverged(ctx, dir, "is in the way")
+                } else {
+                    match fs::create_dir(dir) {
+                        Ok(()) => {
+                            ctx.journal.push(operation.clone());
+                            true
+                        }
+                        Err(err) => {
+                            ctx.error(dir, err);
+                            false
+                        }
+                    }
+                }
+            }
+        };
+        if !redone {
+            break;
+        }
+        *done += 1;
+    }
+    Ok(())
+}
+
+/// Sizes up the trees a replay is going to process, for the progress bar.
+fn set_totals(ctx: &mut JobContext, paths: impl Iterator<Item = PathBuf>) {
+    for path in paths {
+        let stats = tree_stats(&path);
+        ctx.progress.files_total += stats.files;
+        ctx.progress.bytes_total += stats.bytes;
+    }
+    ctx.report(true);
+}
+
+/// Moves `from` back to `to` without recording it; undo keeps the original
+/// operation for redo instead.
+fn move_back(ctx: &mut JobContext, from: &Path, to: &Path) -> Result<bool, Cancelled> {
+    let errors = ctx.errors.len();
+    transfer::transfer(ctx, from, to, true, false)?;
+    Ok(ctx.errors.len() == errors)
+}
+
+/// Reports that `path` is not where the journal expects it, which stops the
+/// replay; the rest stays in the history.
+fn diverged(ctx: &mut JobContext, path: &Path, what: &str) -> bool {
+    ctx.error(path, format!("{what}; the file system changed since, so the rest was left alone"));
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::views::operations::transfer::tests::{scratch, Harness};
+    use crate::views::operations::{JobKind, JobRequest};
+
+    fn run(kind: JobKind, sources: Vec<PathBuf>, destination: &Path) -> Vec<Operation> {
+        let harness = Harness::new(&[]);
+        let mut ctx = harness.context();
+        let request = JobRequest { kind, sources, destination: Some(destination.to_path_buf()) };
+        let _ = transfer::run(&mut ctx, &request);
+        assert!(ctx.errors.is_empty(), "{:?}", ctx.errors);
+        ctx.journal
+    }
+
+    /// Replays `operations` with `replay`, returning what was done and what wasn't.
+    fn replay(
+        replay: fn(&mut JobContext, &[Operation]) -> Result<(), Cancelled>,
+        operations: &[Operation],
+    ) -> (Vec<Operation>, Vec<Operation>, usize) {
+        let harness = Harness::new(&[]);
+        let mut ctx = harness.context();
+        let _ = replay(&mut ctx, operations);
+        (ctx.journal, ctx.unfinished, ctx.errors.len())
+    }
+
+    #[test]
+    fn copy_round_trips() {
+        let dir = scratch("journal-copy");
+        fs::create_dir_all(dir.join("to")).unwrap();
+        fs::write(dir.join("a.txt"), "a").unwrap();
+        let operations = run(JobKind::Copy, vec![dir.join("a.txt")], &dir.join("to"));
+
+        let (undone, unfinished, errors) = replay(undo, &operations);
+        assert_eq!((undone.as_slice(), unfinished.len(), errors), (operations.as_slice(), 0, 0));
+        assert!(!dir.join("to/a.txt").exists());
+        assert!(trash::list().iter().any(|item| item.original_path == dir.join("to/a.txt")));
+
+        let (redone, unfinished, errors) = replay(redo, &undone);
+        assert_eq!((redone.as_slice(), unfinished.len(), errors), (operations.as_slice(), 0, 0));
+        assert_eq!(fs::read_to_string(dir.join("to/a.txt")).unwrap(), "a");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn move_round_trips() {
+        let dir = scratch("journal-move");
+        fs::create_dir_all(dir.join("to")).unwrap();
+        fs::create_dir_all(dir.join("folder")).unwrap();
+        fs::write(dir.join("folder/inner.txt"), "inner").unwrap();
+        let operations = run(JobKind::Move, vec![dir.join("folder")], &dir.join("to"));
+
+        let (undone, _, errors) = replay(undo, &operations);
+        assert_eq!(errors, 0);
+        assert_eq!(fs::read_to_string(dir.join("folder/inner.txt")).unwrap(), "inner");
+        assert!(!dir.join("to/folder").exists());
+
+        let (redone, _, errors) = replay(redo, &undone);
+        assert_eq!((redone, errors), (operations, 0));
+        assert_eq!(fs::read_to_string(dir.join("to/folder/inner.txt")).unwrap(), "inner");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn undo_keeps_what_it_did_not_get_to() {
+        let dir = scratch("journal-undo-diverged");
+        fs::create_dir_all(dir.join("to")).unwrap();
+        for name in ["a", "b", "c"] {
+            fs::write(dir.join(name), name).unwrap();
+        }
+        let operations = run(JobKind::Copy, vec![dir.join("a"), dir.join("b"), dir.join("c")], &dir.join("to"));
+        fs::remove_file(dir.join("to/b")).unwrap();
+
+        // c is reverted, b is missing, and a is never reached
+        let (undone, unfinished, errors) = replay(undo, &operations);
+        assert_eq!(undone, &operations[2..]);
+        assert_eq!(unfinished, &operations[..2]);
+        assert_eq!(errors, 1);
+        assert!(dir.join("to/a").exists());
+        assert!(!dir.join("to/c").exists());
+
+        // Once b is back, the rest can be undone after all
+        fs::write(dir.join("to/b"), "b").unwrap();
+        let (undone, unfinished, errors) = replay(undo, &unfinished);
+        assert_eq!((undone.as_slice(), unfinished.len(), errors), (&operations[..2], 0, 0));
+        assert!(!dir.join("to/a").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn redo_keeps_what_it_did_not_get_to() {
+        let dir = scratch("journal-redo-diverged");
+        fs::create_dir_all(dir.join("to")).unwrap();
+        for name in ["a", "b", "c"] {
+            fs::write(dir.join(name), name).unwrap();
+        }
+        let operations = run(JobKind::Move, vec![dir.join("a"), dir.join("b"), dir.join("c")], &dir.join("to"));
+        let (undone, _, _) = replay(undo, &operations);
+        fs::write(dir.join("to/b"), "in the way").unwrap();
+
+        let (redone, unfinished, errors) = replay(redo, &undone);
+        assert_eq!(redone, &operations[..1]);
+        assert_eq!(unfinished, &operations[1..]);
+        assert_eq!(errors, 1);
+        assert!(dir.join("c").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn created_folder_is_only_removed_while_empty() {
+        let dir = scratch("journal-created-dir");
+        fs::create_dir_all(dir.join("new/child")).unwrap();
+        let operations = [Operation::CreatedDir(dir.join("new"))];
+
+        let (undone, unfinished, errors) = replay(undo, &operations);
+        assert_eq!((undone.len(), unfinished.as_slice(), errors), (0, operations.as_slice(), 1));
+
+        fs::remove_dir(dir.join("new/child")).unwrap();
+        let (undone, _, _) = replay(undo, &operations);
+        assert!(!dir.join("new").exists());
+        let (redone, _, _) = replay(redo, &undone);
+        assert_eq!(redone, operations);
+        assert!(dir.join("new").is_dir());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
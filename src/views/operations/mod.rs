@@ -0,0 +1,279 @@
+use dioxus::prelude::*;
+use futures_channel::mpsc as async_mpsc;
+use futures_util::StreamExt;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+
+mod jobs;
+mod journal;
+pub mod rename;
+mod transfer;
+pub mod trash;
+
+pub use jobs::{ConflictAnswer, ConflictChoice, JobId, JobProgress, JobState};
+pub use journal::{JournalEntry, Operation};
+pub use transfer::unique_name;
+
+/// How many actions can be undone.
+const MAX_JOURNAL: usize = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum JobKind {
+    Copy,
+    Move,
+    Delete,
+    /// Move to the freedesktop.org trash instead of deleting for good
+    Trash,
+}
+
+/// What a job should do. `destination` is the directory that copies and moves
+/// go into; deletes and trashing don't use it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JobRequest {
+    pub kind: JobKind,
+    pub sources: Vec<PathBuf>,
+    pub destination: Option<PathBuf>,
+}
+
+impl JobRequest {
+    /// Short human-readable description, e.g. "Copying 3 items to /tmp".
+    pub fn describe(&self) -> String {
+        let (items, destination) = self.describe_parts();
+        match self.kind {
+            JobKind::Copy => format!("Copying {items} to {destination}"),
+            JobKind::Move => format!("Moving {items} to {destination}"),
+            JobKind::Delete => format!("Deleting {items}"),
+            JobKind::Trash => format!("Moving {items} to trash"),
+        }
+    }
+
+    /// The action as named in the undo history, e.g. "Copy 3 items to /tmp".
+    pub fn title(&self) -> String {
+        let (items, destination) = self.describe_parts();
+        match self.kind {
+            JobKind::Copy => format!("Copy {items} to {destination}"),
+            JobKind::Move => format!("Move {items} to {destination}"),
+            JobKind::Delete => format!("Delete {items}"),
+            JobKind::Trash => format!("Move {items} to trash"),
+        }
+    }
+
+    fn describe_parts(&self) -> (String, String) {
+        let items = match self.sources.as_slice() {
+            [single] => single
+                .file_name()
+                .map(|name| format!("\"{}\"", name.to_string_lossy()))
+                .unwrap_or_else(|| single.display().to_string()),
+            sources => format!("{} items", sources.len()),
+        };
+        let destination = self
+            .destination
+            .as_ref()
+            .map(|dest| dest.display().to_string())
+            .unwrap_or_default();
+        (items, destination)
+    }
+}
+
+/// Where a job came from, which decides what happens to the operations it
+/// reports. Each variant carries the title of the journal entry.
+#[derive(Clone, Debug, PartialEq)]
+enum Origin {
+    Action(String),
+    Undo(String),
+    Redo(String),
+}
+
+/// UI-side view of a queued, running or finished job.
+#[derive(Clone)]
+pub struct Job {
+    pub id: JobId,
+    pub description: String,
+    pub progress: JobProgress,
+    pub state: JobState,
+    origin: Origin,
+    cancel: Arc<AtomicBool>,
+    answers: Sender<ConflictAnswer>,
+}
+
+/// Background job queue, provided as context by `App`.
+///
+/// Jobs run one at a time on a worker thread so long copies never block the
+/// UI; progress and conflict questions come back through [`JobQueue::jobs`].
+///
+/// Finished jobs land in an undo history together with the operations they
+/// performed; [`JobQueue::undo`] and [`JobQueue::redo`] replay those as jobs
+/// of their own.
+#[derive(Clone)]
+pub struct JobQueue {
+    jobs: Signal<Vec<Job>>,
+    next_id: Signal<JobId>,
+    worker: Sender<jobs::QueuedJob>,
+    undo_stack: Signal<Vec<JournalEntry>>,
+    redo_stack: Signal<Vec<JournalEntry>>,
+}
+
+impl JobQueue {
+    pub fn jobs(&self) -> Signal<Vec<Job>> {
+        self.jobs
+    }
+
+    pub fn submit(&self, request: JobRequest) -> JobId {
+        let description = request.describe();
+        let origin = Origin::Action(request.title());
+        self.enqueue(jobs::Task::Run(request), description, origin)
+    }
+
+    fn enqueue(&self, task: jobs::Task, description: String, origin: Origin) -> JobId {
+        let mut next_id = self.next_id;
+        let id = next_id();
+        next_id.set(id + 1);
+
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (answers_tx, answers_rx) = mpsc::channel();
+        let _ = self.worker.send(jobs::QueuedJob {
+            id,
+            task,
+            cancel: cancel.clone(),
+            answers: answers_rx,
+        });
+
+        let mut jobs = self.jobs;
+        jobs.push(Job {
+            id,
+            description,
+            progress: JobProgress::default(),
+            state: JobState::Queued,
+            origin,
+            cancel,
+            answers: answers_tx,
+        });
+        id
+    }
+
+    /// Adds an action that already happened, such as creating a folder, to
+    /// the undo history.
+    pub fn record(&self, entry: JournalEntry) {
+        push_entry(self.undo_stack, entry);
+        let mut redo_stack = self.redo_stack;
+        redo_stack.clear();
+    }
+
+    /// Title of the action [`JobQueue::undo`] would revert.
+    pub fn next_undo(&self) -> Option<String> {
+        self.undo_stack.read().last().map(|entry| entry.description.clone())
+    }
+
+    /// Title of the action [`JobQueue::redo`] would perform again.
+    pub fn next_redo(&self) -> Option<String> {
+        self.redo_stack.read().last().map(|entry| entry.description.clone())
+    }
+
+    /// Reverts the most recent action in the background. What could be
+    /// reverted moves to the redo history once the job finishes.
+    pub fn undo(&self) {
+        let mut undo_stack = self.undo_stack;
+        if let Some(entry) = undo_stack.pop() {
+            let description = format!("Undo: {}", entry.description);
+            self.enqueue(jobs::Task::Undo(entry.operations), description, Origin::Undo(entry.description));
+        }
+    }
+
+    /// Performs the most recently undone action again.
+    pub fn redo(&self) {
+        let mut redo_stack = self.redo_stack;
+        if let Some(entry) = redo_stack.pop() {
+            let description = format!("Redo: {}", entry.description);
+            self.enqueue(jobs::Task::Redo(entry.operations), description, Origin::Redo(entry.description));
+        }
+    }
+
+    pub fn cancel(&self, id: JobId) {
+        if let Some(job) = self.jobs.read().iter().find(|job| job.id == id) {
+            job.cancel.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Answers the conflict job `id` is waiting on.
+    pub fn answer(&self, id: JobId, choice: ConflictChoice, apply_to_all: bool) {
+        let mut jobs = self.jobs;
+        let mut jobs = jobs.write();
+        if let Some(job) = jobs.iter_mut().find(|job| job.id == id) {
+            let _ = job.answers.send(ConflictAnswer { choice, apply_to_all });
+            job.state = JobState::Running;
+        }
+    }
+
+    pub fn clear_finished(&self) {
+        let mut jobs = self.jobs;
+        jobs.retain(|job| !job.state.is_finished());
+    }
+}
+
+/// Starts the job worker thread and provides the [`JobQueue`] to every
+/// component below the caller.
+pub fn use_job_queue_provider() -> JobQueue {
+    use_context_provider(|| {
+        let (queue_tx, queue_rx) = mpsc::channel();
+        let (events_tx, mut events_rx) = async_mpsc::unbounded();
+        std::thread::spawn(move || jobs::worker(queue_rx, events_tx));
+
+        let queue = JobQueue {
+            jobs: Signal::new(Vec::new()),
+            next_id: Signal::new(1),
+            worker: queue_tx,
+            undo_stack: Signal::new(Vec::new()),
+            redo_stack: Signal::new(Vec::new()),
+        };
+
+        let mut job_list = queue.jobs;
+        let (undo_stack, mut redo_stack) = (queue.undo_stack, queue.redo_stack);
+        spawn(async move {
+            while let Some(event) = events_rx.next().await {
+                let mut jobs = job_list.write();
+                let Some(job) = jobs.iter_mut().find(|job| job.id == event.job_id()) else {
+                    continue;
+                };
+                match event {
+                    jobs::JobEvent::Started(_) => job.state = JobState::Running,
+                    jobs::JobEvent::Progress(_, progress) => job.progress = progress,
+                    jobs::JobEvent::Conflict(_, conflict) => job.state = JobState::AwaitingAnswer(conflict),
+                    jobs::JobEvent::Finished(_, state, operations, unfinished) => {
+                        job.state = state;
+                        // What an undo or redo didn't get to stays where it was,
+                        // so it can be tried again once the files are back
+                        let (done_stack, unfinished_stack, description) = match job.origin.clone() {
+                            Origin::Action(description) => {
+                                if !operations.is_empty() {
+                                    redo_stack.clear();
+                                }
+                                (undo_stack, redo_stack, description)
+                            }
+                            Origin::Undo(description) => (redo_stack, undo_stack, description),
+                            Origin::Redo(description) => (undo_stack, redo_stack, description),
+                        };
+                        if !unfinished.is_empty() {
+                            let description = description.clone();
+                            push_entry(unfinished_stack, JournalEntry { description, operations: unfinished });
+                        }
+                        if !operations.is_empty() {
+                            push_entry(done_stack, JournalEntry { description, operations });
+                        }
+                    }
+                }
+            }
+        });
+
+        queue
+    })
+}
+
+fn push_entry(mut stack: Signal<Vec<JournalEntry>>, entry: JournalEntry) {
+    let mut stack = stack.write();
+    stack.push(entry);
+    if stack.len() > MAX_JOURNAL {
+        stack.remove(0);
+    }
+}
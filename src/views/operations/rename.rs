@@ -0,0 +1,464 @@
+//! Renaming entries, one at a time or in batches driven by a [`RenamePattern`].
+//!
+//! Batch renames are planned first so the UI can preview every new name and
+//! flag collisions; nothing touches the disk until [`apply`] runs the plan.
+
+use chrono::{DateTime, Local};
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::journal::Operation;
+use super::transfer::unique_name;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum CaseTransform {
+    #[default]
+    Keep,
+    Lower,
+    Upper,
+    /// Capitalise the first letter of every word
+    Title,
+}
+
+impl CaseTransform {
+    pub const ALL: [CaseTransform; 4] = [Self::Keep, Self::Lower, Self::Upper, Self::Title];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Keep => "Keep case",
+            Self::Lower => "lowercase",
+            Self::Upper => "UPPERCASE",
+            Self::Title => "Title Case",
+        }
+    }
+
+    fn apply(self, text: &str) -> String {
+        match self {
+            Self::Keep => text.to_string(),
+            Self::Lower => text.to_lowercase(),
+            Self::Upper => text.to_uppercase(),
+            Self::Title => {
+                let mut result = String::with_capacity(text.len());
+                let mut word_start = true;
+                for c in text.chars() {
+                    if word_start {
+                        result.extend(c.to_uppercase());
+                    } else {
+                        result.extend(c.to_lowercase());
+                    }
+                    word_start = !c.is_alphanumeric();
+                }
+                result
+            }
+        }
+    }
+}
+
+/// How a batch rename derives new names.
+///
+/// With an empty `find` the replacement is a template for the whole name,
+/// otherwise it replaces every match of `find`. Replacements may use these
+/// tokens, plus `$1` capture groups in regex mode (`${1}` when a letter,
+/// digit or `_` follows):
+///
+/// - `{n}` / `{n:3}`: the counter, optionally zero-padded to a width
+/// - `{name}` / `{ext}`: the original name without extension, and the extension
+/// - `{date}` / `{date:%Y%m%d}`: the modification date, with an optional
+///   strftime format
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenamePattern {
+    pub find: String,
+    pub replace: String,
+    pub regex: bool,
+    /// Applied to the new name, leaving the extension alone
+    pub case: CaseTransform,
+    pub counter_start: u64,
+    pub counter_step: u64,
+}
+
+impl Default for RenamePattern {
+    fn default() -> Self {
+        Self {
+            find: String::new(),
+            replace: String::new(),
+            regex: false,
+            case: CaseTransform::Keep,
+            counter_start: 1,
+            counter_step: 1,
+        }
+    }
+}
+
+/// One row of a batch rename preview.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlannedRename {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    /// Why this rename can't be applied, e.g. a collision
+    pub problem: Option<String>,
+}
+
+impl PlannedRename {
+    pub fn is_change(&self) -> bool {
+        self.from != self.to
+    }
+}
+
+/// Checks that `name` can be used as a single file name.
+pub fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        Err("name is empty".to_string())
+    } else if name == "." || name == ".." {
+        Err(format!("\"{name}\" is reserved"))
+    } else if name.contains('/') || name.contains('\0') {
+        Err("name contains \"/\"".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+/// Works out the new name of every path in `paths`, numbered in that order.
+/// Fails only when the pattern itself is invalid; per-file problems are
+/// reported on the rows.
+pub fn plan(paths: &[PathBuf], pattern: &RenamePattern) -> Result<Vec<PlannedRename>, String> {
+    let regex = if pattern.regex && !pattern.find.is_empty() {
+        Some(Regex::new(&pattern.find).map_err(|err| err.to_string())?)
+    } else {
+        None
+    };
+
+    let mut planned: Vec<PlannedRename> = paths
+        .iter()
+        .enumerate()
+        .map(|(i, path)| {
+            let counter = pattern.counter_start + i as u64 * pattern.counter_step;
+            let name = file_name(path);
+            let to = match new_name(path, &name, counter, pattern, regex.as_ref()) {
+                Ok(new) => path.with_file_name(new),
+                Err(problem) => {
+                    return PlannedRename {
+                        from: path.clone(),
+                        to: path.clone(),
+                        problem: Some(problem),
+                    }
+                }
+            };
+            PlannedRename {
+                from: path.clone(),
+                to,
+                problem: None,
+            }
+        })
+        .collect();
+
+    // Collisions among the new names, and with entries that stay where they are
+    let mut targets: BTreeMap<PathBuf, usize> = BTreeMap::new();
+    for rename in &planned {
+        *targets.entry(rename.to.clone()).or_default() += 1;
+    }
+    let moving_away: BTreeSet<PathBuf> = planned
+        .iter()
+        .filter(|rename| rename.is_change())
+        .map(|rename| rename.from.clone())
+        .collect();
+    for rename in planned.iter_mut().filter(|rename| rename.problem.is_none()) {
+        if targets[&rename.to] > 1 {
+            rename.problem = Some("same new name as another file".to_string());
+        } else if rename.is_change()
+            && !moving_away.contains(&rename.to)
+            && fs::symlink_metadata(&rename.to).is_ok()
+        {
+            rename.problem = Some("a file with this name already exists".to_string());
+        }
+    }
+    Ok(planned)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn new_name(
+    path: &Path,
+    name: &str,
+    counter: u64,
+    pattern: &RenamePattern,
+    regex: Option<&Regex>,
+) -> Result<String, String> {
+    let stem = Path::new(name).file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let ext = Path::new(name).extension().map(|e| e.to_string_lossy().into_owned()).unwrap_or_default();
+    let modified: Option<DateTime<Local>> = fs::metadata(path).and_then(|m| m.modified()).ok().map(DateTime::from);
+    let tokens = Tokens { counter, stem: &stem, ext: &ext, modified };
+
+    let renamed = if pattern.find.is_empty() {
+        if pattern.replace.is_empty() {
+            name.to_string()
+        } else {
+            tokens.expand(&pattern.replace, false)?
+        }
+    } else if let Some(regex) = regex {
+        let replacement = tokens.expand(&pattern.replace, true)?;
+        regex.replace_all(name, replacement.as_str()).into_owned()
+    } else {
+        name.replace(&pattern.find, &tokens.expand(&pattern.replace, false)?)
+    };
+
+    // Case changes leave the extension alone
+    let renamed = match renamed.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => format!("{}.{ext}", pattern.case.apply(stem)),
+        _ => pattern.case.apply(&renamed),
+    };
+    validate_name(&renamed)?;
+    Ok(renamed)
+}
+
+/// Values for the `{…}` tokens of one file.
+struct Tokens<'a> {
+    counter: u64,
+    stem: &'a str,
+    ext: &'a str,
+    modified: Option<DateTime<Local>>,
+}
+
+impl Tokens<'_> {
+    /// Replaces the tokens in `template`. In regex replacements `$` in the
+    /// values is escaped so file names can't be read as capture references.
+    fn expand(&self, template: &str, for_regex: bool) -> Result<String, String> {
+        let mut result = String::with_capacity(template.len());
+        let mut rest = template;
+
+        while let Some(start) = rest.find('{') {
+            result.push_str(&rest[..start]);
+            let Some(len) = rest[start..].find('}') else {
+                rest = &rest[start..];
+                break;
+            };
+            let token = &rest[start + 1..start + len];
+            let (key, argument) = match token.split_once(':') {
+                Some((key, argument)) => (key, Some(argument)),
+                None => (token, None),
+            };
+
+            let value = match (key, argument) {
+                ("n", None) => self.counter.to_string(),
+                ("n", Some(width)) => {
+                    let width: usize = width.parse().map_err(|_| format!("bad counter width in {{{token}}}"))?;
+                    format!("{:0width$}", self.counter)
+                }
+                ("name", None) => self.stem.to_string(),
+                ("ext", None) => self.ext.to_string(),
+                ("date", format) => {
+                    let modified = self.modified.ok_or("modification date unavailable")?;
+                    let format = format.unwrap_or("%Y-%m-%d");
+                    let items: Vec<_> = chrono::format::StrftimeItems::new(format).collect();
+                    if items.contains(&chrono::format::Item::Error) {
+                        return Err(format!("bad date format in {{{token}}}"));
+                    }
+                    modified.format_with_items(items.into_iter()).to_string()
+                }
+                // Not a token; keep it as typed
+                _ => format!("{{{token}}}"),
+            };
+            if for_regex {
+                result.push_str(&value.replace('$', "$$"));
+            } else {
+                result.push_str(&value);
+            }
+            rest = &rest[start + len + 1..];
+        }
+        result.push_str(rest);
+        Ok(result)
+    }
+}
+
+/// Renames `path` to `new_name` in the same directory, refusing to replace
+/// an existing entry. Returns `None` when the name didn't change.
+pub fn rename(path: &Path, new_name: &str) -> io::Result<Option<Operation>> {
+    validate_name(new_name).map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    Ok(apply(&[(path.to_path_buf(), path.with_file_name(new_name))])?.pop())
+}
+
+/// Carries out `renames`, given as `(from, to)` pairs. Swaps and chains such as
+/// `a → b, b → c` work because entries pass through temporary names first. On
+/// failure every rename done so far is rolled back.
+///
+/// The operations returned are the individual steps, temporary names included,
+/// since only replaying those in order (or reverse) is safe for undo and redo.
+pub fn apply(renames: &[(PathBuf, PathBuf)]) -> io::Result<Vec<Operation>> {
+    let renames: Vec<&(PathBuf, PathBuf)> = renames.iter().filter(|(from, to)| from != to).collect();
+    let sources: BTreeSet<&PathBuf> = renames.iter().map(|(from, _)| from).collect();
+    let staged = renames.iter().any(|(_, to)| sources.contains(to));
+
+    let mut done: Vec<(PathBuf, PathBuf)> = Vec::new();
+    let result = (|| -> io::Result<()> {
+        let mut pending: Vec<(PathBuf, PathBuf)> = Vec::new();
+        for (from, to) in &renames {
+            let from = if staged {
+                let temporary = unique_name(&from.with_file_name(format!(".{}.renaming", file_name(from))));
+                step(from, &temporary, &mut done)?;
+                temporary
+            } else {
+                from.clone()
+            };
+            pending.push((from, to.clone()));
+        }
+        for (from, to) in pending {
+            step(&from, &to, &mut done)?;
+        }
+        Ok(())
+    })();
+
+    if let Err(err) = result {
+        for (from, to) in done.into_iter().rev() {
+            let _ = fs::rename(&to, &from);
+        }
+        return Err(err);
+    }
+    Ok(done.into_iter().map(|(from, to)| Operation::Moved { from, to }).collect())
+}
+
+fn step(from: &Path, to: &Path, done: &mut Vec<(PathBuf, PathBuf)>) -> io::Result<()> {
+    if fs::symlink_metadata(to).is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", to.display()),
+        ));
+    }
+    fs::rename(from, to)?;
+    done.push((from.to_path_buf(), to.to_path_buf()));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::views::operations::journal;
+    use crate::views::operations::transfer::tests::{scratch, Harness};
+
+    fn names(planned: &[PlannedRename]) -> Vec<(String, Option<&str>)> {
+        planned.iter().map(|rename| (file_name(&rename.to), rename.problem.as_deref())).collect()
+    }
+
+    fn pattern(find: &str, replace: &str, regex: bool) -> RenamePattern {
+        RenamePattern { find: find.to_string(), replace: replace.to_string(), regex, ..RenamePattern::default() }
+    }
+
+    #[test]
+    fn templates_expand_tokens() {
+        let dir = scratch("rename-template");
+        let paths = [dir.join("a.txt"), dir.join("b.md")];
+
+        let planned = plan(&paths, &RenamePattern { counter_start: 9, ..pattern("", "{name}-{n:3}.{ext}", false) }).unwrap();
+        assert_eq!(names(&planned), [("a-009.txt".to_string(), None), ("b-010.md".to_string(), None)]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn regex_groups_and_case() {
+        let dir = scratch("rename-regex");
+        let paths = [dir.join("IMG_1234.JPG")];
+
+        let mut pattern = pattern(r"IMG_(\d+)", "photo $1 {n}", true);
+        pattern.case = CaseTransform::Title;
+        assert_eq!(names(&plan(&paths, &pattern).unwrap()), [("Photo 1234 1.JPG".to_string(), None)]);
+        assert!(plan(&paths, &self::pattern("(", "", true)).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn flags_collisions_but_allows_swaps_and_chains() {
+        let dir = scratch("rename-plan");
+        for name in ["1", "2", "a", "keep"] {
+            fs::write(dir.join(name), name).unwrap();
+        }
+        let numbered = |start| RenamePattern { counter_start: start, ..pattern("", "{n}", false) };
+
+        // 1 → 2 while 2 → 3: 2 is moving away, so 1 may take its name
+        let chain = plan(&[dir.join("1"), dir.join("2")], &numbered(2)).unwrap();
+        assert_eq!(names(&chain), [("2".to_string(), None), ("3".to_string(), None)]);
+        // 1 ↔ 2
+        let swap = plan(&[dir.join("2"), dir.join("1")], &numbered(1)).unwrap();
+        assert_eq!(names(&swap), [("1".to_string(), None), ("2".to_string(), None)]);
+
+        let same = plan(&[dir.join("1"), dir.join("2")], &pattern("", "x", false)).unwrap();
+        assert_eq!(names(&same)[0].1, Some("same new name as another file"));
+        let taken = plan(&[dir.join("a")], &pattern("a", "keep", false)).unwrap();
+        assert_eq!(names(&taken)[0].1, Some("a file with this name already exists"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_unusable_names() {
+        assert!(validate_name("ok.txt").is_ok());
+        for name in ["", ".", "..", "a/b", "a\0b"] {
+            assert!(validate_name(name).is_err(), "{name:?}");
+        }
+    }
+
+    #[test]
+    fn swap_applies_and_round_trips_through_the_journal() {
+        let dir = scratch("rename-swap");
+        fs::write(dir.join("a"), "a").unwrap();
+        fs::write(dir.join("b"), "b").unwrap();
+        fs::write(dir.join("c"), "c").unwrap();
+        let renames = [
+            (dir.join("a"), dir.join("b")),
+            (dir.join("b"), dir.join("a")),
+            (dir.join("c"), dir.join("d")),
+        ];
+        let contents = |name: &str| fs::read_to_string(dir.join(name)).unwrap();
+
+        let operations = apply(&renames).unwrap();
+        assert_eq!((contents("a"), contents("b"), contents("d")), ("b".into(), "a".into(), "c".into()));
+
+        let harness = Harness::new(&[]);
+        let mut ctx = harness.context();
+        let _ = journal::undo(&mut ctx, &operations);
+        assert!(ctx.errors.is_empty(), "{:?}", ctx.errors);
+        assert!(ctx.unfinished.is_empty());
+        assert_eq!((contents("a"), contents("b"), contents("c")), ("a".into(), "b".into(), "c".into()));
+
+        let undone = std::mem::take(&mut ctx.journal);
+        let mut ctx = harness.context();
+        let _ = journal::redo(&mut ctx, &undone);
+        assert!(ctx.errors.is_empty(), "{:?}", ctx.errors);
+        assert_eq!((contents("a"), contents("b"), contents("d")), ("b".into(), "a".into(), "c".into()));
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 3);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn failed_batch_is_rolled_back() {
+        let dir = scratch("rename-rollback");
+        fs::write(dir.join("a"), "a").unwrap();
+        fs::write(dir.join("b"), "b").unwrap();
+        fs::write(dir.join("taken"), "taken").unwrap();
+
+        let result = apply(&[(dir.join("a"), dir.join("b")), (dir.join("b"), dir.join("taken"))]);
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(dir.join("a")).unwrap(), "a");
+        assert_eq!(fs::read_to_string(dir.join("b")).unwrap(), "b");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 3);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn single_rename_refuses_to_replace() {
+        let dir = scratch("rename-single");
+        fs::write(dir.join("a"), "a").unwrap();
+        fs::write(dir.join("b"), "b").unwrap();
+
+        assert!(rename(&dir.join("a"), "b").is_err());
+        assert_eq!(rename(&dir.join("a"), "a").unwrap(), None);
+        assert_eq!(
+            rename(&dir.join("a"), "c").unwrap(),
+            Some(Operation::Moved { from: dir.join("a"), to: dir.join("c") })
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
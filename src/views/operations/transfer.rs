@@ -0,0 +1,601 @@
+use std::fs::{self, File, Metadata};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use futures_channel::mpsc::UnboundedSender;
+
+use super::journal::Operation;
+use super::jobs::{Conflict, ConflictAnswer, ConflictChoice, JobEvent, JobId, JobProgress};
+use super::{trash, JobKind, JobRequest};
+
+const CHUNK_SIZE: usize = 1024 * 1024;
+/// Progress is reported at most this often, except when a file completes.
+const REPORT_INTERVAL: Duration = Duration::from_millis(100);
+/// How often a job waiting for a conflict answer checks whether it was cancelled.
+const ANSWER_POLL: Duration = Duration::from_millis(200);
+
+/// The job was cancelled from the UI.
+pub struct Cancelled;
+
+/// Worker-side state of the running job.
+pub struct JobContext<'a> {
+    pub id: JobId,
+    pub cancel: &'a AtomicBool,
+    pub answers: &'a Receiver<ConflictAnswer>,
+    pub events: &'a UnboundedSender<JobEvent>,
+    pub progress: JobProgress,
+    pub errors: Vec<String>,
+    /// What the job changed so far, for undo
+    pub journal: Vec<Operation>,
+    /// Operations an undo or redo stopped short of, kept for another try
+    pub unfinished: Vec<Operation>,
+    last_report: Instant,
+    /// Choice the user asked to apply to every further conflict of the job
+    remembered: Option<ConflictChoice>,
+}
+
+impl<'a> JobContext<'a> {
+    pub fn new(
+        id: JobId,
+        cancel: &'a AtomicBool,
+        answers: &'a Receiver<ConflictAnswer>,
+        events: &'a UnboundedSender<JobEvent>,
+    ) -> Self {
+        Self {
+            id,
+            cancel,
+            answers,
+            events,
+            progress: JobProgress::default(),
+            errors: Vec::new(),
+            journal: Vec::new(),
+            unfinished: Vec::new(),
+            last_report: Instant::now(),
+            remembered: None,
+        }
+    }
+
+    pub fn check_cancel(&self) -> Result<(), Cancelled> {
+        if self.cancel.load(Ordering::Relaxed) {
+            Err(Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn report(&mut self, force: bool) {
+        if force || self.last_report.elapsed() >= REPORT_INTERVAL {
+            self.last_report = Instant::now();
+            let _ = self
+                .events
+                .unbounded_send(JobEvent::Progress(self.id, self.progress.clone()));
+        }
+    }
+
+    pub fn error(&mut self, path: &Path, err: impl std::fmt::Display) {
+        self.errors.push(format!("{}: {err}", path.display()));
+    }
+
+    /// Marks a whole subtree as processed without touching it.
+    pub fn advance(&mut self, stats: TreeStats) {
+        self.progress.files_done += stats.files;
+        self.progress.bytes_done += stats.bytes;
+        self.report(true);
+    }
+
+    /// Asks the UI how to handle `target` already existing, unless an earlier
+    /// answer was meant for all conflicts.
+    fn resolve(&mut self, source: &Path, target: &Path, both_dirs: bool) -> Result<ConflictChoice, Cancelled> {
+        if let Some(choice) = self.remembered {
+            // Merge only makes sense for two directories
+            if choice != ConflictChoice::Merge || both_dirs {
+                return Ok(choice);
+            }
+        }
+
+        let conflict = Conflict {
+            source: source.to_path_buf(),
+            target: target.to_path_buf(),
+            both_dirs,
+        };
+        let _ = self.events.unbounded_send(JobEvent::Conflict(self.id, conflict));
+
+        loop {
+            self.check_cancel()?;
+            match self.answers.recv_timeout(ANSWER_POLL) {
+                Ok(answer) => {
+                    if answer.apply_to_all {
+                        self.remembered = Some(answer.choice);
+                    }
+                    return Ok(answer.choice);
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return Err(Cancelled),
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct TreeStats {
+    pub files: u64,
+    pub bytes: u64,
+}
+
+/// Counts files (anything that isn't a directory) and their bytes below `path`.
+pub fn tree_stats(path: &Path) -> TreeStats {
+    let mut stats = TreeStats::default();
+    let mut pending = vec![path.to_path_buf()];
+
+    while let Some(path) = pending.pop() {
+        let Ok(metadata) = fs::symlink_metadata(&path) else {
+            continue;
+        };
+        if metadata.is_dir() {
+            if let Ok(entries) = fs::read_dir(&path) {
+                pending.extend(entries.flatten().map(|entry| entry.path()));
+            }
+        } else {
+            stats.files += 1;
+            stats.bytes += metadata.len();
+        }
+    }
+    stats
+}
+
+/// Runs `request` to completion, cancellation or the first fatal error.
+pub fn run(ctx: &mut JobContext, request: &JobRequest) -> Result<(), Cancelled> {
+    let mut totals = TreeStats::default();
+    for source in &request.sources {
+        ctx.check_cancel()?;
+        let stats = tree_stats(source);
+        totals.files += stats.files;
+        totals.bytes += stats.bytes;
+    }
+    ctx.progress.files_total = totals.files;
+    ctx.progress.bytes_total = totals.bytes;
+    ctx.report(true);
+
+    for source in &request.sources {
+        match (request.kind, &request.destination) {
+            (JobKind::Delete, _) => delete(ctx, source)?,
+            (JobKind::Trash, _) => move_to_trash(ctx, source)?,
+            (kind, Some(destination)) => transfer_into(ctx, source, destination, kind == JobKind::Move)?,
+            (_, None) => ctx.error(source, "no destination given"),
+        }
+    }
+
+    ctx.progress.current = None;
+    ctx.report(true);
+    Ok(())
+}
+
+fn transfer_into(ctx: &mut JobContext, source: &Path, dest_dir: &Path, remove_source: bool) -> Result<(), Cancelled> {
+    let Some(name) = source.file_name() else {
+        ctx.error(source, "cannot copy a filesystem root");
+        return Ok(());
+    };
+    let target = dest_dir.join(name);
+
+    if target == source {
+        if remove_source {
+            // Moving something onto itself is a no-op
+            ctx.advance(tree_stats(source));
+            return Ok(());
+        }
+        // Pasting a copy next to the original
+        return transfer(ctx, source, &unique_name(&target), false, true);
+    }
+    if target.starts_with(source) {
+        ctx.error(source, "cannot copy a folder into itself");
+        ctx.advance(tree_stats(source));
+        return Ok(());
+    }
+    // Replacing the folder it is in would throw away the source with it
+    if source.starts_with(&target) {
+        ctx.error(source, "cannot replace a folder with its own contents");
+        ctx.advance(tree_stats(source));
+        return Ok(());
+    }
+
+    transfer(ctx, source, &target, remove_source, true)
+}
+
+/// Copies or moves `source` to `target`. With `record` set, the entries this
+/// creates are added to the job's journal; nested calls for the contents of a
+/// freshly created directory don't record, as undoing the directory covers them.
+pub fn transfer(
+    ctx: &mut JobContext,
+    source: &Path,
+    target: &Path,
+    remove_source: bool,
+    record: bool,
+) -> Result<(), Cancelled> {
+    ctx.check_cancel()?;
+    let metadata = match fs::symlink_metadata(source) {
+        Ok(metadata) => metadata,
+        Err(err) => {
+            ctx.error(source, err);
+            return Ok(());
+        }
+    };
+    ctx.progress.current = Some(source.to_path_buf());
+
+    let mut target = target.to_path_buf();
+    let mut merging = false;
+    if let Ok(existing) = fs::symlink_metadata(&target) {
+        let both_dirs = metadata.is_dir() && existing.is_dir();
+        match ctx.resolve(source, &target, both_dirs)? {
+            ConflictChoice::Skip => {
+                ctx.advance(tree_stats(source));
+                return Ok(());
+            }
+            ConflictChoice::Rename => target = unique_name(&target),
+            ConflictChoice::Merge if both_dirs => merging = true,
+            ConflictChoice::Overwrite | ConflictChoice::Merge if source.starts_with(&target) => {
+                ctx.error(source, "cannot replace a folder with its own contents");
+                ctx.advance(tree_stats(source));
+                return Ok(());
+            }
+            ConflictChoice::Overwrite | ConflictChoice::Merge => {
+                // Trash what gets replaced so the overwrite can be undone,
+                // falling back to deleting it where there is no trash
+                match trash::trash(&target) {
+                    Ok(item) if record => ctx.journal.push(Operation::Trashed(item)),
+                    Ok(_) => {}
+                    Err(_) => {
+                        if let Err(err) = remove_any(&target, &existing) {
+                            ctx.error(&target, err);
+                            ctx.advance(tree_stats(source));
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+        }
+    }
+    let operation = if remove_source {
+        Operation::Moved {
+            from: source.to_path_buf(),
+            to: target.clone(),
+        }
+    } else {
+        Operation::Copied {
+            source: source.to_path_buf(),
+            target: target.clone(),
+        }
+    };
+
+    // Same filesystem: a rename moves the whole subtree at once
+    if remove_source && !merging && fs::rename(source, &target).is_ok() {
+        if record {
+            ctx.journal.push(operation);
+        }
+        ctx.advance(tree_stats(&target));
+        return Ok(());
+    }
+
+    if metadata.is_dir() {
+        // Recorded up front so a cancelled copy can still be undone
+        if record && !merging {
+            ctx.journal.push(operation);
+        }
+        copy_dir(ctx, source, &target, &metadata, remove_source, record && merging)
+    } else {
+        if metadata.is_symlink() {
+            match copy_symlink(source, &target) {
+                Ok(()) => ctx.advance(TreeStats { files: 1, bytes: metadata.len() }),
+                Err(err) => {
+                    ctx.error(source, err);
+                    return Ok(());
+                }
+            }
+        } else if !copy_file(ctx, source, &target, &metadata)? {
+            return Ok(());
+        }
+        if record {
+            ctx.journal.push(operation);
+        }
+        if remove_source {
+            if let Err(err) = fs::remove_file(source) {
+                ctx.error(source, err);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn copy_dir(
+    ctx: &mut JobContext,
+    source: &Path,
+    target: &Path,
+    metadata: &Metadata,
+    remove_source: bool,
+    record: bool,
+) -> Result<(), Cancelled> {
+    if let Err(err) = fs::create_dir_all(target) {
+        ctx.error(target, err);
+        ctx.advance(tree_stats(source));
+        return Ok(());
+    }
+
+    let children: Vec<PathBuf> = match fs::read_dir(source) {
+        Ok(entries) => entries.flatten().map(|entry| entry.path()).collect(),
+        Err(err) => {
+            ctx.error(source, err);
+            return Ok(());
+        }
+    };
+    for child in children {
+        if let Some(name) = child.file_name() {
+            transfer(ctx, &child, &target.join(name), remove_source, record)?;
+        }
+    }
+
+    let _ = fs::set_permissions(target, metadata.permissions());
+    // Fails when a child was skipped or failed, which is already reported
+    if remove_source {
+        let _ = fs::remove_dir(source);
+    }
+    Ok(())
+}
+
+/// Copies one regular file in chunks. Returns false if the copy failed; a
+/// partially written target is removed in that case and on cancellation.
+fn copy_file(ctx: &mut JobContext, source: &Path, target: &Path, metadata: &Metadata) -> Result<bool, Cancelled> {
+    let mut copied = 0;
+    let result = (|| -> io::Result<bool> {
+        let mut input = File::open(source)?;
+        let mut output = File::create(target)?;
+        let mut buffer = vec![0; CHUNK_SIZE];
+        loop {
+            if ctx.check_cancel().is_err() {
+                return Ok(false);
+            }
+            let read = input.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            output.write_all(&buffer[..read])?;
+            copied += read as u64;
+            ctx.progress.bytes_done += read as u64;
+            ctx.report(false);
+        }
+        output.set_permissions(metadata.permissions())?;
+        Ok(true)
+    })();
+
+    match result {
+        Ok(true) => {
+            ctx.progress.files_done += 1;
+            // Files that grew while being copied would push past 100%
+            ctx.progress.bytes_done += metadata.len().saturating_sub(copied);
+            ctx.report(true);
+            Ok(true)
+        }
+        Ok(false) => {
+            let _ = fs::remove_file(target);
+            Err(Cancelled)
+        }
+        Err(err) => {
+            ctx.error(source, err);
+            let _ = fs::remove_file(target);
+            ctx.progress.files_done += 1;
+            ctx.progress.bytes_done += metadata.len().saturating_sub(copied);
+            Ok(false)
+        }
+    }
+}
+
+#[cfg(unix)]
+fn copy_symlink(source: &Path, target: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(source)?, target)
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(source: &Path, target: &Path) -> io::Result<()> {
+    fs::copy(source, target).map(|_| ())
+}
+
+/// Trashing is a single rename, so the whole subtree completes at once.
+pub fn move_to_trash(ctx: &mut JobContext, path: &Path) -> Result<(), Cancelled> {
+    ctx.check_cancel()?;
+    ctx.progress.current = Some(path.to_path_buf());
+    let stats = tree_stats(path);
+    match trash::trash(path) {
+        Ok(item) => ctx.journal.push(Operation::Trashed(item)),
+        Err(err) => ctx.error(path, err),
+    }
+    ctx.advance(stats);
+    Ok(())
+}
+
+pub fn delete(ctx: &mut JobContext, path: &Path) -> Result<(), Cancelled> {
+    ctx.check_cancel()?;
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(err) => {
+            ctx.error(path, err);
+            return Ok(());
+        }
+    };
+    ctx.progress.current = Some(path.to_path_buf());
+
+    if metadata.is_dir() {
+        match fs::read_dir(path) {
+            Ok(entries) => {
+                for child in entries.flatten() {
+                    delete(ctx, &child.path())?;
+                }
+            }
+            Err(err) => ctx.error(path, err),
+        }
+        if let Err(err) = fs::remove_dir(path) {
+            ctx.error(path, err);
+        }
+    } else {
+        if let Err(err) = fs::remove_file(path) {
+            ctx.error(path, err);
+        }
+        ctx.advance(TreeStats { files: 1, bytes: metadata.len() });
+    }
+    Ok(())
+}
+
+fn remove_any(path: &Path, metadata: &Metadata) -> io::Result<()> {
+    if metadata.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// `name (2).ext`, `name (3).ext`, … — the first variant of `path` that doesn't exist.
+pub fn unique_name(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+
+    (2..)
+        .map(|n| path.with_file_name(format!("{stem} ({n}){extension}")))
+        .find(|candidate| fs::symlink_metadata(candidate).is_err())
+        .expect("unbounded range always yields a free name")
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    use super::super::jobs::ConflictAnswer;
+
+    /// An empty directory of its own for each test.
+    pub fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("operations-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Channels a job talks to the UI through, with answers queued up for
+    /// any conflicts it asks about.
+    pub struct Harness {
+        cancel: AtomicBool,
+        answers: Receiver<ConflictAnswer>,
+        events: UnboundedSender<JobEvent>,
+    }
+
+    impl Harness {
+        pub fn new(choices: &[ConflictChoice]) -> Self {
+            let (answer_tx, answers) = mpsc::channel();
+            for &choice in choices {
+                answer_tx.send(ConflictAnswer { choice, apply_to_all: false }).unwrap();
+            }
+            Self {
+                cancel: AtomicBool::new(false),
+                answers,
+                events: futures_channel::mpsc::unbounded().0,
+            }
+        }
+
+        pub fn context(&self) -> JobContext<'_> {
+            JobContext::new(1, &self.cancel, &self.answers, &self.events)
+        }
+    }
+
+    #[test]
+    fn copy_onto_itself_picks_a_free_name() {
+        let dir = scratch("copy-twice");
+        fs::write(dir.join("a.txt"), "a").unwrap();
+
+        let harness = Harness::new(&[]);
+        let mut ctx = harness.context();
+        let _ = transfer_into(&mut ctx, &dir.join("a.txt"), &dir, false);
+        assert!(ctx.errors.is_empty());
+        assert_eq!(fs::read_to_string(dir.join("a (2).txt")).unwrap(), "a");
+        assert_eq!(
+            ctx.journal,
+            [Operation::Copied { source: dir.join("a.txt"), target: dir.join("a (2).txt") }]
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn folder_is_not_copied_into_itself() {
+        let dir = scratch("into-itself");
+        fs::create_dir_all(dir.join("x")).unwrap();
+
+        let harness = Harness::new(&[]);
+        let mut ctx = harness.context();
+        let _ = transfer_into(&mut ctx, &dir.join("x"), &dir.join("x"), false);
+        assert_eq!(ctx.errors.len(), 1);
+        assert!(!dir.join("x/x").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn moving_onto_the_containing_folder_is_refused() {
+        let dir = scratch("onto-parent");
+        fs::create_dir_all(dir.join("x/x")).unwrap();
+        fs::write(dir.join("x/x/keep.txt"), "keep").unwrap();
+
+        let harness = Harness::new(&[ConflictChoice::Overwrite]);
+        let mut ctx = harness.context();
+        let _ = transfer_into(&mut ctx, &dir.join("x/x"), &dir, true);
+        assert_eq!(ctx.errors.len(), 1);
+        assert!(ctx.journal.is_empty());
+        assert_eq!(fs::read_to_string(dir.join("x/x/keep.txt")).unwrap(), "keep");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn overwrite_never_removes_the_folder_holding_the_source() {
+        let dir = scratch("overwrite-parent");
+        fs::create_dir_all(dir.join("x")).unwrap();
+        fs::write(dir.join("x/x"), "keep").unwrap();
+
+        let harness = Harness::new(&[ConflictChoice::Overwrite]);
+        let mut ctx = harness.context();
+        let _ = transfer(&mut ctx, &dir.join("x/x"), &dir.join("x"), true, true);
+        assert_eq!(ctx.errors.len(), 1);
+        assert!(ctx.journal.is_empty());
+        assert_eq!(fs::read_to_string(dir.join("x/x")).unwrap(), "keep");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn skip_leaves_the_target_alone() {
+        let dir = scratch("skip");
+        fs::create_dir_all(dir.join("to")).unwrap();
+        fs::write(dir.join("a.txt"), "new").unwrap();
+        fs::write(dir.join("to/a.txt"), "old").unwrap();
+
+        let harness = Harness::new(&[ConflictChoice::Skip]);
+        let mut ctx = harness.context();
+        let _ = transfer_into(&mut ctx, &dir.join("a.txt"), &dir.join("to"), true);
+        assert!(ctx.errors.is_empty());
+        assert!(ctx.journal.is_empty());
+        assert_eq!(fs::read_to_string(dir.join("to/a.txt")).unwrap(), "old");
+        assert!(dir.join("a.txt").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn unique_name_keeps_the_extension() {
+        let dir = scratch("unique");
+        fs::write(dir.join("notes.txt"), "").unwrap();
+        fs::write(dir.join("notes (2).txt"), "").unwrap();
+
+        assert_eq!(unique_name(&dir.join("notes.txt")), dir.join("notes (3).txt"));
+        assert_eq!(unique_name(&dir.join("folder")), dir.join("folder (2)"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,486 @@
+//! Moving files to the trash as described by the freedesktop.org Trash
+//! specification: <https://specifications.freedesktop.org/trash-spec/latest/>.
+//!
+//! Files on the same filesystem as the home trash go to
+//! `$XDG_DATA_HOME/Trash`; anything else goes to a trash directory at the top
+//! of its own mount, so trashing is always a cheap rename.
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+use std::path::{Component, Path, PathBuf};
+
+const INFO_EXTENSION: &str = "trashinfo";
+const STICKY_BIT: u32 = 0o1000;
+
+/// An entry found in one of the trash directories.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrashedItem {
+    /// Name of the entry in the trash's `files` directory
+    pub name: String,
+    /// Where the entry lived before it was trashed
+    pub original_path: PathBuf,
+    /// `DeletionDate` as written in the info file, local time without zone
+    pub deleted_at: String,
+    pub is_dir: bool,
+    /// The trash directory holding the entry
+    pub trash_dir: PathBuf,
+}
+
+impl TrashedItem {
+    /// The trashed entry itself.
+    pub fn file_path(&self) -> PathBuf {
+        self.trash_dir.join("files").join(&self.name)
+    }
+
+    /// Its `.trashinfo` metadata file.
+    pub fn info_path(&self) -> PathBuf {
+        self.trash_dir
+            .join("info")
+            .join(format!("{}.{INFO_EXTENSION}", self.name))
+    }
+}
+
+fn uid() -> u32 {
+    // SAFETY: getuid has no preconditions and cannot fail
+    unsafe { libc::getuid() }
+}
+
+/// `$XDG_DATA_HOME/Trash`, defaulting to `~/.local/share/Trash`.
+fn home_trash() -> io::Result<PathBuf> {
+    dirs::data_dir()
+        .map(|dir| dir.join("Trash"))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no home directory"))
+}
+
+/// The topmost ancestor of `path` still on device `dev`, i.e. its mount point.
+fn mount_top(path: &Path, dev: u64) -> PathBuf {
+    let mut top = path.to_path_buf();
+    for ancestor in path.ancestors().skip(1) {
+        match fs::symlink_metadata(ancestor) {
+            Ok(metadata) if metadata.dev() == dev => top = ancestor.to_path_buf(),
+            _ => break,
+        }
+    }
+    top
+}
+
+/// The per-mount trash for `top`: `$top/.Trash/$uid` when the administrator
+/// provided a valid shared `.Trash` (a sticky, non-symlink directory),
+/// otherwise `$top/.Trash-$uid`.
+fn mount_trash(top: &Path) -> io::Result<PathBuf> {
+    let shared = top.join(".Trash");
+    if let Ok(metadata) = fs::symlink_metadata(&shared) {
+        if metadata.is_dir() && metadata.permissions().mode() & STICKY_BIT != 0 {
+            let dir = shared.join(uid().to_string());
+            if ensure_trash_dir(&dir).is_ok() {
+                return Ok(dir);
+            }
+        }
+    }
+
+    let dir = top.join(format!(".Trash-{}", uid()));
+    ensure_trash_dir(&dir)?;
+    Ok(dir)
+}
+
+fn ensure_trash_dir(dir: &Path) -> io::Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true).mode(0o700);
+    builder.create(dir.join("files"))?;
+    builder.create(dir.join("info"))
+}
+
+/// Picks the trash directory for `path`, plus the mount top that paths in its
+/// info files are relative to (`None` for the home trash, which stores
+/// absolute paths).
+fn trash_dir_for(path: &Path, dev: u64) -> io::Result<(PathBuf, Option<PathBuf>)> {
+    let home = home_trash()?;
+    ensure_trash_dir(&home)?;
+    if fs::metadata(&home)?.dev() == dev {
+        return Ok((home, None));
+    }
+
+    let top = mount_top(path, dev);
+    Ok((mount_trash(&top)?, Some(top)))
+}
+
+/// Moves `path` to the trash and returns the resulting entry.
+pub fn trash(path: &Path) -> io::Result<TrashedItem> {
+    let path = std::path::absolute(path)?;
+    let metadata = fs::symlink_metadata(&path)?;
+    // The device of the entry itself, except a mount point belongs to its parent
+    let dev = match path.parent().map(fs::symlink_metadata) {
+        Some(Ok(parent)) if parent.dev() != metadata.dev() => parent.dev(),
+        _ => metadata.dev(),
+    };
+
+    let (trash_dir, top) = trash_dir_for(&path, dev)?;
+    move_into(&path, &metadata, trash_dir, top.as_deref())
+}
+
+/// Moves `path` into `trash_dir`, recording its path relative to `top` if the
+/// trash belongs to a mount.
+fn move_into(path: &Path, metadata: &fs::Metadata, trash_dir: PathBuf, top: Option<&Path>) -> io::Result<TrashedItem> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "cannot trash a filesystem root"))?
+        .to_string_lossy()
+        .into_owned();
+    let recorded = match top {
+        Some(top) => path.strip_prefix(top).unwrap_or(path).to_path_buf(),
+        None => path.to_path_buf(),
+    };
+    let deleted_at = chrono::Local::now().format("%Y-%m-%dT%H:%M:%S").to_string();
+
+    // The spec reserves a name by creating its info file atomically first
+    let stem = Path::new(&name).file_stem().unwrap_or_default().to_string_lossy().into_owned();
+    let extension = Path::new(&name)
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+    let candidates = std::iter::once(name.clone())
+        .chain((2..).map(|n| format!("{stem} ({n}){extension}")));
+
+    for candidate in candidates {
+        let item = TrashedItem {
+            name: candidate,
+            original_path: path.to_path_buf(),
+            deleted_at: deleted_at.clone(),
+            is_dir: metadata.is_dir(),
+            trash_dir: trash_dir.clone(),
+        };
+        let mut info = match OpenOptions::new().write(true).create_new(true).open(item.info_path()) {
+            Ok(info) => info,
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err),
+        };
+        if fs::symlink_metadata(item.file_path()).is_ok() {
+            // Orphaned file without info; leave it alone and try the next name
+            let _ = fs::remove_file(item.info_path());
+            continue;
+        }
+
+        let written = write!(
+            info,
+            "[Trash Info]\nPath={}\nDeletionDate={deleted_at}\n",
+            encode_path(&recorded)
+        )
+        .and_then(|()| info.sync_all());
+        if let Err(err) = written.and_then(|()| fs::rename(path, item.file_path())) {
+            let _ = fs::remove_file(item.info_path());
+            return Err(err);
+        }
+        return Ok(item);
+    }
+    unreachable!("candidate names are unbounded")
+}
+
+/// Puts `item` back where it came from and returns that location. Fails
+/// rather than overwrite anything that has taken its place since.
+pub fn restore(item: &TrashedItem) -> io::Result<PathBuf> {
+    let target = &item.original_path;
+    if fs::symlink_metadata(target).is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", target.display()),
+        ));
+    }
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    fs::rename(item.file_path(), target)?;
+    fs::remove_file(item.info_path())?;
+    Ok(target.clone())
+}
+
+/// Every trash directory that currently exists: the home trash followed by
+/// the per-mount trashes of mounted filesystems.
+fn trash_dirs() -> Vec<(PathBuf, Option<PathBuf>)> {
+    let mut dirs = Vec::new();
+    if let Ok(home) = home_trash() {
+        dirs.push((home, None));
+    }
+
+    let uid = uid().to_string();
+    for top in mount_points() {
+        for dir in [top.join(".Trash").join(&uid), top.join(format!(".Trash-{uid}"))] {
+            if dir.join("info").is_dir() {
+                dirs.push((dir, Some(top.clone())));
+            }
+        }
+    }
+    dirs
+}
+
+/// Mount points listed in `/proc/self/mounts`.
+fn mount_points() -> Vec<PathBuf> {
+    let Ok(mounts) = fs::read_to_string("/proc/self/mounts") else {
+        return Vec::new();
+    };
+    mounts
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .map(|field| PathBuf::from(unescape_mount_field(field)))
+        .collect()
+}
+
+/// `/proc/self/mounts` escapes space, tab, newline and backslash as octal.
+fn unescape_mount_field(field: &str) -> String {
+    field
+        .replace("\\040", " ")
+        .replace("\\011", "\t")
+        .replace("\\012", "\n")
+        .replace("\\134", "\\")
+}
+
+/// Lists everything in the trash, most recently deleted first.
+pub fn list() -> Vec<TrashedItem> {
+    list_in(trash_dirs())
+}
+
+/// [`list`] for the given trash directories and the mount tops they belong to.
+fn list_in(trash_dirs: Vec<(PathBuf, Option<PathBuf>)>) -> Vec<TrashedItem> {
+    let mut items = Vec::new();
+
+    for (trash_dir, top) in trash_dirs {
+        let Ok(infos) = fs::read_dir(trash_dir.join("info")) else {
+            continue;
+        };
+        for info in infos.flatten() {
+            let info_path = info.path();
+            if info_path.extension().is_none_or(|ext| ext != INFO_EXTENSION) {
+                continue;
+            }
+            let Some(name) = info_path.file_stem().map(|stem| stem.to_string_lossy().into_owned()) else {
+                continue;
+            };
+            let Ok(contents) = fs::read_to_string(&info_path) else {
+                continue;
+            };
+            let Some((recorded, deleted_at)) = parse_info(&contents) else {
+                continue;
+            };
+            let Ok(metadata) = fs::symlink_metadata(trash_dir.join("files").join(&name)) else {
+                continue;
+            };
+
+            let original_path = match &top {
+                Some(top) if recorded.is_relative() => top.join(recorded),
+                _ => recorded,
+            };
+            items.push(TrashedItem {
+                name,
+                original_path,
+                deleted_at,
+                is_dir: metadata.is_dir(),
+                trash_dir: trash_dir.clone(),
+            });
+        }
+    }
+
+    // The spec's date format sorts correctly as text
+    items.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+    items
+}
+
+fn parse_info(contents: &str) -> Option<(PathBuf, String)> {
+    let mut in_section = false;
+    let mut path = None;
+    let mut deleted_at = String::new();
+
+    for line in contents.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_section = line == "[Trash Info]";
+        } else if in_section {
+            if let Some(value) = line.strip_prefix("Path=") {
+                path = decode_path(value);
+            } else if let Some(value) = line.strip_prefix("DeletionDate=") {
+                deleted_at = value.to_string();
+            }
+        }
+    }
+    path.map(|path| (path, deleted_at))
+}
+
+/// Percent-encodes a path for the `Path=` key, keeping `/` and unreserved characters.
+fn encode_path(path: &Path) -> String {
+    let mut encoded = String::new();
+    for &byte in path.as_os_str().as_encoded_bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+/// Decodes a `Path=` value. Returns `None` for a path with a `..` component.
+fn decode_path(value: &str) -> Option<PathBuf> {
+    use std::os::unix::ffi::OsStringExt;
+
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    let path = PathBuf::from(std::ffi::OsString::from_vec(decoded));
+    // Relative paths never contain `..` per the spec; refuse them rather than
+    // restore outside the mount
+    if path.components().any(|c| c == Component::ParentDir) {
+        None
+    } else {
+        Some(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    #[test]
+    fn encodes_everything_but_unreserved_characters() {
+        assert_eq!(encode_path(Path::new("/home/me/a b%.txt")), "/home/me/a%20b%25.txt");
+        assert_eq!(encode_path(Path::new("/tmp/ü")), "/tmp/%C3%BC");
+        assert_eq!(encode_path(Path::new("rel/x-y_z.~")), "rel/x-y_z.~");
+    }
+
+    #[test]
+    fn decoding_reverses_encoding() {
+        for path in ["/home/me/a b%.txt", "/tmp/ü/[1]#?", "relative/dir"] {
+            assert_eq!(decode_path(&encode_path(Path::new(path))).as_deref(), Some(Path::new(path)));
+        }
+        // Not valid UTF-8, which paths are still allowed to be
+        let raw = Path::new(OsStr::from_bytes(b"/tmp/\xff\xfe"));
+        assert_eq!(decode_path(&encode_path(raw)).as_deref(), Some(raw));
+    }
+
+    #[test]
+    fn malformed_escapes_are_kept_literally() {
+        assert_eq!(decode_path("/a%2"), Some(PathBuf::from("/a%2")));
+        assert_eq!(decode_path("/a%zz"), Some(PathBuf::from("/a%zz")));
+        assert_eq!(decode_path("/a%41"), Some(PathBuf::from("/aA")));
+    }
+
+    #[test]
+    fn parent_components_are_refused() {
+        assert_eq!(decode_path("../etc/passwd"), None);
+        assert_eq!(decode_path("/home/me/%2E%2E/x"), None);
+        assert_eq!(parse_info("[Trash Info]\nPath=a/../../b\nDeletionDate=2024-05-01T10:20:30\n"), None);
+    }
+
+    #[test]
+    fn reads_path_and_date_from_the_trash_info_section() {
+        let info = "[Other]\nPath=/wrong\n\n[Trash Info]\nPath=/home/me/a%20b.txt\nDeletionDate=2024-05-01T10:20:30\n";
+        assert_eq!(
+            parse_info(info),
+            Some((PathBuf::from("/home/me/a b.txt"), "2024-05-01T10:20:30".to_string()))
+        );
+        assert_eq!(parse_info("[Trash Info]\nDeletionDate=2024-05-01T10:20:30\n"), None);
+    }
+
+    #[test]
+    fn unescapes_mount_points() {
+        assert_eq!(unescape_mount_field("/media/My\\040Disk\\134x"), "/media/My Disk\\x");
+    }
+
+    fn scratch(case: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("trash-{}-{case}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn trash_into(path: &Path, trash_dir: &Path, top: Option<&Path>) -> TrashedItem {
+        ensure_trash_dir(trash_dir).unwrap();
+        let metadata = fs::symlink_metadata(path).unwrap();
+        move_into(path, &metadata, trash_dir.to_path_buf(), top).unwrap()
+    }
+
+    #[test]
+    fn trashed_files_are_listed_and_restored() {
+        let dir = scratch("round-trip");
+        let trash_dir = dir.join("Trash");
+        fs::create_dir(dir.join("folder")).unwrap();
+        fs::write(dir.join("folder/inner.txt"), "inner").unwrap();
+        fs::write(dir.join("a b.txt"), "first").unwrap();
+
+        let folder = trash_into(&dir.join("folder"), &trash_dir, None);
+        let file = trash_into(&dir.join("a b.txt"), &trash_dir, None);
+        // A second file of the same name gets a name of its own in the trash
+        fs::write(dir.join("a b.txt"), "second").unwrap();
+        let again = trash_into(&dir.join("a b.txt"), &trash_dir, None);
+        assert_eq!(again.name, "a b (2).txt");
+        assert!(!dir.join("folder").exists() && !dir.join("a b.txt").exists());
+
+        let mut listed = list_in(vec![(trash_dir.clone(), None)]);
+        listed.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(listed, [again.clone(), file.clone(), folder.clone()]);
+        assert!(listed[2].is_dir);
+
+        assert_eq!(restore(&file).unwrap(), dir.join("a b.txt"));
+        assert_eq!(fs::read_to_string(dir.join("a b.txt")).unwrap(), "first");
+        // The original place is taken again, so the other one stays put
+        assert_eq!(restore(&again).unwrap_err().kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(restore(&folder).unwrap(), dir.join("folder"));
+        assert_eq!(fs::read_to_string(dir.join("folder/inner.txt")).unwrap(), "inner");
+        assert_eq!(list_in(vec![(trash_dir, None)]), [again]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn mount_trashes_record_paths_below_the_mount() {
+        let top = scratch("mount");
+        let trash_dir = top.join(format!(".Trash-{}", uid()));
+        fs::create_dir(top.join("docs")).unwrap();
+        fs::write(top.join("docs/note.txt"), "note").unwrap();
+
+        let item = trash_into(&top.join("docs/note.txt"), &trash_dir, Some(&top));
+        let info = fs::read_to_string(item.info_path()).unwrap();
+        assert!(info.contains("\nPath=docs/note.txt\n"), "{info}");
+        assert_eq!(list_in(vec![(trash_dir.clone(), Some(top.clone()))]), std::slice::from_ref(&item));
+
+        fs::remove_dir(top.join("docs")).unwrap();
+        assert_eq!(restore(&item).unwrap(), top.join("docs/note.txt"));
+        assert!(list_in(vec![(trash_dir, Some(top.clone()))]).is_empty());
+
+        fs::remove_dir_all(top).unwrap();
+    }
+
+    #[test]
+    fn entries_that_would_restore_outside_the_mount_are_skipped() {
+        let top = scratch("escape");
+        let trash_dir = top.join(format!(".Trash-{}", uid()));
+        ensure_trash_dir(&trash_dir).unwrap();
+        fs::write(trash_dir.join("files/evil"), "").unwrap();
+        fs::write(
+            trash_dir.join("info/evil.trashinfo"),
+            "[Trash Info]\nPath=../../etc/evil\nDeletionDate=2024-05-01T10:20:30\n",
+        )
+        .unwrap();
+
+        assert!(list_in(vec![(trash_dir, Some(top.clone()))]).is_empty());
+        fs::remove_dir_all(top).unwrap();
+    }
+}
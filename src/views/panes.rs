@@ -0,0 +1,189 @@
+//! The optional second pane of FileManager.
+//!
+//! The left pane is always the active tab, through [`CurrentPath`] and
+//! [`CurrentSelection`]. The right pane has its own location and selection,
+//! which it hands to the listing as those same contexts, so the listing code
+//! doesn't care which side it is on.
+
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use super::config;
+use super::history::History;
+use super::operations::JobKind;
+use super::tabs::Tabs;
+use super::{CurrentPath, Selection};
+
+const PANES_FILE: &str = "panes.toml";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+impl Side {
+    pub fn other(self) -> Side {
+        match self {
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+        }
+    }
+}
+
+/// Paths put aside by Copy or Cut, waiting to be pasted. Shared by both
+/// panes so one can paste what the other copied.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Clipboard {
+    pub kind: JobKind,
+    pub paths: Vec<PathBuf>,
+}
+
+/// What is remembered between sessions.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct PaneState {
+    split: bool,
+    right: Option<PathBuf>,
+}
+
+/// Both panes, provided as context by [`use_panes_provider`].
+#[derive(Clone, Copy)]
+pub struct Panes {
+    pub split: Signal<bool>,
+    /// The pane keyboard shortcuts and "to the other pane" actions start from
+    pub active: Signal<Side>,
+    pub clipboard: Signal<Option<Clipboard>>,
+    tabs: Tabs,
+    left: Signal<PathBuf>,
+    right: Signal<PathBuf>,
+    right_selection: Signal<Selection>,
+    right_history: Signal<History>,
+    /// Where the right pane's history last saw it, to tell visits apart
+    /// from steps back and forward
+    right_visited: Signal<PathBuf>,
+}
+
+impl Panes {
+    /// The location shown on `side`.
+    pub fn path(&self, side: Side) -> Signal<PathBuf> {
+        match side {
+            Side::Left => self.left,
+            Side::Right => self.right,
+        }
+    }
+
+    pub fn right_selection(&self) -> Signal<Selection> {
+        self.right_selection
+    }
+
+    /// The back and forward history of `side`; the left pane's is the active
+    /// tab's.
+    pub fn history(&self, side: Side) -> History {
+        match side {
+            Side::Left => self.tabs.list().read().active_tab().history.clone(),
+            Side::Right => self.right_history.read().clone(),
+        }
+    }
+
+    pub fn go_back(&self, side: Side) {
+        match side {
+            Side::Left => self.tabs.go_back(),
+            Side::Right => self.travel_right(History::go_back),
+        }
+    }
+
+    pub fn go_forward(&self, side: Side) {
+        match side {
+            Side::Left => self.tabs.go_forward(),
+            Side::Right => self.travel_right(History::go_forward),
+        }
+    }
+
+    fn travel_right(&self, step: fn(&mut History, &Path) -> Option<PathBuf>) {
+        let (mut right, mut history, mut visited) = (self.right, self.right_history, self.right_visited);
+        let current = right.peek().clone();
+        let target = step(&mut history.write(), &current);
+        if let Some(target) = target {
+            visited.set(target.clone());
+            right.set(target);
+        }
+    }
+
+    /// Turns the second pane on or off; closing it makes the left pane active.
+    pub fn toggle_split(&self) {
+        let (mut split, mut active) = (self.split, self.active);
+        split.toggle();
+        if !split() {
+            active.set(Side::Left);
+        }
+        self.save();
+    }
+
+    fn save(&self) {
+        let state = PaneState {
+            split: *self.split.peek(),
+            // The settings file only holds UTF-8 paths
+            right: Some(self.right.peek().clone()).filter(|path| path.to_str().is_some()),
+        };
+        config::save_toml_later(PANES_FILE, &state);
+    }
+}
+
+/// Restores whether the view was split and where the right pane was. Must be
+/// called after the tabs provider, whose location becomes the left pane.
+pub fn use_panes_provider(root: PathBuf) {
+    let CurrentPath(left) = use_context();
+    let tabs = use_context::<Tabs>();
+    let state = use_hook(|| config::load_toml::<PaneState>(PANES_FILE).unwrap_or_default());
+    let panes = use_context_provider(|| {
+        let right = state.right.clone().filter(|path| path.is_dir()).unwrap_or(root);
+        Panes {
+            split: Signal::new(state.split),
+            active: Signal::new(Side::Left),
+            clipboard: Signal::new(None),
+            tabs,
+            left,
+            right: Signal::new(right.clone()),
+            right_selection: Signal::new(Selection::default()),
+            right_history: Signal::new(History::default()),
+            right_visited: Signal::new(right),
+        }
+    });
+
+    use_effect(move || {
+        let path = panes.right.read().clone();
+        let (mut history, mut visited) = (panes.right_history, panes.right_visited);
+        if *visited.peek() != path {
+            history.write().left(visited.replace(path));
+        }
+        panes.save();
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pane_state_round_trips_through_the_settings_file() {
+        let state = PaneState {
+            split: true,
+            right: Some(PathBuf::from("/home/me/music")),
+        };
+        let text = toml::to_string_pretty(&state).unwrap();
+        assert_eq!(toml::from_str::<PaneState>(&text).unwrap(), state);
+
+        let closed = PaneState::default();
+        let text = toml::to_string_pretty(&closed).unwrap();
+        assert_eq!(toml::from_str::<PaneState>(&text).unwrap(), closed);
+    }
+
+    #[test]
+    fn missing_pane_settings_fall_back_to_defaults() {
+        let state: PaneState = toml::from_str("split = true\n").unwrap();
+        assert_eq!(state, PaneState { split: true, right: None });
+        assert_eq!(toml::from_str::<PaneState>("").unwrap(), PaneState::default());
+    }
+}
@@ -0,0 +1,187 @@
+//! Turning what was typed into the path bar into a folder: `~` and
+//! environment variables are expanded, relative paths start from the folder
+//! being shown, and folder names complete on Tab.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// Expands `~` and `$VAR` / `${VAR}` in `input` and resolves it against
+/// `base`. `.` and `..` are applied to the text, like a shell's `cd` does.
+pub fn expand(input: &str, base: &Path) -> Result<PathBuf, String> {
+    let input = expand_vars(input.trim())?;
+    let path = match input.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => {
+            let home = dirs::home_dir().ok_or("There is no home folder to expand ~ to")?;
+            home.join(rest.trim_start_matches('/'))
+        }
+        _ => PathBuf::from(&input),
+    };
+    Ok(normalize(&base.join(path)))
+}
+
+/// Like [`expand`], but also requires an existing folder.
+pub fn resolve(input: &str, base: &Path) -> Result<PathBuf, String> {
+    if input.trim().is_empty() {
+        return Err("Type a path".to_string());
+    }
+    let path = expand(input, base)?;
+    match fs::metadata(&path) {
+        Ok(metadata) if metadata.is_dir() => Ok(path),
+        Ok(_) => Err(format!("{} is not a folder", path.display())),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Err(format!("{} does not exist", path.display())),
+        Err(err) => Err(format!("{}: {err}", path.display())),
+    }
+}
+
+/// Completions for the last segment of `input`, as whole replacement texts
+/// ending in `/`. Only folders are offered; hidden ones only once the segment
+/// starts with a dot.
+pub fn complete(input: &str, base: &Path) -> Vec<String> {
+    let (dir_part, prefix) = match input.rfind('/') {
+        Some(slash) => input.split_at(slash + 1),
+        None => ("", input),
+    };
+    let Ok(dir) = expand(if dir_part.is_empty() { "." } else { dir_part }, base) else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| name.starts_with(prefix) && (prefix.starts_with('.') || !name.starts_with('.')))
+        .collect();
+    names.sort_by_key(|name| name.to_lowercase());
+    names.into_iter().map(|name| format!("{dir_part}{name}/")).collect()
+}
+
+/// The longest text all `candidates` start with.
+pub fn common_prefix(candidates: &[String]) -> String {
+    let Some(first) = candidates.first() else {
+        return String::new();
+    };
+    let mut len = first.len();
+    for candidate in &candidates[1..] {
+        len = first
+            .char_indices()
+            .zip(candidate.chars())
+            .take_while(|((_, a), b)| a == b)
+            .map(|((index, a), _)| index + a.len_utf8())
+            .last()
+            .unwrap_or(0)
+            .min(len);
+    }
+    first[..len].to_string()
+}
+
+fn expand_vars(input: &str) -> Result<String, String> {
+    let mut expanded = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(dollar) = rest.find('$') {
+        expanded.push_str(&rest[..dollar]);
+        let after = &rest[dollar + 1..];
+        let (name, remaining) = if let Some(braced) = after.strip_prefix('{') {
+            let end = braced.find('}').ok_or("Missing } after ${")?;
+            (&braced[..end], &braced[end + 1..])
+        } else {
+            let end = after.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(after.len());
+            (&after[..end], &after[end..])
+        };
+        if name.is_empty() {
+            // A lone $ is just part of a name
+            expanded.push('$');
+        } else {
+            let value = env::var_os(name).ok_or_else(|| format!("${name} is not set"))?;
+            expanded.push_str(&value.to_string_lossy());
+        }
+        rest = remaining;
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand_resolves_against_the_base_folder() {
+        let base = Path::new("/home/me/src");
+        assert_eq!(expand("app", base).unwrap(), Path::new("/home/me/src/app"));
+        assert_eq!(expand("../docs/./notes", base).unwrap(), Path::new("/home/me/docs/notes"));
+        assert_eq!(expand("  /etc/../usr/ ", base).unwrap(), Path::new("/usr"));
+        assert_eq!(expand("/..", base).unwrap(), Path::new("/"));
+    }
+
+    #[test]
+    fn expand_replaces_home_and_variables() {
+        let home = dirs::home_dir().unwrap();
+        let base = Path::new("/");
+        assert_eq!(expand("~", base).unwrap(), home);
+        assert_eq!(expand("~/music", base).unwrap(), home.join("music"));
+        // Only a leading ~ on its own means home
+        assert_eq!(expand("~other", base).unwrap(), Path::new("/~other"));
+
+        let home_var = PathBuf::from(env::var("HOME").unwrap());
+        assert_eq!(expand("$HOME/x", base).unwrap(), home_var.join("x"));
+        assert_eq!(expand("${HOME}x", base).unwrap(), PathBuf::from(format!("{}x", home_var.display())));
+        assert_eq!(expand("/price$", base).unwrap(), Path::new("/price$"));
+    }
+
+    #[test]
+    fn expand_reports_unusable_variables() {
+        let base = Path::new("/");
+        assert_eq!(expand("${HOME", base).unwrap_err(), "Missing } after ${");
+        let err = expand("$FILE_MANAGER_TEST_UNSET_VARIABLE/x", base).unwrap_err();
+        assert_eq!(err, "$FILE_MANAGER_TEST_UNSET_VARIABLE is not set");
+    }
+
+    #[test]
+    fn complete_offers_matching_folders() {
+        let dir = env::temp_dir().join(format!("path-input-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for name in ["Documents", "downloads", "Desktop/inner", ".dotfiles", "dev"] {
+            fs::create_dir_all(dir.join(name)).unwrap();
+        }
+        fs::write(dir.join("diary.txt"), "").unwrap();
+
+        assert_eq!(complete("D", &dir), ["Desktop/", "Documents/"]);
+        assert_eq!(complete("d", &dir), ["dev/", "downloads/"]);
+        assert_eq!(complete("", &dir), ["Desktop/", "dev/", "Documents/", "downloads/"]);
+        assert_eq!(complete(".", &dir), [".dotfiles/"]);
+        assert_eq!(complete("Desktop/", &dir), ["Desktop/inner/"]);
+        let absolute = format!("{}/De", dir.display());
+        assert_eq!(complete(&absolute, Path::new("/")), [format!("{absolute}sktop/")]);
+        assert!(complete("missing/", &dir).is_empty());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn common_prefix_stops_at_the_first_difference() {
+        let candidates = |names: &[&str]| names.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+        assert_eq!(common_prefix(&candidates(&["Desktop/", "Documents/"])), "D");
+        assert_eq!(common_prefix(&candidates(&["café/", "cafés/"])), "café");
+        assert_eq!(common_prefix(&candidates(&["only/"])), "only/");
+        assert_eq!(common_prefix(&[]), "");
+        assert_eq!(common_prefix(&candidates(&["a", "b"])), "");
+    }
+}
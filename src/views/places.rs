@@ -0,0 +1,273 @@
+//! Places for the FileManager sidebar: the home folder, the standard XDG
+//! user folders, mounted file systems and the user's bookmarks.
+
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::config;
+
+const BOOKMARKS_FILE: &str = "bookmarks.toml";
+
+/// A folder the sidebar links to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Place {
+    pub name: String,
+    pub path: PathBuf,
+    pub icon: &'static str,
+}
+
+/// Home and the XDG user folders that exist, e.g. Documents and Downloads.
+pub fn standard_places() -> Vec<Place> {
+    let Some(home) = dirs::home_dir() else {
+        return Vec::new();
+    };
+    let mut places = vec![Place {
+        name: "Home".to_string(),
+        path: home.clone(),
+        icon: "🏠",
+    }];
+    let user_dirs = [
+        (dirs::desktop_dir(), "🖥"),
+        (dirs::document_dir(), "📄"),
+        (dirs::download_dir(), "⬇"),
+        (dirs::audio_dir(), "🎵"),
+        (dirs::picture_dir(), "🖼"),
+        (dirs::video_dir(), "🎬"),
+    ];
+    for (dir, icon) in user_dirs {
+        // Unset XDG folders fall back to home itself
+        let Some(path) = dir.filter(|dir| *dir != home && dir.is_dir()) else {
+            continue;
+        };
+        places.push(Place {
+            name: display_name(&path),
+            path,
+            icon,
+        });
+    }
+    places
+}
+
+/// The root file system, then the others worth browsing from
+/// `/proc/self/mountinfo`: block devices and network shares, without the
+/// kernel's own pseudo file systems.
+pub fn mounted_places() -> Vec<Place> {
+    let mut places = vec![Place {
+        name: "File System".to_string(),
+        path: PathBuf::from("/"),
+        icon: "💽",
+    }];
+    let Ok(mountinfo) = fs::read_to_string("/proc/self/mountinfo") else {
+        return places;
+    };
+    for mount in mountinfo.lines().filter_map(parse_mountinfo_line) {
+        if !mount.is_browsable() || places.iter().any(|place| place.path == mount.mount_point) {
+            continue;
+        }
+        places.push(Place {
+            name: display_name(&mount.mount_point),
+            icon: if mount.is_network() { "🌐" } else { "💽" },
+            path: mount.mount_point,
+        });
+    }
+    places
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Mount {
+    mount_point: PathBuf,
+    fs_type: String,
+    source: String,
+}
+
+impl Mount {
+    fn is_network(&self) -> bool {
+        matches!(self.fs_type.as_str(), "nfs" | "nfs4" | "cifs" | "smb3" | "fuse.sshfs")
+    }
+
+    fn is_browsable(&self) -> bool {
+        // Snap packages and similar images are mounted read-only squashfs
+        let is_device = self.source.starts_with("/dev/") && self.fs_type != "squashfs";
+        let is_system = ["/boot", "/snap", "/var/lib"].iter().any(|dir| self.mount_point.starts_with(dir));
+        (is_device || self.is_network()) && !is_system
+    }
+}
+
+/// Parses one line of mountinfo, e.g.
+/// `36 35 98:0 /mnt1 /mnt2 rw,noatime master:1 - ext3 /dev/root rw,errors=continue`.
+fn parse_mountinfo_line(line: &str) -> Option<Mount> {
+    let (before, after) = line.split_once(" - ")?;
+    let mount_point = before.split(' ').nth(4)?;
+    let mut after = after.split(' ');
+    let fs_type = after.next()?;
+    let source = after.next()?;
+    Some(Mount {
+        mount_point: PathBuf::from(unescape_octal(mount_point)),
+        fs_type: fs_type.to_string(),
+        source: unescape_octal(source),
+    })
+}
+
+/// mountinfo writes spaces, tabs, newlines and backslashes as `\ooo`.
+fn unescape_octal(field: &str) -> String {
+    let mut bytes = Vec::with_capacity(field.len());
+    let raw = field.as_bytes();
+    let mut i = 0;
+    while i < raw.len() {
+        let code = raw.get(i + 1..i + 4).and_then(|digits| u8::from_str_radix(std::str::from_utf8(digits).ok()?, 8).ok());
+        match code {
+            Some(code) if raw[i] == b'\\' => {
+                bytes.push(code);
+                i += 4;
+            }
+            _ => {
+                bytes.push(raw[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+fn display_name(path: &Path) -> String {
+    match path.file_name() {
+        Some(name) => name.to_string_lossy().into_owned(),
+        None => path.display().to_string(),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// The bookmarks file; TOML needs a table at the top.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct BookmarksFile {
+    bookmarks: Vec<Bookmark>,
+}
+
+/// The user's bookmarks, provided as context by [`use_bookmarks_provider`].
+#[derive(Clone, Copy)]
+pub struct Bookmarks {
+    list: Signal<Vec<Bookmark>>,
+    /// Folder row being dragged out of a listing, which the sidebar
+    /// bookmarks when it is dropped there
+    pub dragged: Signal<Option<PathBuf>>,
+}
+
+impl Bookmarks {
+    pub fn list(&self) -> Signal<Vec<Bookmark>> {
+        self.list
+    }
+
+    /// Bookmarks `path` under its folder name, unless it already is.
+    pub fn add(&self, path: PathBuf) {
+        let mut list = self.list;
+        if list.peek().iter().any(|bookmark| bookmark.path == path) {
+            return;
+        }
+        list.write().push(Bookmark {
+            name: display_name(&path),
+            path,
+        });
+        self.save();
+    }
+
+    pub fn remove(&self, index: usize) {
+        let mut list = self.list;
+        if index < list.peek().len() {
+            list.write().remove(index);
+            self.save();
+        }
+    }
+
+    /// Renames the bookmark at `index`; an empty name keeps the old one.
+    pub fn rename(&self, index: usize, name: &str) {
+        let mut list = self.list;
+        let name = name.trim();
+        if name.is_empty() || index >= list.peek().len() {
+            return;
+        }
+        list.write()[index].name = name.to_string();
+        self.save();
+    }
+
+    /// Moves the bookmark at `from` to position `to`.
+    pub fn reorder(&self, from: usize, to: usize) {
+        let mut list = self.list;
+        let len = list.peek().len();
+        if from >= len || to >= len || from == to {
+            return;
+        }
+        let mut bookmarks = list.write();
+        let bookmark = bookmarks.remove(from);
+        bookmarks.insert(to, bookmark);
+        drop(bookmarks);
+        self.save();
+    }
+
+    fn save(&self) {
+        // The settings file only holds UTF-8 paths, so others last until the
+        // app quits
+        let list = self.list.peek();
+        let bookmarks = list.iter().filter(|bookmark| bookmark.path.to_str().is_some()).cloned().collect();
+        let _ = config::save_toml(BOOKMARKS_FILE, &BookmarksFile { bookmarks });
+    }
+}
+
+/// Loads the saved bookmarks and provides [`Bookmarks`].
+pub fn use_bookmarks_provider() {
+    use_context_provider(|| Bookmarks {
+        list: Signal::new(config::load_toml::<BookmarksFile>(BOOKMARKS_FILE).unwrap_or_default().bookmarks),
+        dragged: Signal::new(None),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bookmarks_round_trip_through_the_settings_file() {
+        let list = vec![Bookmark {
+            name: "Projects".to_string(),
+            path: PathBuf::from("/home/me/projects"),
+        }];
+        let file = BookmarksFile { bookmarks: list.clone() };
+        let text = toml::to_string_pretty(&file).unwrap();
+        assert_eq!(toml::from_str::<BookmarksFile>(&text).unwrap().bookmarks, list);
+    }
+
+    #[test]
+    fn mountinfo_lines_give_mount_point_type_and_source() {
+        let line = "36 35 98:0 / /mnt/usb rw,noatime master:1 - ext4 /dev/sdb1 rw,errors=continue";
+        let mount = parse_mountinfo_line(line).unwrap();
+        assert_eq!(mount.mount_point, Path::new("/mnt/usb"));
+        assert_eq!(mount.fs_type, "ext4");
+        assert_eq!(mount.source, "/dev/sdb1");
+
+        // Without optional fields, and with escaped spaces in the mount point
+        let line = "52 29 0:46 / /media/me/My\\040Disk rw - vfat /dev/sdc1 rw";
+        let mount = parse_mountinfo_line(line).unwrap();
+        assert_eq!(mount.mount_point, Path::new("/media/me/My Disk"));
+        assert_eq!(mount.fs_type, "vfat");
+
+        assert_eq!(parse_mountinfo_line("garbage"), None);
+        assert_eq!(parse_mountinfo_line("1 2 3:4 / - ext4 /dev/sda1"), None);
+    }
+
+    #[test]
+    fn octal_escapes_are_decoded() {
+        assert_eq!(unescape_octal("a\\040b"), "a b");
+        assert_eq!(unescape_octal("tab\\011here"), "tab\there");
+        assert_eq!(unescape_octal("back\\134slash"), "back\\slash");
+        assert_eq!(unescape_octal("caf\\303\\251"), "café");
+        // Anything that isn't three octal digits stays as it was
+        assert_eq!(unescape_octal("\\08x \\12 \\777 end\\"), "\\08x \\12 \\777 end\\");
+    }
+}
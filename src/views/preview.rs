@@ -0,0 +1,323 @@
+//! Loading files for the preview pane.
+//!
+//! Only the start of a file is read, so previewing a huge log costs the same
+//! as previewing a small one. Structured formats are pretty-printed when the
+//! whole file fits in that first chunk.
+
+use base64::Engine;
+use pulldown_cmark::{html, CowStr, Event, LinkType, Options, Parser, Tag, TagEnd};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::Path;
+use std::sync::OnceLock;
+use syntect::highlighting::ThemeSet;
+use syntect::html::highlighted_html_for_string;
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+
+/// Bytes of a text file that are read and shown.
+const TEXT_LIMIT: usize = 256 * 1024;
+/// Highlighting is the slow part; longer text is shown plain.
+const HIGHLIGHT_LIMIT: usize = 64 * 1024;
+/// Bytes of a binary file shown in the hex view.
+const HEX_LIMIT: usize = 4 * 1024;
+/// Larger images only get their dimensions shown.
+const IMAGE_LIMIT: u64 = 16 * 1024 * 1024;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PreviewContent {
+    /// Text as HTML, highlighted when its syntax is known
+    Code { html: String, syntax: String },
+    /// Rendered Markdown; raw HTML in the source is shown as text
+    Markdown { html: String },
+    Image {
+        /// None when the image is too large to embed
+        data_url: Option<String>,
+        width: usize,
+        height: usize,
+    },
+    /// `offset  hex bytes  ascii` lines
+    Hex(Vec<String>),
+    Empty,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Preview {
+    pub content: PreviewContent,
+    pub size: u64,
+    /// Only the first part of the file is shown
+    pub truncated: bool,
+    /// Something worth knowing about how the file is shown
+    pub note: Option<String>,
+}
+
+/// Reads enough of the file at `path` to preview it.
+pub fn load(path: &Path) -> io::Result<Preview> {
+    let size = fs::metadata(path)?.len();
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let mut preview = Preview {
+        content: PreviewContent::Empty,
+        size,
+        truncated: false,
+        note: None,
+    };
+    if size == 0 {
+        return Ok(preview);
+    }
+
+    if let Some(mime) = image_mime(&extension) {
+        if let Ok(dimensions) = imagesize::size(path) {
+            let data_url = if size <= IMAGE_LIMIT {
+                let bytes = fs::read(path)?;
+                Some(format!(
+                    "data:{mime};base64,{}",
+                    base64::engine::general_purpose::STANDARD.encode(bytes)
+                ))
+            } else {
+                preview.note = Some("Too large to show; only its dimensions were read".to_string());
+                None
+            };
+            preview.content = PreviewContent::Image {
+                data_url,
+                width: dimensions.width,
+                height: dimensions.height,
+            };
+            return Ok(preview);
+        }
+    }
+
+    let mut head = Vec::with_capacity(TEXT_LIMIT.min(size as usize));
+    File::open(path)?.take(TEXT_LIMIT as u64 + 1).read_to_end(&mut head)?;
+    preview.truncated = head.len() > TEXT_LIMIT;
+    head.truncate(TEXT_LIMIT);
+
+    let Some(text) = as_text(&head, preview.truncated) else {
+        preview.truncated = head.len() > HEX_LIMIT || preview.truncated;
+        head.truncate(HEX_LIMIT);
+        preview.content = PreviewContent::Hex(hex_lines(&head));
+        return Ok(preview);
+    };
+
+    if matches!(extension.as_str(), "md" | "markdown") {
+        preview.content = PreviewContent::Markdown { html: markdown(text) };
+        return Ok(preview);
+    }
+
+    // Pretty-printing needs the whole document
+    let pretty = match extension.as_str() {
+        _ if preview.truncated => None,
+        "json" => Some(
+            serde_json::from_str::<serde_json::Value>(text)
+                .map_err(|err| err.to_string())
+                .and_then(|value| serde_json::to_string_pretty(&value).map_err(|err| err.to_string())),
+        ),
+        "toml" => Some(
+            toml::from_str::<toml::Value>(text)
+                .map_err(|err| err.to_string())
+                .and_then(|value| toml::to_string_pretty(&value).map_err(|err| err.to_string())),
+        ),
+        "yaml" | "yml" => Some(
+            serde_yaml::from_str::<serde_yaml::Value>(text)
+                .map_err(|err| err.to_string())
+                .and_then(|value| serde_yaml::to_string(&value).map_err(|err| err.to_string())),
+        ),
+        _ => None,
+    };
+    let text = match pretty {
+        Some(Ok(pretty)) => pretty,
+        Some(Err(err)) => {
+            preview.note = Some(format!("Could not parse it, so it is shown as written: {err}"));
+            text.to_string()
+        }
+        None => text.to_string(),
+    };
+
+    let (html, syntax) = highlight(&text, &extension);
+    preview.content = PreviewContent::Code { html, syntax };
+    Ok(preview)
+}
+
+fn image_mime(extension: &str) -> Option<&'static str> {
+    match extension {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        "bmp" => Some("image/bmp"),
+        "ico" => Some("image/x-icon"),
+        _ => None,
+    }
+}
+
+/// `bytes` as UTF-8 text, or `None` if they look binary. A character cut in
+/// half at the end of a `truncated` read doesn't count against it.
+fn as_text(bytes: &[u8], truncated: bool) -> Option<&str> {
+    if bytes.contains(&0) {
+        return None;
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Some(text),
+        Err(err) if truncated && err.error_len().is_none() => {
+            std::str::from_utf8(&bytes[..err.valid_up_to()]).ok()
+        }
+        Err(_) => None,
+    }
+}
+
+fn hex_lines(bytes: &[u8]) -> Vec<String> {
+    bytes
+        .chunks(16)
+        .enumerate()
+        .map(|(row, chunk)| {
+            let hex: Vec<String> = chunk.iter().map(|byte| format!("{byte:02x}")).collect();
+            let ascii: String = chunk
+                .iter()
+                .map(|&byte| if byte.is_ascii_graphic() || byte == b' ' { byte as char } else { '.' })
+                .collect();
+            format!("{:08x}  {:<47}  {ascii}", row * 16, hex.join(" "))
+        })
+        .collect()
+}
+
+fn markdown(text: &str) -> String {
+    let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    // What each open link or image became: the tag that closes it, if it's
+    // still there, and the text to show if nothing else ends up inside it
+    let mut open: Vec<(Option<TagEnd>, Option<CowStr>)> = Vec::new();
+    // A file being previewed doesn't get to run scripts in the app or make it
+    // fetch anything; remote images become links to them
+    let events = Parser::new_ext(text, options).flat_map(|event| {
+        let event = match event {
+            Event::Start(Tag::Link { link_type, dest_url, title, id }) => {
+                let kept = link_type == LinkType::Email || is_safe_link(&dest_url);
+                open.push((kept.then_some(TagEnd::Link), None));
+                let link = Event::Start(Tag::Link { link_type, dest_url, title, id });
+                return kept.then_some(link).into_iter().collect();
+            }
+            Event::Start(Tag::Image { link_type, dest_url, title, id }) if is_embedded_image(&dest_url) => {
+                open.push((Some(TagEnd::Image), None));
+                return vec![Event::Start(Tag::Image { link_type, dest_url, title, id })];
+            }
+            Event::Start(Tag::Image { link_type, dest_url, title, id }) => {
+                if !is_safe_link(&dest_url) {
+                    open.push((None, None));
+                    return Vec::new();
+                }
+                open.push((Some(TagEnd::Link), Some(dest_url.clone())));
+                return vec![Event::Start(Tag::Link { link_type, dest_url, title, id })];
+            }
+            Event::End(TagEnd::Link | TagEnd::Image) => {
+                let Some((end, fallback)) = open.pop() else {
+                    return Vec::new();
+                };
+                return fallback.map(Event::Text).into_iter().chain(end.map(Event::End)).collect();
+            }
+            Event::Html(raw) | Event::InlineHtml(raw) => Event::Text(raw),
+            event => event,
+        };
+        if let Some((_, fallback)) = open.last_mut() {
+            *fallback = None;
+        }
+        vec![event]
+    });
+    let mut output = String::new();
+    html::push_html(&mut output, events);
+    output
+}
+
+/// Whether a link may point at `url`: web pages, mail addresses and places
+/// in the same document.
+fn is_safe_link(url: &str) -> bool {
+    url.starts_with('#') || matches!(url_scheme(url).as_deref(), Some("http" | "https" | "mailto"))
+}
+
+/// Whether `url` carries the image itself rather than pointing somewhere.
+fn is_embedded_image(url: &str) -> bool {
+    url.trim_start().to_ascii_lowercase().starts_with("data:image/")
+}
+
+/// The lowercased scheme of `url`, ignoring the whitespace and control
+/// characters browsers skip over.
+fn url_scheme(url: &str) -> Option<String> {
+    let url: String = url.chars().filter(|c| !c.is_ascii_whitespace() && !c.is_ascii_control()).collect();
+    let (scheme, _) = url.split_once(':')?;
+    let valid = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c));
+    valid.then(|| scheme.to_ascii_lowercase())
+}
+
+/// `text` as HTML with inline colours, and the name of the syntax used.
+fn highlight(text: &str, extension: &str) -> (String, String) {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    static THEMES: OnceLock<ThemeSet> = OnceLock::new();
+    let syntaxes = SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines);
+
+    let syntax: Option<&SyntaxReference> = syntaxes.find_syntax_by_extension(extension).or_else(|| {
+        let first_line = text.lines().next().unwrap_or_default();
+        syntaxes.find_syntax_by_first_line(first_line)
+    });
+    let plain = || format!("<pre>{}</pre>", escape_html(text));
+    let Some(syntax) = syntax.filter(|_| text.len() <= HIGHLIGHT_LIMIT) else {
+        return (plain(), syntax.map_or("Plain text", |syntax| &syntax.name).to_string());
+    };
+
+    let themes = THEMES.get_or_init(ThemeSet::load_defaults);
+    let html = highlighted_html_for_string(text, syntaxes, syntax, &themes.themes["InspiredGitHub"])
+        .unwrap_or_else(|_| plain());
+    (html, syntax.name.clone())
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markdown_keeps_web_and_mail_links() {
+        let html = markdown("[a](https://example.com) [b](#usage) [c](mailto:me@example.com) <me@example.com>");
+        assert!(html.contains(r#"href="https://example.com""#));
+        assert!(html.contains(r##"href="#usage""##));
+        assert!(html.contains(r#"href="mailto:me@example.com""#));
+        assert_eq!(html.matches("<a ").count(), 4);
+    }
+
+    #[test]
+    fn markdown_drops_links_to_other_schemes() {
+        let html = markdown("[click](javascript:alert(1)) [x](JavaScript\t:alert(1)) [f](file:///etc/passwd) [y](//evil.example)");
+        assert!(!html.contains("<a"), "{html}");
+        assert!(html.contains("click"));
+    }
+
+    #[test]
+    fn markdown_turns_remote_images_into_links() {
+        let html = markdown("![logo](https://example.com/logo.png) ![](https://example.com/track.gif)");
+        assert!(!html.contains("<img"), "{html}");
+        assert!(html.contains(r#"<a href="https://example.com/logo.png">logo</a>"#));
+        assert!(html.contains(r#"<a href="https://example.com/track.gif">https://example.com/track.gif</a>"#));
+
+        let html = markdown("![dot](data:image/png;base64,iVBORw0KGgo=) ![x](javascript:alert(1))");
+        assert_eq!(html.matches("<img").count(), 1);
+        assert!(!html.contains("javascript"));
+    }
+
+    #[test]
+    fn markdown_shows_raw_html_as_text() {
+        let html = markdown("<script>alert(1)</script>\n\nhi <b onclick=\"x\">there</b>");
+        assert!(!html.contains("<script") && !html.contains("<b "), "{html}");
+    }
+}
@@ -0,0 +1,254 @@
+use dioxus::prelude::*;
+use std::collections::{BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+
+/// The selected rows of the FileManager listing, shared as context so file
+/// operations, the status bar and menus all act on the same set of paths.
+#[derive(Clone, Copy)]
+pub struct CurrentSelection(pub Signal<Selection>);
+
+/// A set of selected paths plus the anchor that Shift-click ranges start from,
+/// and the keyboard cursor.
+///
+/// Methods that depend on row order take the paths in display order, so the
+/// selection itself doesn't care how the listing is sorted.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Selection {
+    paths: BTreeSet<PathBuf>,
+    anchor: Option<PathBuf>,
+    /// Row the arrow keys move from; follows clicks too
+    cursor: Option<PathBuf>,
+}
+
+impl Selection {
+    pub fn contains(&self, path: &Path) -> bool {
+        self.paths.contains(path)
+    }
+
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item = &PathBuf> {
+        self.paths.iter()
+    }
+
+    pub fn cursor(&self) -> Option<&PathBuf> {
+        self.cursor.as_ref()
+    }
+
+    /// Plain click: select only `path`.
+    pub fn select_only(&mut self, path: PathBuf) {
+        self.paths.clear();
+        self.paths.insert(path.clone());
+        self.anchor = Some(path.clone());
+        self.cursor = Some(path);
+    }
+
+    /// Ctrl-click: flip `path` and make it the new range anchor.
+    pub fn toggle(&mut self, path: PathBuf) {
+        if !self.paths.remove(&path) {
+            self.paths.insert(path.clone());
+        }
+        self.anchor = Some(path.clone());
+        self.cursor = Some(path);
+    }
+
+    /// Shift-click: select every row between the anchor and `path`, replacing
+    /// the rest of the selection. Without an anchor this is a plain click.
+    pub fn select_range(&mut self, path: PathBuf, order: &[PathBuf]) {
+        let anchor = self.anchor.as_ref().and_then(|a| order.iter().position(|p| p == a));
+        let (Some(from), Some(to)) = (anchor, order.iter().position(|p| *p == path)) else {
+            self.select_only(path);
+            return;
+        };
+
+        let (start, end) = if from <= to { (from, to) } else { (to, from) };
+        self.paths = order[start..=end].iter().cloned().collect();
+        self.cursor = Some(path);
+    }
+
+    /// Arrow keys: moves the cursor `offset` rows from where it is, clamped to
+    /// the listing. Plain moves select only the new row; `extend` selects from
+    /// the anchor to it like Shift-click.
+    pub fn move_cursor(&mut self, offset: isize, extend: bool, order: &[PathBuf]) {
+        let Some(last) = order.len().checked_sub(1) else {
+            return;
+        };
+        let target = match self.cursor.as_ref().and_then(|cursor| order.iter().position(|p| p == cursor)) {
+            Some(current) => current.saturating_add_signed(offset).min(last),
+            // The first key press lands on the first or last row
+            None if offset < 0 => last,
+            None => 0,
+        };
+        let path = order[target].clone();
+        if extend {
+            self.select_range(path, order);
+        } else {
+            self.select_only(path);
+        }
+    }
+
+    /// Space: flips the row under the cursor.
+    pub fn toggle_cursor(&mut self) {
+        if let Some(cursor) = self.cursor.clone() {
+            self.toggle(cursor);
+        }
+    }
+
+    pub fn select_all(&mut self, order: &[PathBuf]) {
+        self.paths = order.iter().cloned().collect();
+    }
+
+    pub fn invert(&mut self, order: &[PathBuf]) {
+        self.paths = order
+            .iter()
+            .filter(|path| !self.paths.contains(*path))
+            .cloned()
+            .collect();
+    }
+
+    pub fn clear(&mut self) {
+        self.paths.clear();
+        self.anchor = None;
+    }
+
+    /// Drops paths that are no longer listed, e.g. after they were deleted.
+    pub fn retain_listed(&mut self, order: &[PathBuf]) {
+        let listed: HashSet<&PathBuf> = order.iter().collect();
+        self.paths.retain(|path| listed.contains(path));
+        if self.anchor.as_ref().is_some_and(|anchor| !listed.contains(anchor)) {
+            self.anchor = None;
+        }
+        if self.cursor.as_ref().is_some_and(|cursor| !listed.contains(cursor)) {
+            self.cursor = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(names: &str) -> Vec<PathBuf> {
+        names.chars().map(|name| PathBuf::from(format!("/d/{name}"))).collect()
+    }
+
+    fn selected(selection: &Selection) -> String {
+        selection
+            .paths()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn range_runs_from_the_anchor_either_way() {
+        let order = rows("abcde");
+        let mut selection = Selection::default();
+        selection.select_only(order[3].clone());
+
+        selection.select_range(order[1].clone(), &order);
+        assert_eq!(selected(&selection), "bcd");
+        // The anchor stays put, so a second Shift-click replaces the range
+        selection.select_range(order[4].clone(), &order);
+        assert_eq!(selected(&selection), "de");
+        assert_eq!(selection.cursor(), Some(&order[4]));
+    }
+
+    #[test]
+    fn range_without_an_anchor_selects_one_row() {
+        let order = rows("abc");
+        let mut selection = Selection::default();
+        selection.select_range(order[2].clone(), &order);
+        assert_eq!(selected(&selection), "c");
+
+        // An anchor that is no longer listed counts as none
+        selection.select_range(order[0].clone(), &rows("ab"));
+        assert_eq!(selected(&selection), "a");
+    }
+
+    #[test]
+    fn toggling_moves_the_anchor() {
+        let order = rows("abcde");
+        let mut selection = Selection::default();
+        selection.select_only(order[0].clone());
+        selection.toggle(order[3].clone());
+        assert_eq!(selected(&selection), "ad");
+
+        selection.select_range(order[4].clone(), &order);
+        assert_eq!(selected(&selection), "de");
+        selection.toggle(order[4].clone());
+        assert_eq!(selected(&selection), "d");
+    }
+
+    #[test]
+    fn invert_flips_every_listed_row() {
+        let order = rows("abcd");
+        let mut selection = Selection::default();
+        selection.select_only(order[1].clone());
+        selection.toggle(order[2].clone());
+
+        selection.invert(&order);
+        assert_eq!(selected(&selection), "ad");
+        selection.invert(&order);
+        assert_eq!(selected(&selection), "bc");
+    }
+
+    #[test]
+    fn cursor_starts_at_either_end_and_stays_inside_the_listing() {
+        let order = rows("abcd");
+        let mut selection = Selection::default();
+        selection.move_cursor(-1, false, &order);
+        assert_eq!(selection.cursor(), Some(&order[3]));
+
+        let mut selection = Selection::default();
+        selection.move_cursor(1, false, &order);
+        assert_eq!(selection.cursor(), Some(&order[0]));
+        selection.move_cursor(-5, false, &order);
+        assert_eq!(selection.cursor(), Some(&order[0]));
+        selection.move_cursor(10, false, &order);
+        assert_eq!((selection.cursor(), selected(&selection).as_str()), (Some(&order[3]), "d"));
+
+        let mut selection = Selection::default();
+        selection.move_cursor(1, false, &[]);
+        assert_eq!(selection, Selection::default());
+    }
+
+    #[test]
+    fn extending_the_cursor_selects_from_the_anchor() {
+        let order = rows("abcde");
+        let mut selection = Selection::default();
+        selection.select_only(order[2].clone());
+
+        selection.move_cursor(1, true, &order);
+        selection.move_cursor(1, true, &order);
+        assert_eq!(selected(&selection), "cde");
+        selection.move_cursor(-3, true, &order);
+        assert_eq!(selected(&selection), "bc");
+
+        // A plain move starts over from the new row
+        selection.move_cursor(-1, false, &order);
+        selection.move_cursor(1, true, &order);
+        assert_eq!(selected(&selection), "ab");
+    }
+
+    #[test]
+    fn unlisted_paths_drop_out() {
+        let order = rows("abcd");
+        let mut selection = Selection::default();
+        selection.select_only(order[1].clone());
+        selection.select_range(order[3].clone(), &order);
+
+        let remaining = rows("ac");
+        selection.retain_listed(&remaining);
+        assert_eq!(selected(&selection), "c");
+        assert_eq!(selection.cursor(), None);
+        // The anchor went too, so Shift-click falls back to a plain click
+        selection.select_range(order[0].clone(), &remaining);
+        assert_eq!(selected(&selection), "a");
+    }
+}
@@ -0,0 +1,286 @@
+//! FileManager tabs, each with its own location, history and selection.
+//!
+//! The active tab's location and selection live in the [`CurrentPath`] and
+//! [`CurrentSelection`] contexts like before, so views that only care about
+//! "where we are" don't need to know about tabs. Switching tabs stores those
+//! signals into the tab being left and loads them from the one being opened.
+
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use super::config;
+use super::history::History;
+use super::{CurrentPath, CurrentSelection, Selection};
+
+const TABS_FILE: &str = "tabs.toml";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Tab {
+    pub id: u64,
+    pub path: PathBuf,
+    #[serde(default)]
+    pub history: History,
+    /// Only kept while the app runs
+    #[serde(skip)]
+    pub selection: Selection,
+}
+
+impl Tab {
+    fn new(id: u64, path: PathBuf) -> Self {
+        Tab {
+            id,
+            path,
+            history: History::default(),
+            selection: Selection::default(),
+        }
+    }
+
+    pub fn title(&self) -> String {
+        match self.path.file_name() {
+            Some(name) => name.to_string_lossy().into_owned(),
+            None => self.path.display().to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TabSet {
+    pub tabs: Vec<Tab>,
+    pub active: usize,
+    next_id: u64,
+}
+
+impl TabSet {
+    pub fn active_tab(&self) -> &Tab {
+        &self.tabs[self.active]
+    }
+
+    fn active_tab_mut(&mut self) -> &mut Tab {
+        &mut self.tabs[self.active]
+    }
+
+    /// Adds a tab at `path` right after the active one and returns its index.
+    fn insert(&mut self, path: PathBuf) -> usize {
+        let tab = Tab::new(self.next_id, path);
+        self.next_id += 1;
+        let index = (self.active + 1).min(self.tabs.len());
+        self.tabs.insert(index, tab);
+        index
+    }
+
+    /// Records that the active tab went to `path`.
+    fn navigated(&mut self, path: &Path) {
+        let tab = self.active_tab_mut();
+        if tab.path == path {
+            return;
+        }
+        let previous = std::mem::replace(&mut tab.path, path.to_path_buf());
+        tab.history.left(previous);
+    }
+
+    /// Makes the saved tabs usable again: at least one tab, a valid active
+    /// index, and every location still existing.
+    fn restored(mut self, fallback: &Path) -> Self {
+        for tab in &mut self.tabs {
+            if !tab.path.is_dir() {
+                tab.path = existing_ancestor(&tab.path).unwrap_or_else(|| fallback.to_path_buf());
+            }
+        }
+        if self.tabs.is_empty() {
+            self.active = 0;
+            self.insert(fallback.to_path_buf());
+        }
+        self.active = self.active.min(self.tabs.len() - 1);
+        self.next_id = self.tabs.iter().map(|tab| tab.id + 1).max().unwrap_or_default();
+        self
+    }
+
+    /// A copy the settings file can hold: locations that aren't UTF-8 become
+    /// their nearest ancestor that is, or drop out of the history.
+    fn savable(&self) -> TabSet {
+        let mut set = self.clone();
+        for tab in &mut set.tabs {
+            if tab.path.to_str().is_none() {
+                let ancestor = tab.path.ancestors().find(|ancestor| ancestor.to_str().is_some());
+                tab.path = ancestor.map(Path::to_path_buf).unwrap_or_default();
+            }
+            tab.history.retain(|path| path.to_str().is_some());
+        }
+        set
+    }
+}
+
+fn existing_ancestor(path: &Path) -> Option<PathBuf> {
+    path.ancestors().find(|ancestor| ancestor.is_dir()).map(Path::to_path_buf)
+}
+
+/// The open tabs, provided as context by [`use_tabs_provider`].
+#[derive(Clone, Copy)]
+pub struct Tabs {
+    set: Signal<TabSet>,
+    path: Signal<PathBuf>,
+    selection: Signal<Selection>,
+}
+
+impl Tabs {
+    pub fn list(&self) -> Signal<TabSet> {
+        self.set
+    }
+
+    /// Switches to the tab at `index`.
+    pub fn activate(&self, index: usize) {
+        let (mut set, mut path, mut selection) = (self.set, self.path, self.selection);
+        if index >= set.peek().tabs.len() || index == set.peek().active {
+            return;
+        }
+        {
+            let mut set = set.write();
+            let leaving = set.active_tab_mut();
+            leaving.selection = selection.peek().clone();
+            set.active = index;
+        }
+        let tab = set.peek().active_tab().clone();
+        selection.set(tab.selection);
+        path.set(tab.path);
+        self.save();
+    }
+
+    /// Opens `path` in a new tab next to the active one, switching to it when
+    /// `activate` is set.
+    pub fn open(&self, path: PathBuf, activate: bool) {
+        let mut set = self.set;
+        let index = set.write().insert(path);
+        if activate {
+            self.activate(index);
+        } else {
+            self.save();
+        }
+    }
+
+    /// Closes the tab at `index`; the last tab stays open.
+    pub fn close(&self, index: usize) {
+        let mut set = self.set;
+        let (count, active) = (set.peek().tabs.len(), set.peek().active);
+        if count <= 1 || index >= count {
+            return;
+        }
+        if index == active {
+            // Move to a neighbour first so its location and selection load
+            self.activate(if index + 1 < count { index + 1 } else { index - 1 });
+        }
+        let mut tabs = set.write();
+        tabs.tabs.remove(index);
+        if index < tabs.active {
+            tabs.active -= 1;
+        }
+        drop(tabs);
+        self.save();
+    }
+
+    /// Moves the tab at `from` to position `to`, keeping the same tab active.
+    pub fn reorder(&self, from: usize, to: usize) {
+        let mut set = self.set;
+        let mut tabs = set.write();
+        if from >= tabs.tabs.len() || to >= tabs.tabs.len() || from == to {
+            return;
+        }
+        let active_id = tabs.active_tab().id;
+        let tab = tabs.tabs.remove(from);
+        tabs.tabs.insert(to, tab);
+        tabs.active = tabs.tabs.iter().position(|tab| tab.id == active_id).unwrap_or_default();
+        drop(tabs);
+        self.save();
+    }
+
+    /// Goes back in the active tab's history.
+    pub fn go_back(&self) {
+        self.travel(History::go_back);
+    }
+
+    /// Goes forward in the active tab's history.
+    pub fn go_forward(&self) {
+        self.travel(History::go_forward);
+    }
+
+    fn travel(&self, step: fn(&mut History, &Path) -> Option<PathBuf>) {
+        let (mut set, mut path) = (self.set, self.path);
+        let target = {
+            let mut set = set.write();
+            let tab = set.active_tab_mut();
+            let Some(target) = step(&mut tab.history, &tab.path) else {
+                return;
+            };
+            // Moving the tab first keeps the navigation effect from
+            // recording this as a new visit
+            tab.path = target.clone();
+            target
+        };
+        path.set(target);
+        self.save();
+    }
+
+    fn save(&self) {
+        config::save_toml_later(TABS_FILE, &self.set.peek().savable());
+    }
+}
+
+/// Restores the tabs of the last session, or opens one at `root`, and provides
+/// [`Tabs`], [`CurrentPath`] and [`CurrentSelection`] for the active tab.
+pub fn use_tabs_provider(root: PathBuf) {
+    let set = use_hook(|| Signal::new(config::load_toml::<TabSet>(TABS_FILE).unwrap_or_default().restored(&root)));
+    let CurrentPath(path) = use_context_provider(|| CurrentPath(Signal::new(set.peek().active_tab().path.clone())));
+    let CurrentSelection(selection) = use_context_provider(|| CurrentSelection(Signal::new(Selection::default())));
+    let tabs = use_context_provider(|| Tabs { set, path, selection });
+
+    // Wherever the active tab goes becomes part of its history
+    use_effect(move || {
+        let path = path();
+        let mut set = tabs.set;
+        if set.peek().active_tab().path != path {
+            set.write().navigated(&path);
+            tabs.save();
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    #[test]
+    fn tab_set_round_trips_through_the_settings_file() {
+        let mut set = TabSet::default().restored(Path::new("/"));
+        set.navigated(Path::new("/tmp"));
+        set.insert(PathBuf::from("/usr"));
+        let text = toml::to_string_pretty(&set.savable()).unwrap();
+        assert_eq!(toml::from_str::<TabSet>(&text).unwrap(), set);
+    }
+
+    #[test]
+    fn older_tab_files_load_with_defaults() {
+        let set: TabSet = toml::from_str("[[tabs]]\nid = 3\npath = \"/tmp\"\n").unwrap();
+        assert_eq!(set.tabs[0].path, Path::new("/tmp"));
+        assert!(!set.tabs[0].history.can_go_back());
+        assert_eq!(set.restored(Path::new("/")).next_id, 4);
+    }
+
+    #[test]
+    fn savable_tabs_leave_out_paths_that_are_not_utf8() {
+        let odd = Path::new("/tmp").join(OsStr::from_bytes(b"caf\xe9"));
+        let mut set = TabSet::default().restored(Path::new("/"));
+        set.navigated(&odd);
+        set.navigated(Path::new("/usr"));
+
+        let saved = set.savable();
+        assert!(toml::to_string(&saved).is_ok());
+        let mut history = saved.active_tab().history.clone();
+        assert_eq!(history.go_back(Path::new("/usr")), Some(PathBuf::from("/")));
+
+        set.navigated(&odd);
+        assert_eq!(set.savable().active_tab().path, Path::new("/tmp"));
+    }
+}
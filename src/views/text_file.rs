@@ -0,0 +1,351 @@
+//! Reading and writing text files for the editor, keeping their encoding and
+//! line endings as they were.
+//!
+//! Saving never writes into the original file: the new contents go to a
+//! temporary file next to it, which then replaces the original in one rename,
+//! so a crash halfway leaves either the old file or the new one.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Files larger than this are not opened in the editor.
+pub const MAX_EDIT_SIZE: u64 = 4 * 1024 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Encoding {
+    Utf8,
+    /// UTF-8 starting with a byte order mark
+    Utf8Bom,
+    Utf16Le,
+    Utf16Be,
+    /// ISO 8859-1, the fallback for bytes that aren't valid UTF-8
+    Latin1,
+}
+
+impl Encoding {
+    pub const ALL: [Encoding; 5] = [
+        Encoding::Utf8,
+        Encoding::Utf8Bom,
+        Encoding::Utf16Le,
+        Encoding::Utf16Be,
+        Encoding::Latin1,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf8Bom => "UTF-8 with BOM",
+            Encoding::Utf16Le => "UTF-16 LE",
+            Encoding::Utf16Be => "UTF-16 BE",
+            Encoding::Latin1 => "ISO 8859-1",
+        }
+    }
+
+    fn decode(self, bytes: &[u8]) -> Option<String> {
+        match self {
+            Encoding::Utf8 => String::from_utf8(bytes.to_vec()).ok(),
+            Encoding::Utf8Bom => String::from_utf8(bytes.strip_prefix(b"\xEF\xBB\xBF")?.to_vec()).ok(),
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                let body = bytes.get(2..)?;
+                if body.len() % 2 != 0 {
+                    return None;
+                }
+                let units = body.chunks(2).map(|pair| {
+                    let pair = [pair[0], pair[1]];
+                    if self == Encoding::Utf16Le {
+                        u16::from_le_bytes(pair)
+                    } else {
+                        u16::from_be_bytes(pair)
+                    }
+                });
+                char::decode_utf16(units).collect::<Result<String, _>>().ok()
+            }
+            Encoding::Latin1 => Some(bytes.iter().map(|&byte| byte as char).collect()),
+        }
+    }
+
+    fn encode(self, text: &str) -> Result<Vec<u8>, String> {
+        match self {
+            Encoding::Utf8 => Ok(text.as_bytes().to_vec()),
+            Encoding::Utf8Bom => Ok([b"\xEF\xBB\xBF".as_slice(), text.as_bytes()].concat()),
+            Encoding::Utf16Le => Ok([0xFF, 0xFE].into_iter().chain(text.encode_utf16().flat_map(u16::to_le_bytes)).collect()),
+            Encoding::Utf16Be => Ok([0xFE, 0xFF].into_iter().chain(text.encode_utf16().flat_map(u16::to_be_bytes)).collect()),
+            Encoding::Latin1 => text
+                .chars()
+                .map(|c| u8::try_from(c).map_err(|_| format!("\"{c}\" can't be written as {}", self.label())))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineEnding {
+    Lf,
+    CrLf,
+    Cr,
+}
+
+impl LineEnding {
+    pub const ALL: [LineEnding; 3] = [LineEnding::Lf, LineEnding::CrLf, LineEnding::Cr];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "LF (Unix)",
+            LineEnding::CrLf => "CRLF (Windows)",
+            LineEnding::Cr => "CR (classic Mac)",
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+            LineEnding::Cr => "\r",
+        }
+    }
+}
+
+/// A text file as the editor holds it. `text` always uses `\n`; the file's
+/// own line endings come back on save.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextFile {
+    pub path: PathBuf,
+    pub text: String,
+    pub encoding: Encoding,
+    pub line_ending: LineEnding,
+    /// The file used more than one kind of line ending
+    pub mixed_endings: bool,
+    /// Modification time and size when read or last saved, to notice other
+    /// programs changing the file under us
+    stamp: (Option<SystemTime>, u64),
+}
+
+impl TextFile {
+    /// Reads the file at `path`, working out its encoding and line endings.
+    pub fn open(path: &Path) -> Result<TextFile, String> {
+        let metadata = fs::metadata(path).map_err(|err| err.to_string())?;
+        if metadata.is_dir() {
+            return Err("it is a folder".to_string());
+        }
+        if metadata.len() > MAX_EDIT_SIZE {
+            return Err(format!("it is larger than {} MB", MAX_EDIT_SIZE / (1024 * 1024)));
+        }
+        let bytes = fs::read(path).map_err(|err| err.to_string())?;
+        let (text, encoding) = decode(&bytes).ok_or("it doesn't look like a text file")?;
+        let (line_ending, mixed_endings) = detect_line_ending(&text);
+
+        Ok(TextFile {
+            path: path.to_path_buf(),
+            text: normalize_line_endings(&text),
+            encoding,
+            line_ending,
+            mixed_endings,
+            stamp: stamp(&metadata),
+        })
+    }
+
+    /// Whether another program changed the file since it was read or saved.
+    pub fn changed_on_disk(&self) -> bool {
+        fs::metadata(&self.path).is_ok_and(|metadata| stamp(&metadata) != self.stamp)
+    }
+
+    /// Writes `text` to the file in its encoding and line endings, atomically.
+    pub fn save(&mut self, text: &str) -> Result<(), String> {
+        let text = normalize_line_endings(text);
+        let bytes = self.encoding.encode(&text.replace('\n', self.line_ending.as_str()))?;
+        write_atomically(&self.path, &bytes).map_err(|err| err.to_string())?;
+        self.text = text;
+        self.mixed_endings = false;
+        if let Ok(metadata) = fs::metadata(&self.path) {
+            self.stamp = stamp(&metadata);
+        }
+        Ok(())
+    }
+}
+
+fn stamp(metadata: &fs::Metadata) -> (Option<SystemTime>, u64) {
+    (metadata.modified().ok(), metadata.len())
+}
+
+/// Picks the encoding from a byte order mark, then tries UTF-8, then falls
+/// back to Latin-1. Text with NUL bytes is taken for binary.
+fn decode(bytes: &[u8]) -> Option<(String, Encoding)> {
+    let guess = if bytes.starts_with(b"\xEF\xBB\xBF") {
+        Encoding::Utf8Bom
+    } else if bytes.starts_with(&[0xFF, 0xFE]) {
+        Encoding::Utf16Le
+    } else if bytes.starts_with(&[0xFE, 0xFF]) {
+        Encoding::Utf16Be
+    } else if bytes.contains(&0) {
+        return None;
+    } else if std::str::from_utf8(bytes).is_ok() {
+        Encoding::Utf8
+    } else {
+        Encoding::Latin1
+    };
+    let text = guess.decode(bytes)?;
+    if text.contains('\0') {
+        return None;
+    }
+    Some((text, guess))
+}
+
+/// The most common line ending in `text`, and whether others appear too.
+fn detect_line_ending(text: &str) -> (LineEnding, bool) {
+    let crlf = text.matches("\r\n").count();
+    let cr = text.matches('\r').count() - crlf;
+    let lf = text.matches('\n').count() - crlf;
+    let kinds = [crlf, cr, lf].iter().filter(|&&count| count > 0).count();
+    let ending = if crlf > lf && crlf >= cr {
+        LineEnding::CrLf
+    } else if cr > lf && cr > crlf {
+        LineEnding::Cr
+    } else {
+        LineEnding::Lf
+    };
+    (ending, kinds > 1)
+}
+
+fn normalize_line_endings(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+/// Replaces `path` with `bytes` through a temporary file in the same folder,
+/// keeping the original's permissions.
+fn write_atomically(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    // Replace what a symlink points at, not the link
+    let path = &fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let permissions = fs::metadata(path).ok().map(|metadata| metadata.permissions());
+
+    let (tmp, mut file) = (0..100)
+        .find_map(|attempt| {
+            let tmp = dir.join(format!(".{name}.{}.{attempt}.tmp", std::process::id()));
+            match OpenOptions::new().write(true).create_new(true).open(&tmp) {
+                Ok(file) => Some(Ok((tmp, file))),
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => None,
+                Err(err) => Some(Err(err)),
+            }
+        })
+        .unwrap_or_else(|| Err(io::Error::new(io::ErrorKind::AlreadyExists, "no free temporary name")))?;
+
+    let written = (|| {
+        file.write_all(bytes)?;
+        if let Some(permissions) = permissions {
+            file.set_permissions(permissions)?;
+        }
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    })();
+    if written.is_err() {
+        let _ = fs::remove_file(&tmp);
+        return written;
+    }
+    // Make the rename itself durable
+    File::open(dir)?.sync_all()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(case: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("text-file-{}-{case}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn byte_order_marks_pick_the_encoding() {
+        assert_eq!(decode(b"\xEF\xBB\xBFhi"), Some(("hi".to_string(), Encoding::Utf8Bom)));
+        assert_eq!(decode(b"\xFF\xFEh\0i\0"), Some(("hi".to_string(), Encoding::Utf16Le)));
+        assert_eq!(decode(b"\xFE\xFF\0h\0i"), Some(("hi".to_string(), Encoding::Utf16Be)));
+        // Half a UTF-16 code unit at the end
+        assert_eq!(decode(b"\xFF\xFEh\0i"), None);
+    }
+
+    #[test]
+    fn invalid_utf8_falls_back_to_latin1() {
+        assert_eq!(decode("grüße".as_bytes()), Some(("grüße".to_string(), Encoding::Utf8)));
+        assert_eq!(decode(b"gr\xFC\xDFe"), Some(("grüße".to_string(), Encoding::Latin1)));
+        assert_eq!(decode(b""), Some((String::new(), Encoding::Utf8)));
+    }
+
+    #[test]
+    fn nul_bytes_mean_binary() {
+        assert_eq!(decode(b"ELF\0\x01"), None);
+        assert_eq!(decode(b"\xEF\xBB\xBFa\0b"), None);
+        assert_eq!(decode(b"\xFF\xFEa\0\0\0"), None);
+    }
+
+    #[test]
+    fn the_most_common_line_ending_wins() {
+        assert_eq!(detect_line_ending("one line"), (LineEnding::Lf, false));
+        assert_eq!(detect_line_ending("a\r\nb\r\n"), (LineEnding::CrLf, false));
+        assert_eq!(detect_line_ending("a\rb\r"), (LineEnding::Cr, false));
+        assert_eq!(detect_line_ending("a\r\nb\r\nc\n"), (LineEnding::CrLf, true));
+        assert_eq!(detect_line_ending("a\rb\rc\r\nd\n"), (LineEnding::Cr, true));
+    }
+
+    #[test]
+    fn ties_go_to_lf_then_crlf() {
+        assert_eq!(detect_line_ending("a\r\nb\n"), (LineEnding::Lf, true));
+        assert_eq!(detect_line_ending("a\rb\n"), (LineEnding::Lf, true));
+        assert_eq!(detect_line_ending("a\r\nb\r"), (LineEnding::CrLf, true));
+    }
+
+    #[test]
+    fn encodings_round_trip() {
+        let text = "naïve café\nline two";
+        for encoding in Encoding::ALL {
+            let bytes = encoding.encode(text).unwrap();
+            assert_eq!(encoding.decode(&bytes).as_deref(), Some(text), "{}", encoding.label());
+            assert_eq!(decode(&bytes), Some((text.to_string(), encoding)), "{}", encoding.label());
+        }
+    }
+
+    #[test]
+    fn latin1_refuses_what_it_cannot_hold() {
+        assert!(Encoding::Latin1.encode("price: 5€").is_err());
+        assert!(Encoding::Utf16Le.encode("price: 5€").is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn saving_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = scratch("permissions");
+        let path = dir.join("script.sh");
+        fs::write(&path, "echo old\r\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o750)).unwrap();
+
+        let mut file = TextFile::open(&path).unwrap();
+        file.save("echo new\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "echo new\r\n");
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o750);
+        assert!(!file.changed_on_disk());
+        // Nothing left behind but the file itself
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn saving_through_a_symlink_replaces_its_target() {
+        let dir = scratch("symlink");
+        fs::write(dir.join("real.txt"), "old").unwrap();
+        std::os::unix::fs::symlink("real.txt", dir.join("link.txt")).unwrap();
+
+        write_atomically(&dir.join("link.txt"), b"new").unwrap();
+        assert!(fs::symlink_metadata(dir.join("link.txt")).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read_to_string(dir.join("real.txt")).unwrap(), "new");
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
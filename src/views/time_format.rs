@@ -0,0 +1,155 @@
+//! Display formats for file timestamps, always in the local time zone.
+
+use chrono::{DateTime, Local, Locale, SecondsFormat};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use std::time::SystemTime;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum TimeFormat {
+    /// "3 days ago"
+    #[default]
+    Relative,
+    /// `2024-05-01 14:03:22`
+    Iso,
+    /// Date and time as the user's locale writes them
+    Locale,
+}
+
+impl TimeFormat {
+    pub const ALL: [TimeFormat; 3] = [TimeFormat::Relative, TimeFormat::Iso, TimeFormat::Locale];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TimeFormat::Relative => "Relative",
+            TimeFormat::Iso => "ISO 8601",
+            TimeFormat::Locale => "Locale",
+        }
+    }
+
+    pub fn format(self, time: SystemTime) -> String {
+        let time = DateTime::<Local>::from(time);
+        match self {
+            TimeFormat::Relative => relative(time, Local::now()),
+            TimeFormat::Iso => time.format("%Y-%m-%d %H:%M:%S").to_string(),
+            TimeFormat::Locale => time.format_localized("%x %X", locale()).to_string(),
+        }
+    }
+}
+
+/// Everything known about `time`, for tooltips: the weekday and date in the
+/// user's locale plus the exact ISO 8601 timestamp with its UTC offset.
+pub fn format_full(time: SystemTime) -> String {
+    let time = DateTime::<Local>::from(time);
+    format!(
+        "{} · {}",
+        time.format_localized("%A %x %X", locale()),
+        time.to_rfc3339_opts(SecondsFormat::AutoSi, false)
+    )
+}
+
+/// "5 minutes ago", "in 2 hours", and so on, counted from `now`.
+fn relative(time: DateTime<Local>, now: DateTime<Local>) -> String {
+    let seconds = now.signed_duration_since(time).num_seconds();
+    let future = seconds < 0;
+    let seconds = seconds.unsigned_abs();
+    if seconds < 60 {
+        return "Just now".to_string();
+    }
+
+    let (amount, unit) = match seconds {
+        s if s < 3_600 => (s / 60, "minute"),
+        s if s < 86_400 => (s / 3_600, "hour"),
+        s if s < 7 * 86_400 => (s / 86_400, "day"),
+        s if s < 30 * 86_400 => (s / (7 * 86_400), "week"),
+        s if s < 365 * 86_400 => (s / (30 * 86_400), "month"),
+        s => (s / (365 * 86_400), "year"),
+    };
+    let plural = if amount == 1 { "" } else { "s" };
+    if future {
+        format!("in {amount} {unit}{plural}")
+    } else {
+        format!("{amount} {unit}{plural} ago")
+    }
+}
+
+/// The locale for dates, from `LC_ALL`, `LC_TIME` or `LANG` like libc picks
+/// it, defaulting to POSIX.
+fn locale() -> Locale {
+    static LOCALE: OnceLock<Locale> = OnceLock::new();
+    *LOCALE.get_or_init(|| locale_from(|var| std::env::var(var).ok()))
+}
+
+/// [`locale`], looking the variables up with `var`.
+fn locale_from(var: impl Fn(&str) -> Option<String>) -> Locale {
+    ["LC_ALL", "LC_TIME", "LANG"]
+        .into_iter()
+        .filter_map(var)
+        .find(|value| !value.is_empty())
+        .and_then(|value| {
+            // `de_DE.UTF-8@euro` names the locale `de_DE`
+            let name = value.split(['.', '@']).next().unwrap_or_default();
+            Locale::try_from(name).ok()
+        })
+        .unwrap_or(Locale::POSIX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn ago(seconds: i64) -> String {
+        let now = Local.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        relative(now - Duration::seconds(seconds), now)
+    }
+
+    #[test]
+    fn the_last_minute_is_just_now() {
+        assert_eq!(ago(0), "Just now");
+        assert_eq!(ago(59), "Just now");
+        assert_eq!(ago(-59), "Just now");
+        assert_eq!(ago(60), "1 minute ago");
+    }
+
+    #[test]
+    fn each_unit_starts_at_one() {
+        assert_eq!(ago(119), "1 minute ago");
+        assert_eq!(ago(120), "2 minutes ago");
+        assert_eq!(ago(3_599), "59 minutes ago");
+        assert_eq!(ago(3_600), "1 hour ago");
+        assert_eq!(ago(86_399), "23 hours ago");
+        assert_eq!(ago(86_400), "1 day ago");
+        assert_eq!(ago(7 * 86_400 - 1), "6 days ago");
+        assert_eq!(ago(7 * 86_400), "1 week ago");
+        assert_eq!(ago(30 * 86_400), "1 month ago");
+        assert_eq!(ago(365 * 86_400), "1 year ago");
+        assert_eq!(ago(3 * 365 * 86_400), "3 years ago");
+    }
+
+    #[test]
+    fn future_times_count_forward() {
+        assert_eq!(ago(-60), "in 1 minute");
+        assert_eq!(ago(-5 * 3_600), "in 5 hours");
+        assert_eq!(ago(-2 * 86_400), "in 2 days");
+    }
+
+    #[test]
+    fn locale_comes_from_the_first_variable_set() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| vars.iter().find(|(var, _)| *var == name).map(|(_, value)| value.to_string())
+        };
+        assert_eq!(locale_from(env(&[("LANG", "de_DE.UTF-8")])), Locale::de_DE);
+        assert_eq!(locale_from(env(&[("LANG", "de_DE.UTF-8@euro"), ("LC_TIME", "fr_FR")])), Locale::fr_FR);
+        assert_eq!(locale_from(env(&[("LC_ALL", "ja_JP"), ("LC_TIME", "fr_FR")])), Locale::ja_JP);
+        // Empty variables are skipped, like libc does
+        assert_eq!(locale_from(env(&[("LC_ALL", ""), ("LANG", "nl_NL@euro")])), Locale::nl_NL);
+    }
+
+    #[test]
+    fn unknown_locales_fall_back_to_posix() {
+        assert_eq!(locale_from(|_| None), Locale::POSIX);
+        assert_eq!(locale_from(|_| Some("C.UTF-8".to_string())), Locale::POSIX);
+        assert_eq!(locale_from(|_| Some("xx_YY".to_string())), Locale::POSIX);
+    }
+}
@@ -0,0 +1,169 @@
+use crate::views::operations::trash::{self, TrashedItem};
+use crate::views::operations::{JobKind, JobQueue, JobRequest};
+use dioxus::prelude::*;
+use std::path::PathBuf;
+
+/// Lists the contents of the trash and lets items be restored or deleted for good.
+#[component]
+pub fn Trash() -> Element {
+    let jobs = use_context::<JobQueue>();
+    let mut items = use_signal(trash::list);
+    let mut message = use_signal(|| None::<String>);
+    let mut confirm_empty = use_signal(|| false);
+    // The entry, by its place in the trash, whose deletion awaits confirmation
+    let mut confirm_delete = use_signal(|| None::<PathBuf>);
+
+    // Reload whenever a job finishes, since trash, delete and empty jobs all
+    // change what is in the trash
+    let finished_jobs = use_memo({
+        let jobs = jobs.clone();
+        move || jobs.jobs().read().iter().filter(|job| job.state.is_finished()).count()
+    });
+    use_effect(move || {
+        finished_jobs();
+        items.set(trash::list());
+    });
+
+    let delete_items = {
+        let jobs = jobs.clone();
+        move |doomed: Vec<TrashedItem>| {
+            let sources = doomed
+                .iter()
+                .flat_map(|item| [item.file_path(), item.info_path()])
+                .collect();
+            jobs.submit(JobRequest {
+                kind: JobKind::Delete,
+                sources,
+                destination: None,
+            });
+        }
+    };
+
+    let mut restore = move |item: TrashedItem| {
+        match trash::restore(&item) {
+            Ok(target) => message.set(Some(format!("Restored {}", target.display()))),
+            Err(err) => message.set(Some(format!("Could not restore {}: {err}", item.original_path.display()))),
+        }
+        items.set(trash::list());
+    };
+
+    rsx! {
+        div { class: "min-h-screen bg-gray-50 p-6",
+            div { class: "max-w-6xl mx-auto",
+                div { class: "mb-8 flex items-end justify-between",
+                    div {
+                        h1 { class: "text-3xl font-bold text-gray-900 mb-2", "Trash" }
+                        p { class: "text-gray-600", "{items.read().len()} items" }
+                    }
+                    button {
+                        class: "px-3 py-1 rounded-md border bg-white text-red-600 hover:bg-red-50 transition-colors disabled:opacity-50 disabled:cursor-not-allowed",
+                        disabled: items.read().is_empty(),
+                        onclick: move |_| confirm_empty.set(true),
+                        "Empty trash"
+                    }
+                }
+                if confirm_empty() && !items.read().is_empty() {
+                    div { class: "flex items-center justify-between gap-4 mb-2 p-3 text-sm bg-red-50 border border-red-200 rounded-md",
+                        span { class: "text-red-800",
+                            "Permanently delete all {items.read().len()} items in the trash? This cannot be undone."
+                        }
+                        div { class: "flex gap-2",
+                            button {
+                                class: "px-3 py-1 rounded-md bg-red-600 text-white hover:bg-red-700 transition-colors",
+                                onclick: {
+                                    let delete_items = delete_items.clone();
+                                    move |_| {
+                                        delete_items(items());
+                                        confirm_empty.set(false);
+                                    }
+                                },
+                                "Empty trash"
+                            }
+                            button {
+                                class: "px-3 py-1 rounded-md border bg-white text-gray-700 hover:bg-gray-50 transition-colors",
+                                onclick: move |_| confirm_empty.set(false),
+                                "Cancel"
+                            }
+                        }
+                    }
+                }
+                if let Some(text) = message() {
+                    div { class: "flex items-center justify-between mb-2 p-3 text-sm bg-blue-50 border border-blue-200 rounded-md",
+                        span { class: "text-blue-800 break-all", "{text}" }
+                        button {
+                            class: "text-blue-600 hover:text-blue-800",
+                            onclick: move |_| message.set(None),
+                            "✕"
+                        }
+                    }
+                }
+                div { class: "bg-white rounded-lg shadow-sm border overflow-hidden",
+                    if items.read().is_empty() {
+                        div { class: "p-8 text-center text-gray-500", "The trash is empty" }
+                    } else {
+                        div { class: "divide-y",
+                            for item in items.read().iter().cloned() {
+                                div {
+                                    key: "{item.file_path().display()}",
+                                    class: "flex items-center gap-4 px-4 py-3 hover:bg-gray-50",
+                                    span { class: "text-xl", if item.is_dir { "📁" } else { "📄" } }
+                                    div { class: "flex-1 min-w-0",
+                                        p { class: "text-gray-900 truncate", "{item.name}" }
+                                        p { class: "text-xs text-gray-500 truncate",
+                                            "{item.original_path.display()}"
+                                        }
+                                    }
+                                    span { class: "text-sm text-gray-500 whitespace-nowrap",
+                                        "{format_deletion_date(&item.deleted_at)}"
+                                    }
+                                    if confirm_delete.read().as_ref() == Some(&item.file_path()) {
+                                        span { class: "text-sm text-red-800 whitespace-nowrap", "Delete for good? This cannot be undone." }
+                                        button {
+                                            class: "px-3 py-1 text-sm rounded-md bg-red-600 text-white hover:bg-red-700 transition-colors",
+                                            onclick: {
+                                                let delete_items = delete_items.clone();
+                                                let item = item.clone();
+                                                move |_| {
+                                                    delete_items(vec![item.clone()]);
+                                                    confirm_delete.set(None);
+                                                }
+                                            },
+                                            "Delete"
+                                        }
+                                        button {
+                                            class: "px-3 py-1 text-sm rounded-md border bg-white text-gray-700 hover:bg-gray-50 transition-colors",
+                                            onclick: move |_| confirm_delete.set(None),
+                                            "Cancel"
+                                        }
+                                    } else {
+                                        button {
+                                            class: "px-3 py-1 text-sm rounded-md border bg-white text-gray-700 hover:bg-gray-50 transition-colors",
+                                            onclick: {
+                                                let item = item.clone();
+                                                move |_| restore(item.clone())
+                                            },
+                                            "Restore"
+                                        }
+                                        button {
+                                            class: "px-3 py-1 text-sm rounded-md border bg-white text-red-600 hover:bg-red-50 transition-colors",
+                                            onclick: {
+                                                let file_path = item.file_path();
+                                                move |_| confirm_delete.set(Some(file_path.clone()))
+                                            },
+                                            "Delete permanently"
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// `DeletionDate` is `YYYY-MM-DDThh:mm:ss`; show it without the `T`.
+fn format_deletion_date(date: &str) -> String {
+    date.replacen('T', " ", 1)
+}